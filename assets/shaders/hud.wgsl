@@ -2,11 +2,13 @@
 struct VertexInput {
     @location(0) position: vec2<f32>,
     @location(1) uv: vec2<f32>,
+    @location(2) color: vec4<f32>,
 };
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
 };
 
 @group(0) @binding(0) var texture_sampler: sampler;
@@ -19,6 +21,7 @@ fn vs_main(
     var out: VertexOutput;
     out.clip_position = vec4<f32>(model.position, 0.0, 1.0);
     out.uv = model.uv;
+    out.color = model.color;
     return out;
 }
 
@@ -26,5 +29,5 @@ fn vs_main(
 fn fs_main(
     in: VertexOutput,
 ) -> @location(0) vec4<f32> {
-    return textureSample(texture_atlas, texture_sampler, in.uv);
+    return textureSample(texture_atlas, texture_sampler, in.uv) * in.color;
 }
\ No newline at end of file
@@ -0,0 +1,39 @@
+// Vertex shader
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+    focus_off: vec4<i32>,
+    cam_pos: vec4<f32>,
+    fog_color: vec4<f32>,
+    fog_range: vec4<f32>,
+    light: vec4<f32>,
+};
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+struct VertexInput {
+    // relative to camera.focus_off
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(
+    vertex: VertexInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.color = vertex.color;
+    out.clip_position = camera.view_proj * vec4<f32>(vertex.position, 1.0);
+    return out;
+}
+
+// Fragment shader
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
@@ -1,27 +1,75 @@
 // Vertex shader
 struct CameraUniform {
     view_proj: mat4x4<f32>,
+    // world origin used for rendering (camera relative positions)
+    focus_off: vec4<i32>,
+    // camera position relative to focus_off
+    cam_pos: vec4<f32>,
+    fog_color: vec4<f32>,
+    // x: fog start, y: fog end
+    fog_range: vec4<f32>,
+    // x: daylight
+    light: vec4<f32>,
 };
 @group(1) @binding(0) // 1.
 var<uniform> camera: CameraUniform;
 
+struct Locals {
+    // xyz: world position of the chunk corner, in blocks
+    // w: horizontal size of a mesh cell in blocks (LOD meshes)
+    origin: vec4<i32>,
+};
+@group(2) @binding(0)
+var<uniform> locals: Locals;
+
+// see `BlockVertex` for the bit layout
 struct VertexInput {
-    @location(0) position: vec3<f32>,
-    @location(1) tex_coords: vec2<f32>,
+    @location(0) data: u32,
+    @location(1) tile: u32,
 }
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) tex_coords: vec2<f32>,
+    @location(1) shade: f32,
+    @location(2) view_distance: f32,
 }
 
+const ATLAS_TILES_PER_ROW: u32 = 16u;
+const TILE_SIZE: f32 = 16.0;
+const ATLAS_SIZE: f32 = 256.0;
+
+// TOP, BOTTOM, RIGHT, LEFT, FRONT, BACK
+const FACE_SHADE = array<f32, 6>(1.0, 0.5, 0.8, 0.8, 0.65, 0.65);
+const AO_CURVE = array<f32, 4>(0.45, 0.65, 0.85, 1.0);
+
 @vertex
 fn vs_main(
     vertex: VertexInput,
 ) -> VertexOutput {
+    let scale = f32(locals.origin.w);
+    let local_pos = vec3<f32>(
+        f32(vertex.data & 0x1Fu) * scale,
+        f32((vertex.data >> 5u) & 0x7Fu),
+        f32((vertex.data >> 12u) & 0x1Fu) * scale,
+    );
+    let face = (vertex.data >> 17u) & 0x7u;
+    let corner = (vertex.data >> 20u) & 0x3u;
+    let ao = (vertex.data >> 22u) & 0x3u;
+    let light = (vertex.data >> 24u) & 0xFu;
+
+    let tile = vec2<f32>(f32(vertex.tile % ATLAS_TILES_PER_ROW), f32(vertex.tile / ATLAS_TILES_PER_ROW));
+    let corner_offset = vec2<f32>(f32(corner & 1u) * (TILE_SIZE - 1.0), f32(corner >> 1u) * TILE_SIZE);
+
+    // integer subtraction first so big world coordinates don't lose precision
+    let origin = vec3<f32>(locals.origin.xyz - camera.focus_off.xyz);
+
     var out: VertexOutput;
-    out.tex_coords = vertex.tex_coords;
-    out.clip_position = camera.view_proj * vec4<f32>(vertex.position, 1.0);
+    out.tex_coords = (tile * TILE_SIZE + corner_offset) / ATLAS_SIZE;
+    out.shade = FACE_SHADE[face] * AO_CURVE[ao] * (f32(light) / 15.0);
+    let position = origin + local_pos;
+    out.view_distance = distance(position, camera.cam_pos.xyz);
+    out.clip_position = camera.view_proj * vec4<f32>(position, 1.0);
     return out;
 }
 // Fragment shader
@@ -33,6 +81,7 @@ var s_diffuse: sampler;
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return textureSample(t_diffuse, s_diffuse, in.tex_coords);
+    let color = textureSample(t_diffuse, s_diffuse, in.tex_coords);
+    let fog = smoothstep(camera.fog_range.x, camera.fog_range.y, in.view_distance);
+    return vec4<f32>(mix(color.rgb * in.shade * camera.light.x, camera.fog_color.rgb, fog), color.a);
 }
- 
\ No newline at end of file
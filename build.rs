@@ -1,7 +1,7 @@
 use anyhow::*;
 use fs_extra::copy_items;
 use fs_extra::dir::CopyOptions;
-use std::env;
+use std::{env, fs, path::Path};
 
 fn main() -> Result<()> {
     // This tells Cargo to rerun this script if something in /res/ changes.
@@ -12,8 +12,35 @@ fn main() -> Result<()> {
     copy_options.overwrite = true;
     let mut paths_to_copy = Vec::new();
     paths_to_copy.push("assets/");
-    copy_items(&paths_to_copy, out_dir, &copy_options)?;
+    copy_items(&paths_to_copy, &out_dir, &copy_options)?;
 
+    embed_recipes(&out_dir)?;
+
+    Ok(())
+}
+
+/// Writes `recipes.rs`, a list of every recipe file with its contents embedded, so the
+/// binary doesn't depend on where it was built
+fn embed_recipes(out_dir: &str) -> Result<()> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/recipes");
+    println!("cargo:rerun-if-changed={}", dir.display());
+
+    let mut paths = fs::read_dir(&dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    paths.retain(|path| path.extension().is_some_and(|ext| ext == "json"));
+    // mismo orden en todas las plataformas, la primera receta que encaja gana
+    paths.sort();
+
+    let mut code = String::from("&[\n");
+    for path in &paths {
+        println!("cargo:rerun-if-changed={}", path.display());
+        let name = path.file_name().context("recipe without a name")?.to_string_lossy();
+        code += &format!("    ({:?}, include_str!({:?})),\n", name, path.display().to_string());
+    }
+    code += "]\n";
+
+    fs::write(Path::new(out_dir).join("recipes.rs"), code)?;
     Ok(())
 }
 
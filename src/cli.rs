@@ -0,0 +1,211 @@
+use std::path::{Path, PathBuf};
+
+use clap::{Parser, Subcommand};
+use log::LevelFilter;
+
+use crate::{settings::{self, Overrides, Settings, MAX_RENDER_DISTANCE, MIN_RENDER_DISTANCE}, world::{self, WorldInfo, WORLDS_DIR}};
+
+
+/// Biggest `generate --radius`, every chunk of the region is kept in memory with its mesh
+pub const MAX_TOOL_RADIUS: u32 = 16;
+
+
+/// Options of the game. Values given here win over the ones in the settings file for
+/// this run, the file keeps its own.
+#[derive(Parser, Debug)]
+#[command(name = "wgpucraft", version, about = "Voxel sandbox game made with wgpu")]
+pub struct Cli {
+    /// Folder of a world to play right away, created if it doesn't exist
+    #[arg(long, value_name = "DIR", global = true)]
+    pub world: Option<PathBuf>,
+    /// Seed of the new world, numbers are used as they are and text is hashed
+    #[arg(long, global = true)]
+    pub seed: Option<String>,
+    /// Chunks from the player to the edge of the full detail terrain
+    #[arg(long, value_name = "CHUNKS")]
+    pub render_distance: Option<usize>,
+    /// Settings file to use instead of the one in the config folder
+    #[arg(long, value_name = "FILE")]
+    pub settings: Option<PathBuf>,
+    /// Where the feet of the player start, instead of the saved position
+    #[arg(long, value_name = "X,Y,Z", value_parser = parse_spawn, allow_hyphen_values = true)]
+    pub spawn: Option<[f32; 3]>,
+    /// off, error, warn, info, debug or trace. Without it `RUST_LOG` is used.
+    #[arg(long, value_name = "LEVEL", global = true)]
+    pub log_level: Option<LevelFilter>,
+    /// Don't start the Tracy profiler client
+    #[arg(long, global = true)]
+    pub no_tracy: bool,
+    /// Borderless fullscreen on the current monitor
+    #[arg(long)]
+    pub fullscreen: bool,
+    #[command(subcommand)]
+    pub tool: Option<Tool>,
+}
+
+/// Tools that run without opening a window
+#[derive(Subcommand, Debug, PartialEq)]
+pub enum Tool {
+    /// Generates the chunks around a column, prints what they have and exits
+    Generate {
+        /// Column at the center of the region, in blocks
+        #[arg(long, value_name = "X,Z", value_parser = parse_column, default_value = "0,0", allow_hyphen_values = true)]
+        center: [i32; 2],
+        /// Chunks from the center chunk to the edge of the region
+        #[arg(long, value_name = "CHUNKS", default_value_t = 4, value_parser = clap::value_parser!(u32).range(0..=MAX_TOOL_RADIUS as i64))]
+        radius: u32,
+        /// Also writes the height of the surface as a grayscale PNG
+        #[arg(long, value_name = "FILE")]
+        heightmap: Option<PathBuf>,
+    },
+    /// Prints what is saved of a world, the one of --world if no folder is given
+    Info {
+        dir: Option<PathBuf>,
+    },
+}
+
+impl Cli {
+    /// `env_logger` at `--log-level`, or as `RUST_LOG` says
+    pub fn init_logging(&self) {
+        let mut builder = env_logger::Builder::from_default_env();
+        if let Some(level) = self.log_level {
+            builder.filter_level(level);
+        }
+        builder.init();
+    }
+
+    pub fn settings_path(&self) -> PathBuf {
+        self.settings.clone().unwrap_or_else(settings::settings_path)
+    }
+
+    /// Replaces the values of `settings` given in the command line, returns the ones of
+    /// the file so they are the ones saved
+    pub fn apply_to(&self, settings: &mut Settings) -> Overrides {
+        let graphics = &mut settings.graphics;
+        let mut overrides = Overrides::default();
+        if let Some(render_distance) = self.render_distance {
+            let render_distance = render_distance.clamp(MIN_RENDER_DISTANCE, MAX_RENDER_DISTANCE);
+            overrides.render_distance = Some(std::mem::replace(&mut graphics.render_distance, render_distance));
+        }
+        if self.fullscreen {
+            overrides.fullscreen = Some(std::mem::replace(&mut graphics.fullscreen, true));
+        }
+        overrides
+    }
+
+    /// `--seed`, a random one if it isn't given
+    pub fn seed(&self) -> u32 {
+        world::parse_seed(self.seed.as_deref().unwrap_or(""))
+    }
+
+    /// World to play without going through the menus: the one of `--world`, created
+    /// with `--seed` if there is none, or a new world in the worlds folder if only the
+    /// seed is given. `--spawn` moves the player of that world.
+    pub fn startup_world(&self) -> anyhow::Result<Option<WorldInfo>> {
+        let mut info = match &self.world {
+            Some(dir) if world::is_world(dir) => {
+                if self.seed.is_some() {
+                    log::warn!("{} already exists, --seed is ignored", dir.display());
+                }
+                WorldInfo::load(dir)?
+            }
+            Some(dir) => {
+                let name = dir.file_name().map_or("New world".into(), |name| name.to_string_lossy());
+                let info = WorldInfo::new(dir.clone(), &name, self.seed());
+                info.save()?;
+                info
+            }
+            None if self.seed.is_some() => {
+                let seed = self.seed();
+                world::create_world(Path::new(WORLDS_DIR), &format!("Seed {}", seed), seed)?
+            }
+            None => {
+                if self.spawn.is_some() {
+                    log::warn!("--spawn needs --world or --seed, it is ignored");
+                }
+                return Ok(None);
+            }
+        };
+        if let Some(spawn) = self.spawn {
+            info.player_position = Some(spawn);
+        }
+        Ok(Some(info))
+    }
+}
+
+
+fn parse_numbers<T: std::str::FromStr, const N: usize>(text: &str) -> Result<[T; N], String> {
+    let numbers: Vec<T> = text.split(',')
+        .map(|number| number.trim().parse().map_err(|_| format!("'{}' isn't a number", number.trim())))
+        .collect::<Result<_, _>>()?;
+    numbers.try_into().map_err(|_| format!("expected {} numbers separated by commas", N))
+}
+
+fn parse_spawn(text: &str) -> Result<[f32; 3], String> {
+    let spawn: [f32; 3] = parse_numbers(text)?;
+    if spawn.iter().any(|n| !n.is_finite()) {
+        return Err("coordinates must be finite".to_string());
+    }
+    Ok(spawn)
+}
+
+fn parse_column(text: &str) -> Result<[i32; 2], String> {
+    parse_numbers(text)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Cli, clap::Error> {
+        Cli::try_parse_from(std::iter::once("wgpucraft").chain(args.iter().copied()))
+    }
+
+    #[test]
+    fn valid_definition() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn overrides_settings() {
+        let cli = parse(&["--render-distance", "40", "--fullscreen", "--log-level", "debug"]).unwrap();
+        let mut settings = Settings::default();
+        let overrides = cli.apply_to(&mut settings);
+        assert_eq!(settings.graphics.render_distance, MAX_RENDER_DISTANCE);
+        assert!(settings.graphics.fullscreen);
+        assert_eq!(cli.log_level, Some(LevelFilter::Debug));
+
+        // al guardar vuelven los valores del archivo, salvo lo que el jugador cambió
+        let defaults = Settings::default().graphics;
+        settings.graphics.fov = 70.0;
+        let saved = overrides.file_settings(&settings).graphics;
+        assert_eq!((saved.render_distance, saved.fullscreen, saved.fov), (defaults.render_distance, false, 70.0));
+        let player_changed = Overrides { fullscreen: None, ..overrides };
+        assert!(player_changed.file_settings(&settings).graphics.fullscreen);
+
+        let mut settings = Settings::default();
+        assert_eq!(parse(&[]).unwrap().apply_to(&mut settings), Overrides::default());
+        assert_eq!(settings.graphics.render_distance, defaults.render_distance);
+        assert!(parse(&["--log-level", "loud"]).is_err());
+    }
+
+    #[test]
+    fn spawn_and_columns() {
+        assert_eq!(parse(&["--spawn", "-5,70.5, 3"]).unwrap().spawn, Some([-5.0, 70.5, 3.0]));
+        assert!(parse(&["--spawn", "1,2"]).is_err());
+        assert!(parse(&["--spawn", "1,x,3"]).is_err());
+        assert!(parse(&["--spawn", "1,inf,3"]).is_err());
+
+        let cli = parse(&["generate", "--center", "-32,16", "--seed", "7"]).unwrap();
+        assert_eq!(cli.tool, Some(Tool::Generate { center: [-32, 16], radius: 4, heightmap: None }));
+        assert_eq!(cli.seed(), 7);
+        let radius = |radius: &str| parse(&["generate", "--radius", radius]).map(|cli| cli.tool);
+        assert!(matches!(radius("16"), Ok(Some(Tool::Generate { radius: MAX_TOOL_RADIUS, .. }))));
+        assert!(radius("17").is_err());
+        assert!(radius("100000").is_err());
+        assert_eq!(parse(&["info", "worlds/test"]).unwrap().tool, Some(Tool::Info { dir: Some("worlds/test".into()) }));
+    }
+}
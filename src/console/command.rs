@@ -0,0 +1,489 @@
+use std::fmt;
+
+use cgmath::{Point3, Vector3};
+
+use crate::{player::GameMode, render::atlas::MaterialType, world::DAY_LENGTH};
+
+
+/// Blocks `/fill` changes at most, bigger boxes are refused
+pub const MAX_FILL_BLOCKS: usize = 32768;
+/// Named times of `/time set`, in ticks
+const TIME_NAMES: [(&str, u32); 4] = [("day", 1000), ("noon", 6000), ("night", 13000), ("midnight", 18000)];
+const GAME_MODES: [(&str, GameMode); 2] = [("survival", GameMode::Survival), ("creative", GameMode::Creative)];
+
+
+/// What commands can do to the game. `State` implements it, the tests use a fake world.
+pub trait CommandTarget {
+    /// Feet of the player, `~` coordinates are relative to it
+    fn player_position(&self) -> Point3<f32>;
+    fn teleport(&mut self, feet: Point3<f32>);
+    /// False if the block isn't loaded
+    fn set_block(&mut self, position: Vector3<i32>, material: MaterialType) -> bool;
+    /// Sets every loaded block between the two corners, both included. Returns how many.
+    fn fill(&mut self, min: Vector3<i32>, max: Vector3<i32>, material: MaterialType) -> usize;
+    /// `None` without a world
+    fn seed(&self) -> Option<u32>;
+    fn set_time(&mut self, time: u32);
+    fn set_game_mode(&mut self, mode: GameMode);
+    /// Returns the render distance set, after clamping it
+    fn set_render_distance(&mut self, render_distance: usize) -> usize;
+}
+
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CommandError {
+    Empty,
+    UnknownCommand(String),
+    /// Wrong number of arguments or keyword, with the usage of the command
+    Usage(&'static str),
+    InvalidArgument { arg: String, expected: &'static str },
+    /// The command was right but couldn't be done
+    Failed(String),
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandError::Empty => write!(f, "No command given"),
+            CommandError::UnknownCommand(name) => write!(f, "Unknown command '{}', try /help", name),
+            CommandError::Usage(usage) => write!(f, "Usage: /{}", usage),
+            CommandError::InvalidArgument { arg, expected } => write!(f, "Invalid argument '{}', expected {}", arg, expected),
+            CommandError::Failed(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+
+/// What an argument is, for the completions
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ArgKind {
+    /// Number, or `~` followed by an optional offset from the player
+    Coord,
+    Integer,
+    Block,
+    GameMode,
+    /// Ticks or one of the named times
+    Time,
+    /// One of these words
+    Keyword(&'static [&'static str]),
+}
+
+impl ArgKind {
+    fn suggestions(self) -> Vec<&'static str> {
+        match self {
+            ArgKind::Coord => vec!["~"],
+            ArgKind::Integer => vec![],
+            ArgKind::Block => MaterialType::NAMES.iter().map(|(_, name)| *name).collect(),
+            ArgKind::GameMode => GAME_MODES.iter().map(|(name, _)| *name).collect(),
+            ArgKind::Time => TIME_NAMES.iter().map(|(name, _)| *name).collect(),
+            ArgKind::Keyword(words) => words.to_vec(),
+        }
+    }
+}
+
+
+/// Arguments given to a command, already counted against its `ArgKind`s
+pub struct Args<'a> {
+    tokens: Vec<&'a str>,
+}
+
+impl<'a> Args<'a> {
+    fn get(&self, index: usize) -> &'a str {
+        self.tokens[index]
+    }
+
+    /// Coordinate at `index`, `~` ones added to `origin`
+    pub fn coord(&self, index: usize, origin: f32) -> Result<f32, CommandError> {
+        let token = self.get(index);
+        parse_coord(token, origin).ok_or_else(|| invalid(token, "a number or ~"))
+    }
+
+    /// Three coordinates from `index`, relative to `origin`
+    pub fn position(&self, index: usize, origin: Point3<f32>) -> Result<Point3<f32>, CommandError> {
+        Ok(Point3::new(self.coord(index, origin.x)?, self.coord(index + 1, origin.y)?, self.coord(index + 2, origin.z)?))
+    }
+
+    /// Block containing the position at `index`
+    pub fn block_position(&self, index: usize, origin: Point3<f32>) -> Result<Vector3<i32>, CommandError> {
+        let position = self.position(index, origin)?;
+        Ok(Vector3::new(position.x.floor() as i32, position.y.floor() as i32, position.z.floor() as i32))
+    }
+
+    pub fn integer(&self, index: usize) -> Result<i64, CommandError> {
+        let token = self.get(index);
+        token.parse().map_err(|_| invalid(token, "a whole number"))
+    }
+
+    pub fn block(&self, index: usize) -> Result<MaterialType, CommandError> {
+        let token = self.get(index);
+        MaterialType::from_name(token).ok_or_else(|| invalid(token, "a block name"))
+    }
+
+    pub fn game_mode(&self, index: usize) -> Result<GameMode, CommandError> {
+        let token = self.get(index);
+        GAME_MODES.iter().find(|(name, _)| *name == token).map(|(_, mode)| *mode)
+            .ok_or_else(|| invalid(token, "survival or creative"))
+    }
+
+    /// Ticks of the day, named times allowed
+    pub fn time(&self, index: usize) -> Result<u32, CommandError> {
+        let token = self.get(index);
+        let named = TIME_NAMES.iter().find(|(name, _)| *name == token).map(|(_, time)| *time);
+        named.or_else(|| token.parse::<u32>().ok())
+            .map(|time| time % DAY_LENGTH)
+            .ok_or_else(|| invalid(token, "ticks or day, noon, night, midnight"))
+    }
+}
+
+fn invalid(token: &str, expected: &'static str) -> CommandError {
+    CommandError::InvalidArgument { arg: token.to_string(), expected }
+}
+
+/// `5` is 5, `~` is `origin` and `~-2` is `origin - 2`
+pub fn parse_coord(token: &str, origin: f32) -> Option<f32> {
+    let (relative, number) = match token.strip_prefix('~') {
+        Some(rest) => (true, rest),
+        None => (false, token),
+    };
+    let value = if relative && number.is_empty() { 0.0 } else { number.parse::<f32>().ok()? };
+    if !value.is_finite() {
+        return None;
+    }
+    Some(if relative { origin + value } else { value })
+}
+
+
+pub type CommandResult = Result<String, CommandError>;
+
+/// A command of the console
+pub struct Command {
+    pub name: &'static str,
+    /// Shown by `/help` and when the arguments are wrong, without the `/`
+    pub usage: &'static str,
+    pub args: &'static [ArgKind],
+    /// Returns the message shown in the console
+    pub run: fn(&Args, &mut dyn CommandTarget) -> CommandResult,
+}
+
+
+/// Commands by name, the builtin ones by default
+pub struct CommandRegistry {
+    commands: Vec<Command>,
+}
+
+impl Default for CommandRegistry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        for command in builtin_commands() {
+            registry.register(command);
+        }
+        registry
+    }
+}
+
+impl CommandRegistry {
+    pub fn empty() -> Self {
+        Self { commands: Vec::new() }
+    }
+
+    /// Adds `command`, replacing the one with the same name
+    pub fn register(&mut self, command: Command) {
+        self.commands.retain(|c| c.name != command.name);
+        self.commands.push(command);
+        self.commands.sort_by_key(|c| c.name);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Command> {
+        self.commands.iter().find(|c| c.name == name)
+    }
+
+    pub fn commands(&self) -> impl Iterator<Item = &Command> {
+        self.commands.iter()
+    }
+
+    /// Runs `line`, a command without the leading `/`
+    pub fn execute(&self, line: &str, target: &mut dyn CommandTarget) -> CommandResult {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let Some((name, args)) = tokens.split_first() else {
+            return Err(CommandError::Empty);
+        };
+        if *name == "help" {
+            return Ok(self.help());
+        }
+
+        let command = self.get(name).ok_or_else(|| CommandError::UnknownCommand(name.to_string()))?;
+        // las palabras fijas, como el `set` de `/time set`, se comprueban aquí
+        let keywords_match = command.args.iter().zip(args).all(|(kind, arg)| match kind {
+            ArgKind::Keyword(words) => words.contains(arg),
+            _ => true,
+        });
+        if args.len() != command.args.len() || !keywords_match {
+            return Err(CommandError::Usage(command.usage));
+        }
+        (command.run)(&Args { tokens: args.to_vec() }, target)
+    }
+
+    /// Every usage, one per line
+    pub fn help(&self) -> String {
+        let usages: Vec<String> = self.commands.iter().map(|c| format!("/{}", c.usage)).collect();
+        usages.join("\n")
+    }
+
+    /// Lines `line` can be completed to, changing its last word. `line` has no `/`.
+    pub fn complete(&self, line: &str) -> Vec<String> {
+        let mut words: Vec<&str> = line.split_whitespace().collect();
+        let partial = if line.is_empty() || line.ends_with(' ') { "" } else { words.pop().unwrap_or("") };
+
+        let candidates = match words.split_first() {
+            None => self.commands.iter().map(|c| c.name).chain(["help"]).collect(),
+            Some((name, args)) => self.get(name)
+                .and_then(|command| command.args.get(args.len()))
+                .map_or(Vec::new(), |kind| kind.suggestions()),
+        };
+
+        let prefix: String = words.iter().map(|word| format!("{} ", word)).collect();
+        let mut lines: Vec<String> = candidates.into_iter()
+            .filter(|candidate| candidate.starts_with(partial))
+            .map(|candidate| format!("{}{}", prefix, candidate))
+            .collect();
+        lines.sort();
+        lines.dedup();
+        lines
+    }
+}
+
+
+fn builtin_commands() -> Vec<Command> {
+    use ArgKind::*;
+    vec![
+        Command {
+            name: "tp",
+            usage: "tp <x> <y> <z>",
+            args: &[Coord, Coord, Coord],
+            run: |args, target| {
+                let feet = args.position(0, target.player_position())?;
+                target.teleport(feet);
+                Ok(format!("Teleported to {:.1} {:.1} {:.1}", feet.x, feet.y, feet.z))
+            },
+        },
+        Command {
+            name: "setblock",
+            usage: "setblock <x> <y> <z> <block>",
+            args: &[Coord, Coord, Coord, Block],
+            run: |args, target| {
+                let position = args.block_position(0, target.player_position())?;
+                let material = args.block(3)?;
+                if !target.set_block(position, material) {
+                    return Err(CommandError::Failed("That position isn't loaded".to_string()));
+                }
+                Ok(format!("Set {} at {} {} {}", material.name(), position.x, position.y, position.z))
+            },
+        },
+        Command {
+            name: "fill",
+            usage: "fill <x1> <y1> <z1> <x2> <y2> <z2> <block>",
+            args: &[Coord, Coord, Coord, Coord, Coord, Coord, Block],
+            run: |args, target| {
+                let origin = target.player_position();
+                let (a, b) = (args.block_position(0, origin)?, args.block_position(3, origin)?);
+                let material = args.block(6)?;
+                let min = Vector3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z));
+                let max = Vector3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z));
+
+                // cada lado cabe en u32 y el producto en u128, con coordenadas extremas no desborda
+                let side = |a: i32, b: i32| a.abs_diff(b) as u128 + 1;
+                let volume = side(a.x, b.x) * side(a.y, b.y) * side(a.z, b.z);
+                if volume > MAX_FILL_BLOCKS as u128 {
+                    return Err(CommandError::Failed(format!("Too many blocks ({}, at most {})", volume, MAX_FILL_BLOCKS)));
+                }
+                let filled = target.fill(min, max, material);
+                Ok(format!("Filled {} blocks with {}", filled, material.name()))
+            },
+        },
+        Command {
+            name: "seed",
+            usage: "seed",
+            args: &[],
+            run: |_, target| match target.seed() {
+                Some(seed) => Ok(format!("Seed: {}", seed)),
+                None => Err(CommandError::Failed("No world loaded".to_string())),
+            },
+        },
+        Command {
+            name: "time",
+            usage: "time set <ticks|day|noon|night|midnight>",
+            args: &[Keyword(&["set"]), Time],
+            run: |args, target| {
+                let time = args.time(1)?;
+                target.set_time(time);
+                Ok(format!("Time set to {}", time))
+            },
+        },
+        Command {
+            name: "gamemode",
+            usage: "gamemode <survival|creative>",
+            args: &[GameMode],
+            run: |args, target| {
+                let mode = args.game_mode(0)?;
+                target.set_game_mode(mode);
+                Ok(format!("Game mode set to {:?}", mode))
+            },
+        },
+        Command {
+            name: "rd",
+            usage: "rd <chunks>",
+            args: &[Integer],
+            run: |args, target| {
+                let chunks = args.integer(0)?.max(0) as usize;
+                let set = target.set_render_distance(chunks);
+                Ok(format!("Render distance: {} chunks", set))
+            },
+        },
+    ]
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    /// Blocks in a map, everything with y in 0..64 is loaded
+    #[derive(Default)]
+    struct FakeWorld {
+        feet: [f32; 3],
+        blocks: HashMap<[i32; 3], MaterialType>,
+        time: u32,
+        mode: Option<GameMode>,
+        render_distance: usize,
+    }
+
+    impl CommandTarget for FakeWorld {
+        fn player_position(&self) -> Point3<f32> {
+            self.feet.into()
+        }
+
+        fn teleport(&mut self, feet: Point3<f32>) {
+            self.feet = feet.into();
+        }
+
+        fn set_block(&mut self, position: Vector3<i32>, material: MaterialType) -> bool {
+            if !(0..64).contains(&position.y) {
+                return false;
+            }
+            self.blocks.insert(position.into(), material);
+            true
+        }
+
+        fn fill(&mut self, min: Vector3<i32>, max: Vector3<i32>, material: MaterialType) -> usize {
+            let mut filled = 0;
+            for x in min.x..=max.x {
+                for y in min.y..=max.y {
+                    for z in min.z..=max.z {
+                        filled += self.set_block(Vector3::new(x, y, z), material) as usize;
+                    }
+                }
+            }
+            filled
+        }
+
+        fn seed(&self) -> Option<u32> {
+            Some(1234)
+        }
+
+        fn set_time(&mut self, time: u32) {
+            self.time = time;
+        }
+
+        fn set_game_mode(&mut self, mode: GameMode) {
+            self.mode = Some(mode);
+        }
+
+        fn set_render_distance(&mut self, render_distance: usize) -> usize {
+            self.render_distance = render_distance.clamp(2, 16);
+            self.render_distance
+        }
+    }
+
+    fn run(world: &mut FakeWorld, line: &str) -> CommandResult {
+        CommandRegistry::default().execute(line, world)
+    }
+
+    #[test]
+    fn relative_coordinates() {
+        assert_eq!(parse_coord("5", 10.0), Some(5.0));
+        assert_eq!(parse_coord("-2.5", 10.0), Some(-2.5));
+        assert_eq!(parse_coord("~", 10.0), Some(10.0));
+        assert_eq!(parse_coord("~3", 10.0), Some(13.0));
+        assert_eq!(parse_coord("~-1.5", 10.0), Some(8.5));
+        assert_eq!(parse_coord("~~", 10.0), None);
+        assert_eq!(parse_coord("x", 10.0), None);
+        assert_eq!(parse_coord("NaN", 10.0), None);
+    }
+
+    #[test]
+    fn teleports() {
+        let mut world = FakeWorld { feet: [1.0, 20.0, 3.0], ..Default::default() };
+        assert!(run(&mut world, "tp 5 ~10 ~").is_ok());
+        assert_eq!(world.feet, [5.0, 30.0, 3.0]);
+
+        assert_eq!(run(&mut world, "tp 1 2"), Err(CommandError::Usage("tp <x> <y> <z>")));
+        assert_eq!(
+            run(&mut world, "tp a 2 3"),
+            Err(CommandError::InvalidArgument { arg: "a".to_string(), expected: "a number or ~" }),
+        );
+    }
+
+    #[test]
+    fn sets_and_fills_blocks() {
+        let mut world = FakeWorld { feet: [0.5, 10.2, -0.5], ..Default::default() };
+        run(&mut world, "setblock ~ ~-1 ~ planks").unwrap();
+        assert_eq!(world.blocks[&[0, 9, -1]], MaterialType::PLANKS);
+
+        assert!(matches!(run(&mut world, "setblock 0 100 0 rock"), Err(CommandError::Failed(_))));
+        assert!(matches!(run(&mut world, "setblock 0 1 0 diamond"), Err(CommandError::InvalidArgument { .. })));
+
+        // las esquinas pueden ir en cualquier orden
+        assert_eq!(run(&mut world, "fill 2 1 2 0 0 0 rock").unwrap(), "Filled 18 blocks with rock");
+        assert_eq!(world.blocks.len(), 19);
+        assert!(matches!(run(&mut world, "fill 0 0 0 100 63 100 air"), Err(CommandError::Failed(_))));
+        // coordenadas que no caben en i32 no desbordan el tamaño
+        assert!(matches!(run(&mut world, "fill -3e9 0 0 3e9 0 0 air"), Err(CommandError::Failed(_))));
+        assert!(matches!(run(&mut world, "fill -3e9 -3e9 -3e9 3e9 3e9 3e9 air"), Err(CommandError::Failed(_))));
+    }
+
+    #[test]
+    fn world_commands() {
+        let mut world = FakeWorld::default();
+        assert_eq!(run(&mut world, "seed").unwrap(), "Seed: 1234");
+
+        run(&mut world, "time set noon").unwrap();
+        assert_eq!(world.time, 6000);
+        run(&mut world, "time set 30000").unwrap();
+        assert_eq!(world.time, 6000);
+        assert_eq!(run(&mut world, "time add 5"), Err(CommandError::Usage("time set <ticks|day|noon|night|midnight>")));
+
+        run(&mut world, "gamemode creative").unwrap();
+        assert_eq!(world.mode, Some(GameMode::Creative));
+
+        assert_eq!(run(&mut world, "rd 40").unwrap(), "Render distance: 16 chunks");
+        assert_eq!(run(&mut world, "fly"), Err(CommandError::UnknownCommand("fly".to_string())));
+        assert_eq!(run(&mut world, "  "), Err(CommandError::Empty));
+        assert!(run(&mut world, "help").unwrap().contains("/tp <x> <y> <z>"));
+    }
+
+    #[test]
+    fn completes_names_and_arguments() {
+        let registry = CommandRegistry::default();
+        assert_eq!(registry.complete("t"), vec!["time", "tp"]);
+        assert_eq!(registry.complete("gamemode "), vec!["gamemode creative", "gamemode survival"]);
+        assert_eq!(registry.complete("setblock ~ ~ ~ pl"), vec!["setblock ~ ~ ~ planks"]);
+        assert_eq!(registry.complete("tp "), vec!["tp ~"]);
+        assert_eq!(registry.complete("time set n"), vec!["time set night", "time set noon"]);
+        assert!(registry.complete("tp 1 2 3 ").is_empty());
+        assert!(registry.complete("unknown ").is_empty());
+    }
+}
@@ -0,0 +1,302 @@
+pub mod command;
+
+use std::{collections::VecDeque, time::{Duration, Instant}};
+
+use winit::keyboard::KeyCode;
+
+use crate::{hud::{layout::Anchor, text::{TextRenderer, TextStyle, GLYPH_HEIGHT, GUI_TEXT_SCALE}}, render::pipelines::hud::HUDVertex};
+use command::CommandRegistry;
+
+
+/// Lines kept in the log, older ones are dropped
+const MAX_LOG_LINES: usize = 100;
+/// Lines kept to browse with the arrows
+const MAX_HISTORY: usize = 50;
+/// Characters the input line takes
+const MAX_INPUT: usize = 256;
+/// Lines shown above the input line while the console is open
+const OPEN_LINES: usize = 10;
+/// How long a line stays on screen with the console closed
+const LINE_LIFETIME: Duration = Duration::from_secs(10);
+/// Time the line takes to fade out at the end of its lifetime
+const FADE_TIME: Duration = Duration::from_secs(1);
+/// Offset from the bottom left corner in GUI units, leaves room for the hotbar
+const CONSOLE_OFFSET: (f32, f32) = (4.0, -44.0);
+const ERROR_COLOR: [f32; 4] = [1.0, 0.35, 0.35, 1.0];
+
+
+/// What a key did to the console
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConsoleKey {
+    /// The console doesn't use the key, it can trigger its action
+    Unused,
+    Used,
+    /// Enter was pressed with this line, the console should be closed
+    Submitted(String),
+}
+
+struct LogLine {
+    text: String,
+    color: [f32; 4],
+    time: Instant,
+}
+
+
+/// Chat-style console: a line to type in, with history and completion, and the log
+/// of messages above it. Commands are run by `CommandRegistry`, this only edits text.
+#[derive(Default)]
+pub struct Console {
+    input: String,
+    history: Vec<String>,
+    /// Index in `history` being shown, `None` while editing a new line
+    browsing: Option<usize>,
+    /// Line being typed before browsing the history, comes back after the newest entry
+    draft: String,
+    /// Lines Tab cycles through and the one shown
+    completions: Vec<String>,
+    completion: usize,
+    log: VecDeque<LogLine>,
+}
+
+impl Console {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts a new line with `prefix`, `/` when opened to type a command
+    pub fn open(&mut self, prefix: &str) {
+        self.input = prefix.to_string();
+        self.browsing = None;
+        self.draft.clear();
+        self.reset_completion();
+    }
+
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    /// Appends typed characters, control characters are left out
+    pub fn type_text(&mut self, text: &str) {
+        let room = MAX_INPUT.saturating_sub(self.input.chars().count());
+        self.input.extend(text.chars().filter(|c| !c.is_control()).take(room));
+        self.reset_completion();
+    }
+
+    pub fn key(&mut self, key: KeyCode, commands: &CommandRegistry) -> ConsoleKey {
+        match key {
+            KeyCode::Enter | KeyCode::NumpadEnter => return ConsoleKey::Submitted(self.submit()),
+            KeyCode::Backspace => {
+                self.input.pop();
+                self.reset_completion();
+            }
+            KeyCode::ArrowUp => self.browse(-1),
+            KeyCode::ArrowDown => self.browse(1),
+            KeyCode::Tab => self.complete(commands),
+            _ => return ConsoleKey::Unused,
+        }
+        ConsoleKey::Used
+    }
+
+    pub fn print(&mut self, text: &str) {
+        self.push_log(text, HUDVertex::WHITE);
+    }
+
+    pub fn print_error(&mut self, text: &str) {
+        self.push_log(text, ERROR_COLOR);
+    }
+
+    /// Log lines on screen at `now`, oldest first, with their opacity. With the console
+    /// open the last `OPEN_LINES` are shown, closed only the recent ones.
+    fn visible_lines(&self, open: bool, now: Instant) -> Vec<(&str, [f32; 4])> {
+        let mut lines: Vec<_> = self.log.iter().rev()
+            .take(OPEN_LINES)
+            .filter_map(|line| {
+                if open {
+                    return Some((line.text.as_str(), line.color));
+                }
+                let left = LINE_LIFETIME.checked_sub(now.duration_since(line.time))?;
+                let alpha = (left.as_secs_f32() / FADE_TIME.as_secs_f32()).min(1.0);
+                let [r, g, b, a] = line.color;
+                Some((line.text.as_str(), [r, g, b, a * alpha]))
+            })
+            .collect();
+        lines.reverse();
+        lines
+    }
+
+    /// Queues the log above the hotbar, and the input line under it while `open`
+    pub fn queue_text(&self, text: &mut TextRenderer, open: bool) {
+        let layout = *text.layout();
+        let style = TextStyle { scale: layout.scale() * GUI_TEXT_SCALE, ..Default::default() };
+        let line_height = GLYPH_HEIGHT * style.scale;
+        let origin = layout.place(Anchor::BottomLeft, CONSOLE_OFFSET, (0.0, 0.0));
+
+        let mut y = origin.y - line_height;
+        if open {
+            text.queue(&format!("{}_", self.input), (origin.x, y), &style);
+            y -= line_height;
+        }
+        for (line, color) in self.visible_lines(open, Instant::now()).iter().rev() {
+            text.queue(line, (origin.x, y), &TextStyle { color: *color, ..style });
+            y -= line_height;
+        }
+    }
+
+    fn push_log(&mut self, text: &str, color: [f32; 4]) {
+        let time = Instant::now();
+        for line in text.lines() {
+            self.log.push_back(LogLine { text: line.to_string(), color, time });
+        }
+        while self.log.len() > MAX_LOG_LINES {
+            self.log.pop_front();
+        }
+    }
+
+    /// Empties the input and returns it, trimmed. Lines that aren't empty go to the history.
+    fn submit(&mut self) -> String {
+        let line = std::mem::take(&mut self.input).trim().to_string();
+        if !line.is_empty() && self.history.last() != Some(&line) {
+            self.history.push(line.clone());
+            if self.history.len() > MAX_HISTORY {
+                self.history.remove(0);
+            }
+        }
+        self.browsing = None;
+        self.reset_completion();
+        line
+    }
+
+    /// Moves through the history, -1 to older lines and 1 to newer ones
+    fn browse(&mut self, step: isize) {
+        if self.history.is_empty() || (self.browsing.is_none() && step > 0) {
+            return;
+        }
+        let newest = self.history.len() - 1;
+        self.browsing = match self.browsing {
+            None => {
+                self.draft = self.input.clone();
+                Some(newest)
+            }
+            Some(i) if step < 0 => Some(i.saturating_sub(1)),
+            Some(i) if i < newest => Some(i + 1),
+            Some(_) => None,
+        };
+        self.input = match self.browsing {
+            Some(i) => self.history[i].clone(),
+            None => self.draft.clone(),
+        };
+        self.reset_completion();
+    }
+
+    /// Completes the command or argument being typed. With several candidates they are
+    /// listed in the log and each Tab shows the next one.
+    fn complete(&mut self, commands: &CommandRegistry) {
+        if !self.completions.is_empty() {
+            self.completion = (self.completion + 1) % self.completions.len();
+            self.input = format!("/{}", self.completions[self.completion]);
+            return;
+        }
+
+        let Some(line) = self.input.strip_prefix('/') else {
+            return;
+        };
+        let candidates = commands.complete(line);
+        match candidates.as_slice() {
+            [] => {}
+            [only] => self.input = format!("/{} ", only),
+            _ => {
+                let last_words: Vec<&str> = candidates.iter().filter_map(|c| c.split_whitespace().last()).collect();
+                self.print(&last_words.join(" "));
+                self.input = format!("/{}", candidates[0]);
+                self.completions = candidates;
+                self.completion = 0;
+            }
+        }
+    }
+
+    fn reset_completion(&mut self) {
+        self.completions.clear();
+        self.completion = 0;
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_line(console: &mut Console, commands: &CommandRegistry, line: &str) -> ConsoleKey {
+        console.open("");
+        console.type_text(line);
+        console.key(KeyCode::Enter, commands)
+    }
+
+    #[test]
+    fn submits_and_browses_history() {
+        let commands = CommandRegistry::default();
+        let mut console = Console::new();
+        assert_eq!(type_line(&mut console, &commands, " /seed "), ConsoleKey::Submitted("/seed".to_string()));
+        type_line(&mut console, &commands, "hello");
+        type_line(&mut console, &commands, "hello");
+        assert_eq!(console.history, vec!["/seed", "hello"]);
+
+        console.open("");
+        console.type_text("draft");
+        console.key(KeyCode::ArrowUp, &commands);
+        assert_eq!(console.input(), "hello");
+        console.key(KeyCode::ArrowUp, &commands);
+        console.key(KeyCode::ArrowUp, &commands);
+        assert_eq!(console.input(), "/seed");
+        console.key(KeyCode::ArrowDown, &commands);
+        console.key(KeyCode::ArrowDown, &commands);
+        assert_eq!(console.input(), "draft");
+
+        assert_eq!(console.key(KeyCode::KeyW, &commands), ConsoleKey::Unused);
+        assert_eq!(console.key(KeyCode::Backspace, &commands), ConsoleKey::Used);
+        assert_eq!(console.input(), "draf");
+    }
+
+    #[test]
+    fn tab_completes() {
+        let commands = CommandRegistry::default();
+        let mut console = Console::new();
+        console.open("/");
+        console.type_text("game");
+        console.key(KeyCode::Tab, &commands);
+        assert_eq!(console.input(), "/gamemode ");
+
+        console.type_text("s");
+        console.key(KeyCode::Tab, &commands);
+        assert_eq!(console.input(), "/gamemode survival ");
+
+        console.open("/t");
+        console.key(KeyCode::Tab, &commands);
+        assert_eq!(console.input(), "/time");
+        console.key(KeyCode::Tab, &commands);
+        assert_eq!(console.input(), "/tp");
+        console.key(KeyCode::Tab, &commands);
+        assert_eq!(console.input(), "/time");
+
+        // el chat no se completa
+        console.open("ti");
+        console.key(KeyCode::Tab, &commands);
+        assert_eq!(console.input(), "ti");
+    }
+
+    #[test]
+    fn old_lines_fade_when_closed() {
+        let mut console = Console::new();
+        console.print("first\nsecond");
+        console.print_error("oops");
+        let now = Instant::now();
+
+        let open: Vec<&str> = console.visible_lines(true, now).iter().map(|(line, _)| *line).collect();
+        assert_eq!(open, vec!["first", "second", "oops"]);
+        assert_eq!(console.visible_lines(false, now)[2].1, ERROR_COLOR);
+
+        let later = now + LINE_LIFETIME - FADE_TIME / 2;
+        assert!(console.visible_lines(false, later).iter().all(|(_, color)| color[3] <= 0.5));
+        assert!(console.visible_lines(false, now + LINE_LIFETIME * 2).is_empty());
+        assert_eq!(console.visible_lines(true, now + LINE_LIFETIME * 2).len(), 3);
+    }
+}
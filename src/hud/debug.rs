@@ -0,0 +1,91 @@
+use crate::{player::raycast::BlockHit, render::atlas::MaterialType, terrain_gen::chunk::CHUNK_AREA};
+
+use super::text::{TextRenderer, TextStyle, GLYPH_HEIGHT, GUI_TEXT_SCALE};
+
+
+/// Distance in GUI units from the top left corner of the window to the first line
+const DEBUG_MARGIN: f32 = 2.0;
+
+
+/// What the F3 overlay shows. `State` fills in the player, `TerrainGen` the chunks and
+/// `Renderer` the frame times, only while the overlay is visible.
+#[derive(Clone, Debug, Default)]
+pub struct DebugStats {
+    /// Seconds between the last two frames presented, smoothed
+    pub frame_time: f32,
+    /// Size of the surface in pixels
+    pub resolution: (u32, u32),
+
+    pub position: [f32; 3],
+    /// Camera angles in degrees
+    pub yaw: f32,
+    pub pitch: f32,
+    pub target: Option<BlockHit>,
+    pub target_material: Option<MaterialType>,
+
+    /// Full detail chunks in the grid, with their blocks generated
+    pub loaded_chunks: usize,
+    /// Grid cells still waiting for a chunk
+    pub pending_chunks: usize,
+    /// Full detail chunks with something to draw
+    pub drawn_chunks: usize,
+    /// LOD chunks with something to draw
+    pub lod_chunks: usize,
+    /// Vertices of every drawn chunk, LOD included
+    pub terrain_vertices: usize,
+    pub biome: &'static str,
+}
+
+impl DebugStats {
+    pub fn lines(&self) -> Vec<String> {
+        let fps = if self.frame_time > 0.0 { 1.0 / self.frame_time } else { 0.0 };
+        let [x, y, z] = self.position;
+        let block = self.position.map(|v| v.floor() as i32);
+        let chunk_size = CHUNK_AREA as i32;
+        let chunk = (block[0].div_euclid(chunk_size), block[2].div_euclid(chunk_size));
+        let local = (block[0].rem_euclid(chunk_size), block[1], block[2].rem_euclid(chunk_size));
+
+        let target = match self.target {
+            Some(hit) => format!(
+                "Target {} {} {} {}, face {:?}",
+                hit.position.x, hit.position.y, hit.position.z,
+                self.target_material.map_or("unknown".to_string(), |material| format!("{:?}", material)), hit.face,
+            ),
+            None => "Target none".to_string(),
+        };
+
+        vec![
+            format!("{:.0} fps ({:.2} ms)", fps, self.frame_time * 1000.0),
+            format!("XYZ {:.3} / {:.3} / {:.3}", x, y, z),
+            format!("Block {} {} {}", block[0], block[1], block[2]),
+            format!("Chunk {} {} (local {} {} {})", chunk.0, chunk.1, local.0, local.1, local.2),
+            format!("Facing {} (yaw {:.1}, pitch {:.1})", facing(self.yaw), self.yaw, self.pitch),
+            target,
+            format!(
+                "Chunks {} loaded, {} pending, {} drawn, {} LOD",
+                self.loaded_chunks, self.pending_chunks, self.drawn_chunks, self.lod_chunks,
+            ),
+            format!("Terrain vertices {}", self.terrain_vertices),
+            format!("Biome {}", self.biome),
+            format!("Window {}x{}", self.resolution.0, self.resolution.1),
+        ]
+    }
+
+    /// Queues the lines in the top left corner
+    pub fn queue_text(&self, text: &mut TextRenderer) {
+        let gui_scale = text.layout().scale();
+        let style = TextStyle { scale: gui_scale * GUI_TEXT_SCALE, ..Default::default() };
+        let margin = DEBUG_MARGIN * gui_scale;
+        for (row, line) in self.lines().iter().enumerate() {
+            text.queue(line, (margin, margin + row as f32 * GLYPH_HEIGHT * style.scale), &style);
+        }
+    }
+}
+
+
+/// Horizontal axis closest to where the camera looks. Yaw 0 looks towards +X and grows
+/// towards +Z, see `Camera::walk_direction`.
+fn facing(yaw: f32) -> &'static str {
+    let quadrant = ((yaw / 90.0).round() as i32).rem_euclid(4);
+    ["+X", "+Z", "-X", "-Z"][quadrant as usize]
+}
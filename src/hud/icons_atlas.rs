@@ -1,16 +1,22 @@
 use anyhow::*;
 
 use crate::render::texture::*;
+use crate::item::tool::{ToolKind, ToolTier};
 use super::HUDVertex;
 
 
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[allow(non_camel_case_types)]
 pub enum IconType {
     ROCK,
     GRASS,
     DIRT,
     STONE,
     WOODEN,
+    LOG,
+    CRAFTING_TABLE,
+    STICK,
+    TOOL(ToolKind, ToolTier),
 }
 
 const ICON_SIZE: (f32, f32) = (32.0, 32.0);
@@ -24,6 +30,11 @@ impl IconType {
             IconType::DIRT => (2, 0),
             IconType::STONE => (3, 0),
             IconType::WOODEN => (4, 0),
+            IconType::LOG => (0, 1),
+            IconType::CRAFTING_TABLE => (1, 4),
+            IconType::STICK => (12, 15),
+            // la última fila: picos, palas y hachas de peor a mejor material
+            IconType::TOOL(kind, tier) => (*kind as u32 * 4 + *tier as u32, 15),
             // Mapea más iconos según tu atlas
         };
         
@@ -35,27 +46,6 @@ impl IconType {
         [u_min, v_min, u_max, v_max]
     }
 
-    pub fn next(self) -> Self {
-        match self {
-            IconType::ROCK => IconType::GRASS,
-            IconType::GRASS => IconType::DIRT,
-            IconType::DIRT => IconType::STONE,
-            IconType::STONE => IconType::WOODEN,
-            IconType::WOODEN => IconType::ROCK, // circular
-        }
-    }
-
-    pub fn prev(self) -> Self {
-        match self {
-            IconType::ROCK => IconType::WOODEN,
-            IconType::WOODEN => IconType::STONE,
-            IconType::STONE => IconType::DIRT,
-            IconType::DIRT => IconType::GRASS,
-            IconType::GRASS => IconType::ROCK,
-        }
-    }
-
-
     pub fn get_vertex_quad(
         &self,
         center_x: f32, 
@@ -70,19 +60,23 @@ impl IconType {
         let vertices = [
             HUDVertex {
                 position: [center_x - half_width, center_y - half_height],
-                uv: [uv[0], uv[3]], // Nota v_min y v_max invertidos
+                uv: [uv[0], uv[3]],
+                color: HUDVertex::WHITE, // Nota v_min y v_max invertidos
             },
             HUDVertex {
                 position: [center_x + half_width, center_y - half_height],
                 uv: [uv[2], uv[3]],
+                color: HUDVertex::WHITE,
             },
             HUDVertex {
                 position: [center_x + half_width, center_y + half_height],
                 uv: [uv[2], uv[1]],
+                color: HUDVertex::WHITE,
             },
             HUDVertex {
                 position: [center_x - half_width, center_y + half_height],
                 uv: [uv[0], uv[1]],
+                color: HUDVertex::WHITE,
             },
         ];
 
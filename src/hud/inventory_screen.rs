@@ -0,0 +1,227 @@
+use crate::{item::{crafting::{CraftingGrid, RecipeBook, INVENTORY_GRID_SIZE, TABLE_GRID_SIZE}, inventory::{Inventory, HOTBAR_SIZE}, ItemStack}, render::{mesh::Mesh, pipelines::GlobalsLayouts, renderer::Renderer, texture::Texture}};
+
+use super::{layout::{Anchor, Layout, Rect}, push_quad, push_stack, text, HUDElement, SLOT_SIZE};
+
+
+/// Background of the inventory window
+const PANEL_COLOR: [u8; 4] = [40, 40, 40, 200];
+/// Space between the slots and the panel border, in GUI units
+const PANEL_PADDING: f32 = 6.0;
+/// Space between the main grid and the hotbar row or the crafting grid, in GUI units
+const HOTBAR_GAP: f32 = 4.0;
+/// Horizontal distance from the center to the crafting grid and to its result, in slots
+const CRAFTING_OFFSET: f32 = 2.0;
+
+
+/// Any slot drawn in the screen
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum ScreenSlot {
+    Inventory(usize),
+    Grid(usize),
+    /// What the crafting grid makes, can only be taken
+    Result,
+}
+
+
+/// Window opened over the game to move stacks around with the mouse.
+/// Shows the crafting grid over the main inventory grid, over the hotbar row.
+pub struct InventoryScreen {
+    pub open: bool,
+    /// Stack picked up with the mouse, drawn under the cursor
+    pub held: Option<ItemStack>,
+    /// 2x2 from the inventory, 3x3 from a crafting table
+    pub crafting: CraftingGrid,
+    /// Result of the crafting grid, updated on every click
+    result: Option<ItemStack>,
+    panel: HUDElement,
+    slots: HUDElement,
+    icons: HUDElement,
+    counts: HUDElement,
+    /// Mouse position in pixels
+    cursor: (f32, f32),
+    /// Same as the HUD
+    layout: Layout,
+}
+
+impl InventoryScreen {
+    pub fn new(renderer: &Renderer, global_layout: &GlobalsLayouts, slots_len: usize, layout: Layout) -> Self {
+        let widget_bytes = include_bytes!("../../assets/images/widget_window.png");
+        let icons_bytes = include_bytes!("../../assets/images/icons_atlas.png");
+
+        let widget_tex = Texture::from_bytes(&renderer.device, &renderer.queue, widget_bytes, "widget_window.png").unwrap();
+        let icons_tex = Texture::from_bytes(&renderer.device, &renderer.queue, icons_bytes, "icons_atlas.png").unwrap();
+        let panel_img = image::DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(1, 1, image::Rgba(PANEL_COLOR)));
+        let panel_tex = Texture::from_image(&renderer.device, &renderer.queue, &panel_img, Some("inventory panel")).unwrap();
+
+        // un icono y un número de dos cifras con sombra por casilla, más los de la pila del cursor
+        let slots_len = slots_len + TABLE_GRID_SIZE * TABLE_GRID_SIZE + 1;
+        Self {
+            open: false,
+            held: None,
+            crafting: CraftingGrid::new(INVENTORY_GRID_SIZE),
+            result: None,
+            panel: HUDElement::new(renderer, global_layout, panel_tex, 1),
+            slots: HUDElement::new(renderer, global_layout, widget_tex, slots_len),
+            icons: HUDElement::new(renderer, global_layout, icons_tex, slots_len + 1),
+            counts: HUDElement::new(renderer, global_layout, text::font_texture(renderer), (slots_len + 1) * 4),
+            cursor: (0.0, 0.0),
+            layout,
+        }
+    }
+
+    pub fn set_layout(&mut self, layout: Layout) {
+        self.layout = layout;
+    }
+
+    pub fn set_cursor(&mut self, position: winit::dpi::PhysicalPosition<f64>) {
+        self.cursor = (position.x as f32, position.y as f32);
+    }
+
+    /// Opens the screen with a crafting grid `grid_size` slots a side
+    pub fn open(&mut self, grid_size: usize) {
+        self.crafting = CraftingGrid::new(grid_size);
+        self.result = None;
+        self.open = true;
+    }
+
+    /// Puts the held stack and the crafting grid back into the inventory. If they don't
+    /// fit what is left stays where it was, the screen stays open and it returns false.
+    pub fn close(&mut self, inventory: &mut Inventory) -> bool {
+        let grid_empty = self.crafting.empty_into(inventory);
+        self.held = self.held.take().and_then(|held| inventory.add(held));
+        if !grid_empty || self.held.is_some() {
+            log::warn!("Inventory full, make room before closing it");
+            return false;
+        }
+        self.result = None;
+        self.open = false;
+        true
+    }
+
+    /// Closes without keeping the held stack or the grid, for when the world is left
+    pub fn discard(&mut self) {
+        self.held = None;
+        self.crafting = CraftingGrid::new(self.crafting.size());
+        self.result = None;
+        self.open = false;
+    }
+
+    /// Clicks the slot under the cursor, returns false if there is none
+    pub fn click(&mut self, inventory: &mut Inventory, recipes: &RecipeBook, secondary: bool) -> bool {
+        let Some(slot) = self.slot_at(inventory, self.cursor) else {
+            return false;
+        };
+
+        match slot {
+            ScreenSlot::Inventory(slot) => self.held = inventory.click(slot, self.held.take(), secondary),
+            ScreenSlot::Grid(slot) => self.held = self.crafting.slots_mut().click(slot, self.held.take(), secondary),
+            ScreenSlot::Result => self.take_result(recipes),
+        }
+        self.result = self.crafting.result(recipes);
+        true
+    }
+
+    /// Crafts into the cursor if it's empty or the result fits on the held stack
+    fn take_result(&mut self, recipes: &RecipeBook) {
+        let Some(result) = self.crafting.result(recipes) else {
+            return;
+        };
+
+        match &mut self.held {
+            None => self.held = self.crafting.craft(recipes),
+            Some(held) if held.item == result.item && held.space() >= result.count => {
+                held.count += self.crafting.craft(recipes).map_or(0, |crafted| crafted.count);
+            }
+            Some(_) => {}
+        }
+    }
+
+    /// Rebuilds every quad, call it when the inventory or the cursor change
+    pub fn update(&mut self, queue: &wgpu::Queue, inventory: &Inventory) {
+        let mut slots = Mesh::new();
+        let mut icons = Mesh::new();
+        let mut counts = Mesh::new();
+        // el panel cubre las rejillas con un margen
+        let mut panel_rect = self.slot_rect(inventory, ScreenSlot::Inventory(0));
+        for slot in self.screen_slots(inventory) {
+            let rect = self.slot_rect(inventory, slot);
+            push_quad(&mut slots, self.layout.quad(&rect));
+            panel_rect = panel_rect.union(&rect);
+
+            let stack = match slot {
+                ScreenSlot::Inventory(slot) => inventory.get(slot),
+                ScreenSlot::Grid(slot) => self.crafting.slots().get(slot),
+                ScreenSlot::Result => self.result.as_ref(),
+            };
+            if let Some(stack) = stack {
+                push_stack(&mut icons, &mut counts, stack, &rect, &self.layout);
+            }
+        }
+        if let Some(held) = &self.held {
+            let size = SLOT_SIZE * self.layout.scale();
+            let rect = Rect::new(self.cursor.0 - size / 2.0, self.cursor.1 - size / 2.0, size, size);
+            push_stack(&mut icons, &mut counts, held, &rect, &self.layout);
+        }
+
+        let mut panel = Mesh::new();
+        push_quad(&mut panel, self.layout.quad(&panel_rect.inflated(PANEL_PADDING * self.layout.scale())));
+
+        self.panel.set_mesh(queue, &panel);
+        self.slots.set_mesh(queue, &slots);
+        self.icons.set_mesh(queue, &icons);
+        self.counts.set_mesh(queue, &counts);
+    }
+
+    /// Back to front
+    pub(super) fn elements(&self) -> [&HUDElement; 4] {
+        [&self.panel, &self.slots, &self.icons, &self.counts]
+    }
+
+    fn screen_slots(&self, inventory: &Inventory) -> impl Iterator<Item = ScreenSlot> {
+        let grid_len = self.crafting.slots().len();
+        (0..inventory.len()).map(ScreenSlot::Inventory)
+            .chain((0..grid_len).map(ScreenSlot::Grid))
+            .chain([ScreenSlot::Result])
+    }
+
+    /// Inventory slots 0..HOTBAR_SIZE are the bottom row, the rest fill the rows above from
+    /// the top. The crafting grid goes over them, on the left, and its result on the right.
+    /// Everything is centered on the window.
+    fn slot_rect(&self, inventory: &Inventory, slot: ScreenSlot) -> Rect {
+        let rows = inventory.len().div_ceil(HOTBAR_SIZE) - 1;
+        let grid_size = self.crafting.size();
+        // centros en unidades de GUI desde el centro de la ventana, y hacia abajo
+        let height = (grid_size + rows + 1) as f32 * SLOT_SIZE + 2.0 * HOTBAR_GAP;
+        let grid_top = -height / 2.0 + SLOT_SIZE / 2.0;
+        let main_top = grid_top + grid_size as f32 * SLOT_SIZE + HOTBAR_GAP;
+        let hotbar = main_top + rows as f32 * SLOT_SIZE + HOTBAR_GAP;
+
+        let offset = match slot {
+            ScreenSlot::Inventory(slot) => {
+                let column = slot % HOTBAR_SIZE;
+                let x = (column as f32 - (HOTBAR_SIZE - 1) as f32 / 2.0) * SLOT_SIZE;
+                let y = if slot < HOTBAR_SIZE {
+                    hotbar
+                } else {
+                    let row = slot / HOTBAR_SIZE - 1;
+                    main_top + row as f32 * SLOT_SIZE
+                };
+                (x, y)
+            }
+            ScreenSlot::Grid(slot) => {
+                let (column, row) = (slot % grid_size, slot / grid_size);
+                let x = (column as f32 - (grid_size - 1) as f32 / 2.0 - CRAFTING_OFFSET) * SLOT_SIZE;
+                (x, grid_top + row as f32 * SLOT_SIZE)
+            }
+            ScreenSlot::Result => {
+                let y = grid_top + (grid_size - 1) as f32 * SLOT_SIZE / 2.0;
+                (CRAFTING_OFFSET * SLOT_SIZE, y)
+            }
+        };
+        self.layout.place(Anchor::Center, offset, (SLOT_SIZE, SLOT_SIZE))
+    }
+
+    fn slot_at(&self, inventory: &Inventory, cursor: (f32, f32)) -> Option<ScreenSlot> {
+        self.screen_slots(inventory).find(|&slot| self.slot_rect(inventory, slot).contains(cursor))
+    }
+}
@@ -0,0 +1,267 @@
+use winit::dpi::PhysicalSize;
+
+use crate::render::pipelines::hud::HUDVertex;
+
+use super::create_hud_quad;
+
+
+/// With the automatic GUI scale every unit of scale needs this many pixels of window,
+/// so the HUD fits in windows down to 320x240 at scale 1
+const AUTO_SCALE_WIDTH: u32 = 320;
+const AUTO_SCALE_HEIGHT: u32 = 240;
+/// `gui_scale` setting that picks the biggest scale the window fits
+pub const GUI_SCALE_AUTO: u32 = 0;
+
+
+/// Point of the window, and of the placed rectangle, that stays put when the window
+/// is resized
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// Where the anchor is as a fraction of the width and height, from the top left corner
+    fn fraction(self) -> (f32, f32) {
+        match self {
+            Anchor::TopLeft => (0.0, 0.0),
+            Anchor::Top => (0.5, 0.0),
+            Anchor::TopRight => (1.0, 0.0),
+            Anchor::Left => (0.0, 0.5),
+            Anchor::Center => (0.5, 0.5),
+            Anchor::Right => (1.0, 0.5),
+            Anchor::BottomLeft => (0.0, 1.0),
+            Anchor::Bottom => (0.5, 1.0),
+            Anchor::BottomRight => (1.0, 1.0),
+        }
+    }
+}
+
+
+/// Rectangle in pixels from the top left corner of the window, y grows downwards
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self { x, y, width, height }
+    }
+
+    pub fn center(&self) -> (f32, f32) {
+        (self.x + self.width / 2.0, self.y + self.height / 2.0)
+    }
+
+    pub fn contains(&self, (x, y): (f32, f32)) -> bool {
+        x >= self.x && y >= self.y && x < self.x + self.width && y < self.y + self.height
+    }
+
+    /// Same center, `factor` times the size
+    pub fn scaled(&self, factor: f32) -> Rect {
+        let (x, y) = self.center();
+        let (width, height) = (self.width * factor, self.height * factor);
+        Rect::new(x - width / 2.0, y - height / 2.0, width, height)
+    }
+
+    /// Smallest rectangle covering both
+    pub fn union(&self, other: &Rect) -> Rect {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        let right = (self.x + self.width).max(other.x + other.width);
+        let bottom = (self.y + self.height).max(other.y + other.height);
+        Rect::new(x, y, right - x, bottom - y)
+    }
+
+    /// Grown by `margin` on every side
+    pub fn inflated(&self, margin: f32) -> Rect {
+        Rect::new(self.x - margin, self.y - margin, self.width + 2.0 * margin, self.height + 2.0 * margin)
+    }
+}
+
+
+/// Places the HUD in GUI units: pixels multiplied by the GUI scale, so it keeps its
+/// proportions whatever the window size and aspect. Rebuilt on every resize.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Layout {
+    size: PhysicalSize<u32>,
+    /// Setting, `GUI_SCALE_AUTO` or the scale wanted
+    gui_scale: u32,
+    /// Pixels per GUI unit actually used
+    scale: f32,
+}
+
+impl Layout {
+    pub fn new(size: PhysicalSize<u32>, gui_scale: u32) -> Self {
+        let mut layout = Self { size, gui_scale, scale: 1.0 };
+        layout.update_scale();
+        layout
+    }
+
+    pub fn resize(&mut self, size: PhysicalSize<u32>) {
+        self.size = size;
+        self.update_scale();
+    }
+
+    pub fn set_gui_scale(&mut self, gui_scale: u32) {
+        self.gui_scale = gui_scale;
+        self.update_scale();
+    }
+
+    /// The scale asked for, capped to the biggest one the window fits
+    fn update_scale(&mut self) {
+        let fits = (self.size.width / AUTO_SCALE_WIDTH).min(self.size.height / AUTO_SCALE_HEIGHT).max(1);
+        let scale = if self.gui_scale == GUI_SCALE_AUTO { fits } else { self.gui_scale.min(fits) };
+        self.scale = scale as f32;
+    }
+
+    pub fn size(&self) -> PhysicalSize<u32> {
+        self.size
+    }
+
+    /// Pixels per GUI unit
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Rectangle `size` GUI units big with its `anchor` point on the same point of the
+    /// window, moved `offset` GUI units right and down
+    pub fn place(&self, anchor: Anchor, offset: (f32, f32), size: (f32, f32)) -> Rect {
+        let scale = self.scale;
+        self.place_pixels(anchor, (offset.0 * scale, offset.1 * scale), (size.0 * scale, size.1 * scale))
+    }
+
+    /// Same as `place` with the offset and size in pixels
+    pub fn place_pixels(&self, anchor: Anchor, offset: (f32, f32), size: (f32, f32)) -> Rect {
+        let (fx, fy) = anchor.fraction();
+        let x = self.size.width as f32 * fx - size.0 * fx + offset.0;
+        let y = self.size.height as f32 * fy - size.1 * fy + offset.1;
+        Rect::new(x, y, size.0, size.1)
+    }
+
+    /// Point in pixels to NDC
+    pub fn point_to_ndc(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        let (width, height) = self.size_f32();
+        (x / width * 2.0 - 1.0, 1.0 - y / height * 2.0)
+    }
+
+    /// Width and height in pixels to NDC
+    pub fn size_to_ndc(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        let (width, height) = self.size_f32();
+        (x / width * 2.0, y / height * 2.0)
+    }
+
+    /// Center and size in NDC, what `create_hud_quad` takes
+    pub fn rect_to_ndc(&self, rect: &Rect) -> (f32, f32, f32, f32) {
+        let (x, y) = self.point_to_ndc(rect.center());
+        let (width, height) = self.size_to_ndc((rect.width, rect.height));
+        (x, y, width, height)
+    }
+
+    /// Quad covering `rect` with the whole texture
+    pub fn quad(&self, rect: &Rect) -> [HUDVertex; 4] {
+        let (x, y, width, height) = self.rect_to_ndc(rect);
+        create_hud_quad(x, y, width, height)
+    }
+
+    fn size_f32(&self) -> (f32, f32) {
+        (self.size.width.max(1) as f32, self.size.height.max(1) as f32)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(width: u32, height: u32, gui_scale: u32) -> Layout {
+        Layout::new(PhysicalSize::new(width, height), gui_scale)
+    }
+
+    #[test]
+    fn auto_scale_fits_the_window() {
+        assert_eq!(layout(1920, 1080, GUI_SCALE_AUTO).scale(), 4.0);
+        assert_eq!(layout(1280, 720, GUI_SCALE_AUTO).scale(), 3.0);
+        assert_eq!(layout(800, 600, GUI_SCALE_AUTO).scale(), 2.0);
+        assert_eq!(layout(3840, 1080, GUI_SCALE_AUTO).scale(), 4.0);
+        // nunca menos de 1, aunque no quepa
+        assert_eq!(layout(200, 100, GUI_SCALE_AUTO).scale(), 1.0);
+    }
+
+    #[test]
+    fn fixed_scale_is_capped() {
+        assert_eq!(layout(1920, 1080, 2).scale(), 2.0);
+        assert_eq!(layout(1280, 720, 8).scale(), 3.0);
+
+        let mut layout = layout(1920, 1080, 3);
+        layout.resize(PhysicalSize::new(640, 480));
+        assert_eq!(layout.scale(), 2.0);
+        layout.set_gui_scale(1);
+        assert_eq!(layout.scale(), 1.0);
+    }
+
+    #[test]
+    fn anchors_follow_the_window() {
+        for (width, height) in [(1920, 1080), (1280, 720), (800, 600), (1080, 1920)] {
+            let layout = layout(width, height, GUI_SCALE_AUTO);
+            let scale = layout.scale();
+            let (w, h) = (width as f32, height as f32);
+
+            let center = layout.place(Anchor::Center, (0.0, 0.0), (10.0, 10.0));
+            assert_eq!(center.center(), (w / 2.0, h / 2.0));
+
+            let bottom = layout.place(Anchor::Bottom, (0.0, -2.0), (182.0, 22.0));
+            assert_eq!(bottom.center().0, w / 2.0);
+            assert_eq!(bottom.y + bottom.height, h - 2.0 * scale);
+            assert_eq!(bottom.width, 182.0 * scale);
+
+            let top_left = layout.place(Anchor::TopLeft, (1.0, 1.0), (4.0, 4.0));
+            assert_eq!((top_left.x, top_left.y), (scale, scale));
+
+            let bottom_right = layout.place(Anchor::BottomRight, (0.0, 0.0), (4.0, 4.0));
+            assert_eq!((bottom_right.x + bottom_right.width, bottom_right.y + bottom_right.height), (w, h));
+        }
+    }
+
+    #[test]
+    fn squares_stay_square_in_ndc() {
+        for (width, height) in [(1920, 1080), (800, 600), (600, 800)] {
+            let layout = layout(width, height, GUI_SCALE_AUTO);
+            let rect = layout.place(Anchor::Center, (0.0, 0.0), (20.0, 20.0));
+            let (x, y, w, h) = layout.rect_to_ndc(&rect);
+
+            assert_eq!((x, y), (0.0, 0.0));
+            // mismo tamaño en píxeles: el ancho en NDC es el alto por height / width
+            assert!((w - h * height as f32 / width as f32).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn pixels_to_ndc() {
+        let layout = layout(800, 600, GUI_SCALE_AUTO);
+        assert_eq!(layout.point_to_ndc((0.0, 0.0)), (-1.0, 1.0));
+        assert_eq!(layout.point_to_ndc((800.0, 600.0)), (1.0, -1.0));
+        assert_eq!(layout.point_to_ndc((400.0, 300.0)), (0.0, 0.0));
+    }
+
+    #[test]
+    fn rect_helpers() {
+        let rect = Rect::new(10.0, 20.0, 30.0, 40.0);
+        assert!(rect.contains((10.0, 20.0)));
+        assert!(!rect.contains((40.0, 20.0)));
+        assert_eq!(rect.scaled(0.5), Rect::new(17.5, 30.0, 15.0, 20.0));
+        assert_eq!(rect.union(&Rect::new(0.0, 0.0, 5.0, 5.0)), Rect::new(0.0, 0.0, 40.0, 60.0));
+        assert_eq!(rect.inflated(1.0), Rect::new(9.0, 19.0, 32.0, 42.0));
+    }
+}
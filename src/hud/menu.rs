@@ -0,0 +1,527 @@
+use crate::{input::{Action, Binding, InputMap}, world::MAX_WORLD_NAME, settings::{Settings, MAX_FOV, MAX_MOUSE_SENSITIVITY, MAX_RENDER_DISTANCE, MIN_FOV, MIN_MOUSE_SENSITIVITY, MIN_RENDER_DISTANCE}};
+
+use super::{layout::{Anchor, Layout}, text::Align, ui::{Ui, UiEvent, Widget, WidgetId, WidgetKind, LIST_ROW_HEIGHT}};
+
+
+/// Window sizes the resolution button goes through, smallest first
+pub const RESOLUTIONS: [(u16, u16); 5] = [(1280, 720), (1366, 768), (1600, 900), (1920, 1080), (2560, 1440)];
+
+/// Size of buttons, toggles and sliders, in GUI units
+const BUTTON_WIDTH: f32 = 150.0;
+const BUTTON_HEIGHT: f32 = 20.0;
+/// Height of titles and headers, in GUI units
+const LABEL_HEIGHT: f32 = 12.0;
+/// Distance between the centers of two rows, in GUI units
+const ROW_SPACING: f32 = 24.0;
+/// Horizontal distance from the center of the window to the center of each column
+/// of the settings screen, in GUI units
+const COLUMN_OFFSET: f32 = 78.0;
+/// Bindings shown at once, the rest are scrolled
+const BINDING_ROWS: usize = 8;
+/// Worlds shown at once in the world list
+const WORLD_ROWS: usize = 8;
+/// Width of the loading bar, in GUI units
+const PROGRESS_WIDTH: f32 = 200.0;
+/// Characters of a typed seed
+const MAX_SEED_LEN: usize = 20;
+
+
+/// What each menu widget does, handled by `State`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MenuAction {
+    Singleplayer,
+    QuitGame,
+    /// Row of the world list
+    Worlds,
+    WorldName,
+    WorldSeed,
+    CreateWorld,
+    PlayWorld,
+    DeleteWorld,
+    /// Back to the main menu
+    Back,
+    Resume,
+    OpenSettings,
+    SaveAndQuit,
+    Resolution,
+    Fullscreen,
+    RenderDistance,
+    Fov,
+    MouseSensitivity,
+    Vsync,
+    /// Row of the binding list, picks the action to bind next
+    Bindings,
+    ResetBindings,
+    /// Leaves the settings screen
+    Done,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MenuPage {
+    MainMenu,
+    WorldList,
+    Loading,
+    Pause,
+    Settings,
+}
+
+
+/// Every screen outside the game: main menu, world list, loading screen, pause menu and
+/// settings, drawn by `HUD::ui`. The widgets only change the menu, `State` applies what
+/// they report to the game.
+pub struct Menu {
+    pub ui: Ui<MenuAction>,
+    page: Option<MenuPage>,
+    /// Action waiting for the next key or button pressed
+    rebinding: Option<Action>,
+    /// Delete was clicked once for the selected world
+    confirm_delete: bool,
+    background: Option<WidgetId>,
+    title: Option<WidgetId>,
+    resolution: Option<WidgetId>,
+    bindings: Option<WidgetId>,
+    worlds: Option<WidgetId>,
+    world_name: Option<WidgetId>,
+    world_seed: Option<WidgetId>,
+    delete: Option<WidgetId>,
+    progress: Option<WidgetId>,
+    progress_label: Option<WidgetId>,
+}
+
+impl Menu {
+    pub fn new(layout: Layout) -> Self {
+        Self {
+            ui: Ui::new(layout),
+            page: None,
+            rebinding: None,
+            confirm_delete: false,
+            background: None,
+            title: None,
+            resolution: None,
+            bindings: None,
+            worlds: None,
+            world_name: None,
+            world_seed: None,
+            delete: None,
+            progress: None,
+            progress_label: None,
+        }
+    }
+
+    /// Page shown, `None` while playing
+    pub fn page(&self) -> Option<MenuPage> {
+        self.page
+    }
+
+    pub fn open(&mut self, page: MenuPage, settings: &Settings) {
+        self.page = Some(page);
+        self.rebinding = None;
+        self.build(settings);
+    }
+
+    pub fn close(&mut self) {
+        self.page = None;
+        self.rebinding = None;
+        self.clear();
+    }
+
+    /// The widgets follow their anchors, only the background has to grow with the window
+    pub fn set_layout(&mut self, layout: Layout) {
+        self.ui.set_layout(layout);
+        if let Some(background) = self.background {
+            let size = self.window_size();
+            self.ui.widget_mut(background).size = size;
+        }
+    }
+
+    pub fn rebinding(&self) -> Option<Action> {
+        self.rebinding
+    }
+
+    /// Waits for a binding for the action in `row` of the binding list
+    pub fn start_rebinding(&mut self, row: usize) {
+        let Some(&action) = Action::ALL.get(row) else {
+            return;
+        };
+        self.rebinding = Some(action);
+        if let Some(title) = self.title {
+            self.ui.widget_mut(title).kind = WidgetKind::Label {
+                text: format!("Press a key for {}, Escape cancels", action.label()),
+                align: Align::Center,
+            };
+        }
+    }
+
+    /// Binds `binding` to the action waiting for one, replacing its bindings. Escape
+    /// cancels. Returns true if `controls` changed.
+    pub fn capture_binding(&mut self, binding: Binding, controls: &mut InputMap) -> bool {
+        let Some(action) = self.rebinding.take() else {
+            return false;
+        };
+        let cancelled = binding == Binding::Key(winit::keyboard::KeyCode::Escape);
+        if !cancelled {
+            controls.set_bindings(action, vec![binding]);
+        }
+        self.set_title("Settings");
+        self.update_bindings(controls);
+        !cancelled
+    }
+
+    /// Rewrites the binding list, actions sharing a binding with another are marked with `!`
+    pub fn update_bindings(&mut self, controls: &InputMap) {
+        let Some(id) = self.bindings else {
+            return;
+        };
+        let items = binding_rows(controls);
+        if let WidgetKind::ScrollList { items: rows, selected, .. } = &mut self.ui.widget_mut(id).kind {
+            *rows = items;
+            *selected = None;
+        }
+    }
+
+    /// Shows the window size of the settings
+    pub fn update_resolution(&mut self, resolution: (u16, u16)) {
+        if let Some(id) = self.resolution {
+            self.ui.widget_mut(id).kind = WidgetKind::Button { label: resolution_label(resolution) };
+        }
+    }
+
+    /// Rows of the world list, nothing selected
+    pub fn set_worlds(&mut self, rows: Vec<String>) {
+        self.confirm_delete = false;
+        self.update_delete_label();
+        if let Some(id) = self.worlds {
+            self.ui.widget_mut(id).kind = WidgetKind::ScrollList { items: rows, selected: None, scroll: 0 };
+        }
+    }
+
+    /// Row of the world list selected
+    pub fn selected_world(&self) -> Option<usize> {
+        match &self.ui.widget(self.worlds?).kind {
+            WidgetKind::ScrollList { selected, .. } => *selected,
+            _ => None,
+        }
+    }
+
+    /// Another world was selected, delete has to be confirmed again
+    pub fn world_selected(&mut self) {
+        self.confirm_delete = false;
+        self.update_delete_label();
+    }
+
+    /// Name and seed typed for a new world
+    pub fn new_world(&self) -> (String, String) {
+        let text = |id: Option<WidgetId>| match id.map(|id| &self.ui.widget(id).kind) {
+            Some(WidgetKind::TextInput { text, .. }) => text.clone(),
+            _ => String::new(),
+        };
+        (text(self.world_name), text(self.world_seed))
+    }
+
+    /// Deleting takes two clicks, returns true on the second one
+    pub fn confirm_delete(&mut self) -> bool {
+        if self.confirm_delete {
+            self.confirm_delete = false;
+            self.update_delete_label();
+            return true;
+        }
+        self.confirm_delete = true;
+        self.update_delete_label();
+        false
+    }
+
+    /// Fills the loading bar, `value` goes from 0 to 1
+    pub fn set_progress(&mut self, value: f32, text: &str) {
+        if let Some(id) = self.progress {
+            self.ui.widget_mut(id).kind = WidgetKind::ProgressBar { value: value.clamp(0.0, 1.0) };
+        }
+        if let Some(id) = self.progress_label {
+            self.ui.widget_mut(id).kind = WidgetKind::Label { text: text.to_string(), align: Align::Center };
+        }
+    }
+
+    /// Replaces the title of the page, for errors
+    pub fn show_message(&mut self, text: &str) {
+        self.set_title(text);
+    }
+
+    /// Events of the widgets since the last call
+    pub fn drain_events(&mut self) -> Vec<UiEvent<MenuAction>> {
+        self.ui.drain_events()
+    }
+
+    /// Removes the widgets and forgets their ids
+    fn clear(&mut self) {
+        self.ui.clear();
+        self.confirm_delete = false;
+        self.background = None;
+        self.title = None;
+        self.resolution = None;
+        self.bindings = None;
+        self.worlds = None;
+        self.world_name = None;
+        self.world_seed = None;
+        self.delete = None;
+        self.progress = None;
+        self.progress_label = None;
+    }
+
+    fn update_delete_label(&mut self) {
+        let label = if self.confirm_delete { "Click again to delete" } else { "Delete" };
+        if let Some(id) = self.delete {
+            self.ui.widget_mut(id).kind = WidgetKind::Button { label: label.to_string() };
+        }
+    }
+
+    /// Size of the window in GUI units
+    fn window_size(&self) -> (f32, f32) {
+        let layout = self.ui.layout();
+        let size = layout.size();
+        (size.width as f32 / layout.scale(), size.height as f32 / layout.scale())
+    }
+
+    fn set_title(&mut self, text: &str) {
+        if let Some(title) = self.title {
+            self.ui.widget_mut(title).kind = WidgetKind::Label { text: text.to_string(), align: Align::Center };
+        }
+    }
+
+    fn build(&mut self, settings: &Settings) {
+        self.clear();
+        // fondo oscuro sobre todo el juego
+        self.background = Some(self.ui.push(Widget::panel().at(Anchor::Center, (0.0, 0.0), self.window_size())));
+
+        match self.page {
+            Some(MenuPage::MainMenu) => self.build_main_menu(),
+            Some(MenuPage::WorldList) => self.build_world_list(),
+            Some(MenuPage::Loading) => self.build_loading(),
+            Some(MenuPage::Pause) => self.build_pause(),
+            Some(MenuPage::Settings) => self.build_settings(settings),
+            None => {}
+        }
+    }
+
+    fn build_main_menu(&mut self) {
+        let button = (BUTTON_WIDTH, BUTTON_HEIGHT);
+        self.title = Some(self.ui.push(Widget::label("wgpucraft", Align::Center)
+            .at(Anchor::Center, (0.0, -2.0 * ROW_SPACING), (BUTTON_WIDTH, LABEL_HEIGHT))));
+        self.ui.push(Widget::button(MenuAction::Singleplayer, "Singleplayer").at(Anchor::Center, (0.0, -ROW_SPACING), button));
+        self.ui.push(Widget::button(MenuAction::OpenSettings, "Settings").at(Anchor::Center, (0.0, 0.0), button));
+        self.ui.push(Widget::button(MenuAction::QuitGame, "Quit").at(Anchor::Center, (0.0, ROW_SPACING), button));
+    }
+
+    fn build_world_list(&mut self) {
+        let button = (BUTTON_WIDTH, BUTTON_HEIGHT);
+        let list_height = WORLD_ROWS as f32 * LIST_ROW_HEIGHT;
+        let list_top = -92.0;
+        let left = |y: f32| (-COLUMN_OFFSET, y);
+        let right = |y: f32| (COLUMN_OFFSET, y);
+
+        self.title = Some(self.ui.push(Widget::label("Select world", Align::Center)
+            .at(Anchor::Center, (0.0, list_top - LABEL_HEIGHT), (2.0 * BUTTON_WIDTH, LABEL_HEIGHT))));
+        self.worlds = Some(self.ui.push(Widget::scroll_list(MenuAction::Worlds, Vec::new())
+            .at(Anchor::Center, (0.0, list_top + list_height / 2.0), (2.0 * COLUMN_OFFSET + BUTTON_WIDTH, list_height))));
+
+        let row = |i: usize| list_top + list_height + i as f32 * ROW_SPACING + BUTTON_HEIGHT / 2.0 + 4.0;
+        self.ui.push(Widget::button(MenuAction::PlayWorld, "Play selected").at(Anchor::Center, left(row(0)), button));
+        self.delete = Some(self.ui.push(Widget::button(MenuAction::DeleteWorld, "Delete").at(Anchor::Center, right(row(0)), button)));
+        self.world_name = Some(self.ui.push(Widget::text_input(MenuAction::WorldName, "", "World name", MAX_WORLD_NAME)
+            .at(Anchor::Center, left(row(1)), button)));
+        self.world_seed = Some(self.ui.push(Widget::text_input(MenuAction::WorldSeed, "", "Seed, empty for random", MAX_SEED_LEN)
+            .at(Anchor::Center, right(row(1)), button)));
+        self.ui.push(Widget::button(MenuAction::CreateWorld, "Create new world").at(Anchor::Center, left(row(2)), button));
+        self.ui.push(Widget::button(MenuAction::Back, "Back").at(Anchor::Center, right(row(2)), button));
+    }
+
+    fn build_loading(&mut self) {
+        self.title = Some(self.ui.push(Widget::label("Generating terrain", Align::Center)
+            .at(Anchor::Center, (0.0, -ROW_SPACING), (PROGRESS_WIDTH, LABEL_HEIGHT))));
+        self.progress = Some(self.ui.push(Widget::progress_bar(0.0)
+            .at(Anchor::Center, (0.0, 0.0), (PROGRESS_WIDTH, LABEL_HEIGHT))));
+        self.progress_label = Some(self.ui.push(Widget::label("", Align::Center)
+            .at(Anchor::Center, (0.0, LABEL_HEIGHT + 4.0), (PROGRESS_WIDTH, LABEL_HEIGHT))));
+    }
+
+    fn build_pause(&mut self) {
+        let button = (BUTTON_WIDTH, BUTTON_HEIGHT);
+        self.title = Some(self.ui.push(Widget::label("Game paused", Align::Center)
+            .at(Anchor::Center, (0.0, -2.0 * ROW_SPACING), (BUTTON_WIDTH, LABEL_HEIGHT))));
+        self.ui.push(Widget::button(MenuAction::Resume, "Resume").at(Anchor::Center, (0.0, -ROW_SPACING), button));
+        self.ui.push(Widget::button(MenuAction::OpenSettings, "Settings").at(Anchor::Center, (0.0, 0.0), button));
+        self.ui.push(Widget::button(MenuAction::SaveAndQuit, "Save and quit").at(Anchor::Center, (0.0, ROW_SPACING), button));
+    }
+
+    fn build_settings(&mut self, settings: &Settings) {
+        let graphics = &settings.graphics;
+        let button = (BUTTON_WIDTH, BUTTON_HEIGHT);
+        let row = |i: usize| -80.0 + i as f32 * ROW_SPACING;
+        let left = |i: usize| (-COLUMN_OFFSET, row(i));
+        let right = |y: f32| (COLUMN_OFFSET, y);
+
+        self.title = Some(self.ui.push(Widget::label("Settings", Align::Center)
+            .at(Anchor::Center, (0.0, row(0) - ROW_SPACING), (2.0 * BUTTON_WIDTH, LABEL_HEIGHT))));
+
+        // gráficos y control en la columna izquierda
+        self.resolution = Some(self.ui.push(Widget::button(MenuAction::Resolution, &resolution_label(graphics.resolution))
+            .at(Anchor::Center, left(0), button)));
+        self.ui.push(Widget::toggle(MenuAction::Fullscreen, "Fullscreen", graphics.fullscreen)
+            .at(Anchor::Center, left(1), button));
+        self.ui.push(Widget::slider(
+            MenuAction::RenderDistance, "Render distance", graphics.render_distance as f32,
+            MIN_RENDER_DISTANCE as f32, MAX_RENDER_DISTANCE as f32, 1.0,
+        ).at(Anchor::Center, left(2), button));
+        self.ui.push(Widget::slider(MenuAction::Fov, "FOV", graphics.fov, MIN_FOV, MAX_FOV, 1.0)
+            .at(Anchor::Center, left(3), button));
+        self.ui.push(Widget::slider(
+            MenuAction::MouseSensitivity, "Sensitivity", settings.mouse_sensitivity,
+            MIN_MOUSE_SENSITIVITY, MAX_MOUSE_SENSITIVITY, 0.1,
+        ).at(Anchor::Center, left(4), button));
+        self.ui.push(Widget::toggle(MenuAction::Vsync, "VSync", graphics.vsync)
+            .at(Anchor::Center, left(5), button));
+
+        // controles en la derecha
+        let list_height = BINDING_ROWS as f32 * LIST_ROW_HEIGHT;
+        let list_top = row(0) + LABEL_HEIGHT / 2.0;
+        self.ui.push(Widget::label("Key bindings", Align::Center)
+            .at(Anchor::Center, right(row(0)), (BUTTON_WIDTH, LABEL_HEIGHT)));
+        self.bindings = Some(self.ui.push(Widget::scroll_list(MenuAction::Bindings, binding_rows(&settings.controls))
+            .at(Anchor::Center, right(list_top + list_height / 2.0), (BUTTON_WIDTH, list_height))));
+        self.ui.push(Widget::button(MenuAction::ResetBindings, "Reset bindings").at(Anchor::Center, right(row(5)), button));
+
+        self.ui.push(Widget::button(MenuAction::Done, "Done").at(Anchor::Center, (0.0, row(7)), button));
+    }
+}
+
+
+/// Size that follows `current` in `RESOLUTIONS`, back to the first after the last
+pub fn next_resolution(current: (u16, u16)) -> (u16, u16) {
+    let area = |(width, height): (u16, u16)| width as u32 * height as u32;
+    RESOLUTIONS.iter()
+        .find(|&&resolution| area(resolution) > area(current))
+        .copied()
+        .unwrap_or(RESOLUTIONS[0])
+}
+
+fn resolution_label((width, height): (u16, u16)) -> String {
+    format!("Resolution: {}x{}", width, height)
+}
+
+/// One row per action with its bindings
+fn binding_rows(controls: &InputMap) -> Vec<String> {
+    let conflicts: Vec<Binding> = controls.conflicts().into_iter().map(|(binding, _)| binding).collect();
+    Action::ALL.iter().map(|&action| {
+        let bindings = controls.bindings(action);
+        let names: Vec<String> = bindings.iter().map(|binding| binding.label()).collect();
+        let mark = if bindings.iter().any(|binding| conflicts.contains(binding)) { "! " } else { "" };
+        format!("{}{}: {}", mark, action.label(), if names.is_empty() { "-".to_string() } else { names.join(", ") })
+    }).collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use winit::{dpi::PhysicalSize, keyboard::KeyCode};
+
+    use super::*;
+
+    fn menu() -> Menu {
+        Menu::new(Layout::new(PhysicalSize::new(1280, 720), 0))
+    }
+
+    fn list_items(menu: &Menu) -> Vec<String> {
+        match &menu.ui.widget(menu.bindings.unwrap()).kind {
+            WidgetKind::ScrollList { items, .. } => items.clone(),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn resolutions_cycle() {
+        assert_eq!(next_resolution((1280, 720)), (1366, 768));
+        assert_eq!(next_resolution((1000, 700)), (1280, 720));
+        assert_eq!(next_resolution((2560, 1440)), (1280, 720));
+        assert_eq!(next_resolution((3840, 2160)), (1280, 720));
+    }
+
+    #[test]
+    fn labels_are_readable() {
+        assert_eq!(Action::MoveForward.label(), "Move forward");
+        assert_eq!(Action::HotbarSlot1.label(), "Hotbar slot 1");
+        assert_eq!(Binding::Key(KeyCode::KeyW).label(), "W");
+        assert_eq!(Binding::Key(KeyCode::Digit3).label(), "3");
+        assert_eq!(Binding::Key(KeyCode::Escape).label(), "Escape");
+    }
+
+    #[test]
+    fn pages_are_rebuilt() {
+        let settings = Settings::default();
+        let mut menu = menu();
+        assert_eq!(menu.page(), None);
+
+        menu.open(MenuPage::Pause, &settings);
+        assert_eq!(menu.page(), Some(MenuPage::Pause));
+        assert!(menu.bindings.is_none());
+
+        menu.open(MenuPage::Settings, &settings);
+        assert_eq!(list_items(&menu).len(), Action::ALL.len());
+        assert_eq!(list_items(&menu)[0], "Move forward: W, ArrowUp");
+
+        menu.open(MenuPage::WorldList, &settings);
+        assert!(menu.bindings.is_none());
+        menu.set_worlds(vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(menu.selected_world(), None);
+        menu.ui.set_focus(menu.world_seed);
+        menu.ui.type_text("123");
+        assert_eq!(menu.new_world(), (String::new(), "123".to_string()));
+
+        menu.close();
+        assert_eq!(menu.page(), None);
+    }
+
+    #[test]
+    fn delete_needs_two_clicks() {
+        let mut menu = menu();
+        menu.open(MenuPage::WorldList, &Settings::default());
+        assert!(!menu.confirm_delete());
+        assert!(menu.confirm_delete());
+
+        // cambiar de mundo pide confirmar otra vez
+        assert!(!menu.confirm_delete());
+        menu.world_selected();
+        assert!(!menu.confirm_delete());
+    }
+
+    #[test]
+    fn background_follows_the_window() {
+        let mut menu = menu();
+        menu.open(MenuPage::MainMenu, &Settings::default());
+        menu.set_layout(Layout::new(PhysicalSize::new(1920, 1080), 0));
+        let background = menu.ui.rect(menu.background.unwrap());
+        assert_eq!((background.width, background.height), (1920.0, 1080.0));
+    }
+
+    #[test]
+    fn rebinding_replaces_the_bindings() {
+        let mut settings = Settings::default();
+        let mut menu = menu();
+        menu.open(MenuPage::Settings, &settings);
+
+        menu.start_rebinding(0);
+        assert_eq!(menu.rebinding(), Some(Action::MoveForward));
+        assert!(menu.capture_binding(Binding::Key(KeyCode::KeyI), &mut settings.controls));
+        assert_eq!(settings.controls.bindings(Action::MoveForward), &[Binding::Key(KeyCode::KeyI)]);
+        assert_eq!(menu.rebinding(), None);
+        assert_eq!(list_items(&menu)[0], "Move forward: I");
+
+        // una tecla ya usada queda marcada en las dos acciones
+        menu.start_rebinding(1);
+        assert!(menu.capture_binding(Binding::Key(KeyCode::KeyI), &mut settings.controls));
+        assert!(list_items(&menu)[0].starts_with("! "));
+        assert!(list_items(&menu)[1].starts_with("! "));
+
+        // escape cancela sin tocar nada
+        menu.start_rebinding(2);
+        assert!(!menu.capture_binding(Binding::Key(KeyCode::Escape), &mut settings.controls));
+        assert_eq!(settings.controls.bindings(Action::MoveLeft)[0], Binding::Key(KeyCode::KeyA));
+    }
+}
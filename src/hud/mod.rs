@@ -1,35 +1,99 @@
-use icons_atlas::IconType;
+use inventory_screen::InventoryScreen;
+use layout::{Anchor, Layout, Rect};
+use ui::UiRenderer;
+use text::{push_text, Align, TextRenderer, TextStyle, GLYPH_HEIGHT, GLYPH_WIDTH, GUI_TEXT_SCALE};
 
-use crate::render::{atlas::Atlas, mesh::Mesh, model::Model, pipelines::{hud::{create_hud_pipeline, HUDVertex}, GlobalsLayouts}, renderer::{self, Draw, Renderer}, texture::Texture};
+use crate::{item::{inventory::{Inventory, HOTBAR_SIZE, INVENTORY_SIZE}, ItemStack}, render::{mesh::Mesh, model::DynamicModel, pipelines::{hud::{create_hud_pipeline, HUDVertex}, GlobalsLayouts}, renderer::{Draw, Renderer}, texture::Texture}};
 
 
 
+pub mod debug;
 pub mod icons_atlas;
+pub mod inventory_screen;
+pub mod layout;
+pub mod menu;
+pub mod text;
+pub mod ui;
 
 
+/// Tint drawn over the whole screen when the camera is under water
+const WATER_OVERLAY_COLOR: [u8; 4] = [20, 60, 160, 90];
+
+/// Side of a hotbar slot, in GUI units
+const SLOT_SIZE: f32 = 20.0;
+/// Space between the hotbar and the bottom of the window, in GUI units
+const HOTBAR_MARGIN: f32 = 2.0;
+/// Side of the crosshair, in GUI units
+const CROSSHAIR_SIZE: f32 = 8.0;
+/// Icon size relative to its slot
+const ICON_SCALE: f32 = 0.7;
+/// Highlight size relative to the slot, slightly bigger so it frames it
+const HIGHLIGHT_SCALE: f32 = 1.1;
+/// Size in pixels of the generated highlight texture and its border
+const HIGHLIGHT_TEXTURE_SIZE: u32 = 24;
+const HIGHLIGHT_BORDER: u32 = 2;
+
 
 
 pub struct HUD {
     pub(crate) pipeline: wgpu::RenderPipeline,
-    pub crosshair: HUDElement,
-    pub widget: HUDElement,
-    pub icons_atlas: HUDElement,
-    pub selected_icon: IconType,
-
+    crosshair: HUDElement,
+    hotbar: HUDElement,
+    hotbar_icons: HUDElement,
+    hotbar_counts: HUDElement,
+    hotbar_highlight: HUDElement,
+    water_overlay: HUDElement,
+    /// Where everything goes for the current window size and GUI scale
+    layout: Layout,
+    /// Draws the water tint, set every frame from the player
+    pub underwater: bool,
+    /// Draws the crosshair and the hotbar, off in the menus without a world
+    pub show_game_hud: bool,
+    pub inventory_screen: InventoryScreen,
+    /// Menus built with `ui::Ui`, over the game and the inventory
+    pub ui: UiRenderer,
+    /// Shows the F3 debug overlay
+    pub show_debug: bool,
+    /// Text queued every frame, drawn over everything else
+    pub text: TextRenderer,
 }
 
+/// A texture and the quads drawn with it
 struct HUDElement {
-    texture: Texture,
+    // la textura tiene que vivir tanto como el bind group
+    _texture: Texture,
     bind_group: wgpu::BindGroup,
-    model: Model<HUDVertex>,
+    model: DynamicModel<HUDVertex>,
+}
+
+impl HUDElement {
+    fn new(renderer: &Renderer, global_layout: &GlobalsLayouts, texture: Texture, max_quads: usize) -> Self {
+        let bind_group = global_layout.bind_hud_texture(
+            &renderer.device,
+            &texture,
+            None, // Usa el sampler por defecto
+        );
+
+        Self {
+            _texture: texture,
+            bind_group,
+            model: DynamicModel::new(&renderer.device, max_quads * 6),
+        }
+    }
+
+    fn set_mesh(&mut self, queue: &wgpu::Queue, mesh: &Mesh<HUDVertex>) {
+        self.model.update(queue, mesh, 0);
+    }
 }
 
 
 impl HUD {
+    /// `gui_scale` is the setting, see `Layout`
     pub fn new(
         renderer: &Renderer,
         global_layout: &GlobalsLayouts,
         shader: wgpu::ShaderModule,
+        gui_scale: u32,
     ) -> Self {
         // Cargar texturas
         let crosshair_bytes = include_bytes!("../../assets/images/crosshair.png");
@@ -38,109 +102,123 @@ impl HUD {
         let icons_bytes = include_bytes!("../../assets/images/icons_atlas.png");
 
         let crosshair_tex = Texture::from_bytes(&renderer.device, &renderer.queue, crosshair_bytes, "crosshair.png").unwrap();
-        let widget_tex = Texture::from_bytes(&renderer.device, &renderer.queue, widget_bytes, "crosshair.png").unwrap();
+        let widget_tex = Texture::from_bytes(&renderer.device, &renderer.queue, widget_bytes, "widget_window.png").unwrap();
         let icons_atlas_tex = Texture::from_bytes(&renderer.device, &renderer.queue, icons_bytes, "icons_atlas.png").unwrap();
+        let highlight_tex = Texture::from_image(&renderer.device, &renderer.queue, &highlight_image(), Some("hotbar highlight")).unwrap();
+        let water_overlay_img = image::DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(1, 1, image::Rgba(WATER_OVERLAY_COLOR)));
+        let water_overlay_tex = Texture::from_image(&renderer.device, &renderer.queue, &water_overlay_img, Some("water overlay")).unwrap();
 
 
         // Crear pipeline usando el hud_layout
         let pipeline = create_hud_pipeline(
-            &renderer.device, 
-            &global_layout, // Usamos el layout específico
-            shader, 
-            &renderer.config
-        );
-
-        // Crear bind groups usando el nuevo método
-        let crosshair_bind_group = global_layout.bind_hud_texture(
-            &renderer.device,
-            &crosshair_tex,
-            None, // Usa el sampler por defecto
-        );
-
-        let widget_bind_group = global_layout.bind_hud_texture(
-            &renderer.device,
-            &widget_tex,
-            None,
-        );
-
-        let icons_bind_group = global_layout.bind_hud_texture(
             &renderer.device,
-            &icons_atlas_tex,
-            None,
+            global_layout, // Usamos el layout específico
+            shader,
+            &renderer.config
         );
 
+        let crosshair = HUDElement::new(renderer, global_layout, crosshair_tex, 1);
+        let hotbar = HUDElement::new(renderer, global_layout, widget_tex, HOTBAR_SIZE);
+        let hotbar_icons = HUDElement::new(renderer, global_layout, icons_atlas_tex, HOTBAR_SIZE);
+        let hotbar_counts = HUDElement::new(renderer, global_layout, text::font_texture(renderer), HOTBAR_SIZE * 4);
+        let hotbar_highlight = HUDElement::new(renderer, global_layout, highlight_tex, 1);
+        let mut water_overlay = HUDElement::new(renderer, global_layout, water_overlay_tex, 1);
+        let layout = Layout::new(renderer.size, gui_scale);
+        let inventory_screen = InventoryScreen::new(renderer, global_layout, INVENTORY_SIZE, layout);
+        let text = TextRenderer::new(renderer, global_layout, layout);
+        let ui = UiRenderer::new(renderer, global_layout);
 
+        let mut mesh = Mesh::new();
+        push_quad(&mut mesh, create_hud_quad(0.0, 0.0, 2.0, 2.0)); // Toda la pantalla
+        water_overlay.set_mesh(&renderer.queue, &mesh);
 
-        let selected_icon = IconType::ROCK; // Puedes cambiar esto según tu lógica
-        // Crear geometría para los elementos del HUD
-        let (crosshair_verts, crosshair_indices) = create_hud_quad(0.0, 0.0, 0.06, 0.06); // Ajusta tamaño según necesites
-        let (widget_verts, widget_indices) = create_hud_quad(0.85, -0.85, 0.2, 0.2); // Posición y tamaño del widget
-        let (icon_verts, icon_indices) = selected_icon.get_vertex_quad(0.85, -0.85, 0.16,0.16); // Posición del icono en el widget
-
-
-
-        
-        // Crear modelos
-        let crosshair_model = Model::new(&renderer.device, &Mesh {
-            verts: crosshair_verts,
-            indices: crosshair_indices,
-        }).unwrap();
-
-        let widget_model = Model::new(&renderer.device, &Mesh {
-            verts: widget_verts,
-            indices: widget_indices,
-        }).unwrap();
 
-        let icon_model = Model::new(&renderer.device, &Mesh {
-            verts: icon_verts.into_iter().collect(), // Convertimos el array a Vec
-            indices: icon_indices.into_iter().collect(), // Convertimos el array a Vec
-        }).unwrap();
-
-
-        // Crear bind groups
-        let crosshair = HUDElement {
-            texture: crosshair_tex,
-            bind_group: crosshair_bind_group,
-            model: crosshair_model,
+        let mut hud = Self {
+            pipeline,
+            crosshair,
+            hotbar,
+            hotbar_icons,
+            hotbar_counts,
+            hotbar_highlight,
+            water_overlay,
+            layout,
+            underwater: false,
+            show_game_hud: true,
+            inventory_screen,
+            ui,
+            show_debug: false,
+            text,
         };
+        hud.update_crosshair(&renderer.queue);
+        hud
+    }
 
-        let widget = HUDElement {
-            texture: widget_tex,
-            bind_group: widget_bind_group,
-            model: widget_model,
-        };
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
 
-        let icons_atlas = HUDElement {
-            texture: icons_atlas_tex,
-            bind_group: icons_bind_group,
-            model: icon_model,
-        };
+    fn update_crosshair(&mut self, queue: &wgpu::Queue) {
+        let rect = self.layout.place(Anchor::Center, (0.0, 0.0), (CROSSHAIR_SIZE, CROSSHAIR_SIZE));
+        let mut mesh = Mesh::new();
+        push_quad(&mut mesh, self.layout.quad(&rect));
+        self.crosshair.set_mesh(queue, &mesh);
+    }
 
+    /// Rebuilds the hotbar quads, call it when the slots or the selection change
+    pub fn update_hotbar(&mut self, queue: &wgpu::Queue, inventory: &Inventory) {
+        let mut slots = Mesh::new();
+        let mut icons = Mesh::new();
+        let mut counts = Mesh::new();
+        for (i, slot) in inventory.hotbar().iter().enumerate() {
+            let rect = self.slot_rect(i);
+            push_quad(&mut slots, self.layout.quad(&rect));
+
+            if let Some(stack) = slot {
+                push_stack(&mut icons, &mut counts, stack, &rect, &self.layout);
+            }
+        }
 
+        let mut highlight = Mesh::new();
+        let rect = self.slot_rect(inventory.selected()).scaled(HIGHLIGHT_SCALE);
+        push_quad(&mut highlight, self.layout.quad(&rect));
 
-        Self {
-            pipeline,
-            crosshair,
-            widget,
-            icons_atlas,
-            selected_icon,
+        self.hotbar.set_mesh(queue, &slots);
+        self.hotbar_icons.set_mesh(queue, &icons);
+        self.hotbar_counts.set_mesh(queue, &counts);
+        self.hotbar_highlight.set_mesh(queue, &highlight);
+    }
+
+    /// Rebuilds the hotbar and, if it's open, the inventory screen
+    pub fn update_inventory(&mut self, queue: &wgpu::Queue, inventory: &Inventory) {
+        self.update_hotbar(queue, inventory);
+        if self.inventory_screen.open {
+            self.inventory_screen.update(queue, inventory);
         }
     }
 
-    pub fn update(&mut self, renderer: &Renderer) {
+    pub fn resize(&mut self, queue: &wgpu::Queue, new_size: winit::dpi::PhysicalSize<u32>, inventory: &Inventory) {
+        self.layout.resize(new_size);
+        self.update_layout(queue, inventory);
+    }
 
-        // Regenerar la geometría del icono seleccionado
-        let (icon_verts, icon_indices) = self.selected_icon
-            .get_vertex_quad(0.85, -0.85, 0.16, 0.16);
+    /// Changes the GUI scale setting, see `Layout`
+    pub fn set_gui_scale(&mut self, queue: &wgpu::Queue, gui_scale: u32, inventory: &Inventory) {
+        self.layout.set_gui_scale(gui_scale);
+        self.update_layout(queue, inventory);
+    }
 
-        // Actualizar el modelo del atlas de iconos
-        self.icons_atlas.model = Model::new(
-            &renderer.device,
-            &Mesh {
-                verts: icon_verts.into_iter().collect(),
-                indices: icon_indices.into_iter().collect(),
-            }
-        ).expect("Failed to update icon model");
+    /// Places everything again after the layout changed
+    fn update_layout(&mut self, queue: &wgpu::Queue, inventory: &Inventory) {
+        self.inventory_screen.set_layout(self.layout);
+        self.text.set_layout(self.layout);
+        self.update_crosshair(queue);
+        self.update_inventory(queue, inventory);
+    }
+
+    /// Hotbar slots are centered along the bottom edge
+    fn slot_rect(&self, slot: usize) -> Rect {
+        let x = (slot as f32 - (HOTBAR_SIZE - 1) as f32 / 2.0) * SLOT_SIZE;
+        self.layout.place(Anchor::Bottom, (x, -HOTBAR_MARGIN), (SLOT_SIZE, SLOT_SIZE))
     }
 }
 
@@ -149,9 +227,16 @@ impl Draw for HUD {
 
         render_pass.set_pipeline(&self.pipeline);
         render_pass.set_bind_group(1, globals, &[]);
-        
-        // Dibujar elementos del HUD
-        for element in &[&self.crosshair, &self.widget, &self.icons_atlas, ] {
+
+        // Dibujar elementos del HUD, el tinte del agua queda debajo de todo
+        let overlay = self.underwater.then_some(&self.water_overlay);
+        let elements = [&self.crosshair, &self.hotbar, &self.hotbar_icons, &self.hotbar_counts, &self.hotbar_highlight];
+        let elements = self.show_game_hud.then_some(elements).into_iter().flatten();
+        // el inventario abierto tapa la barra
+        let screen = self.inventory_screen.open.then(|| self.inventory_screen.elements()).into_iter().flatten();
+        let ui = self.ui.visible.then(|| self.ui.elements()).into_iter().flatten();
+        let text = [self.text.element()];
+        for element in overlay.into_iter().chain(elements).chain(screen).chain(ui).chain(text) {
             render_pass.set_bind_group(0, &element.bind_group, &[]);
             render_pass.set_vertex_buffer(0, element.model.vbuf().slice(..));
             render_pass.set_index_buffer(
@@ -160,46 +245,98 @@ impl Draw for HUD {
             );
             render_pass.draw_indexed(0..element.model.num_indices as u32, 0, 0..1);
         }
-    
-        
+
+
         Ok(())
-    } 
+    }
+}
+
+
+/// Appends a quad from `create_hud_quad` or `IconType::get_vertex_quad`
+fn push_quad(mesh: &mut Mesh<HUDVertex>, verts: impl IntoIterator<Item = HUDVertex>) {
+    let base_index = mesh.verts.len() as u16;
+    mesh.verts.extend(verts);
+    mesh.push_indices(&[base_index, base_index + 1, base_index + 2, base_index + 2, base_index + 3, base_index]);
+}
+
+
+/// Icon of the stack centered on the slot and its count, if it's more than one, in the
+/// bottom right corner
+fn push_stack(icons: &mut Mesh<HUDVertex>, counts: &mut Mesh<HUDVertex>, stack: &ItemStack, slot: &Rect, layout: &Layout) {
+    if let Some(icon) = stack.item.icon() {
+        let (x, y, width, height) = layout.rect_to_ndc(&slot.scaled(ICON_SCALE));
+        let (verts, _) = icon.get_vertex_quad(x, y, width, height);
+        push_quad(icons, verts);
+    }
+    if stack.count <= 1 {
+        return;
+    }
+
+    let style = TextStyle { align: Align::Right, scale: layout.scale() * GUI_TEXT_SCALE, ..Default::default() };
+    let corner = (slot.x + slot.width, slot.y + slot.height - GLYPH_HEIGHT * style.scale);
+    let glyph = layout.size_to_ndc((GLYPH_WIDTH, GLYPH_HEIGHT));
+    push_text(counts, &stack.count.to_string(), layout.point_to_ndc(corner), glyph, &style);
+}
+
+
+/// White frame with a transparent center
+fn highlight_image() -> image::DynamicImage {
+    let size = HIGHLIGHT_TEXTURE_SIZE;
+    let image = image::RgbaImage::from_fn(size, size, |x, y| {
+        let border = x < HIGHLIGHT_BORDER || y < HIGHLIGHT_BORDER || x >= size - HIGHLIGHT_BORDER || y >= size - HIGHLIGHT_BORDER;
+        if border { image::Rgba([255, 255, 255, 255]) } else { image::Rgba([0, 0, 0, 0]) }
+    });
+    image::DynamicImage::ImageRgba8(image)
+}
+
+
+/// Quad showing the `[u_min, v_min, u_max, v_max]` part of its texture, the right way up and tinted
+fn create_uv_quad(center_x: f32, center_y: f32, width: f32, height: f32, uv: [f32; 4], color: [f32; 4]) -> [HUDVertex; 4] {
+    let half_w = width / 2.0;
+    let half_h = height / 2.0;
+
+    [
+        HUDVertex { position: [center_x - half_w, center_y - half_h], uv: [uv[0], uv[3]], color },
+        HUDVertex { position: [center_x + half_w, center_y - half_h], uv: [uv[2], uv[3]], color },
+        HUDVertex { position: [center_x + half_w, center_y + half_h], uv: [uv[2], uv[1]], color },
+        HUDVertex { position: [center_x - half_w, center_y + half_h], uv: [uv[0], uv[1]], color },
+    ]
 }
 
 
 pub fn create_hud_quad(
-    center_x: f32, 
+    center_x: f32,
     center_y: f32,
     width: f32,
     height: f32,
-) -> (Vec<HUDVertex>, Vec<u16>) {
+) -> [HUDVertex; 4] {
     let half_w = width / 2.0;
     let half_h = height / 2.0;
-    
-    let vertices = vec![
+
+    [
         // Top Left
         HUDVertex {
             position: [center_x - half_w, center_y - half_h],
             uv: [0.0, 0.0],
+            color: HUDVertex::WHITE,
         },
         // Top Right
         HUDVertex {
             position: [center_x + half_w, center_y - half_h],
             uv: [1.0, 0.0],
+            color: HUDVertex::WHITE,
         },
         // Bottom Right
         HUDVertex {
             position: [center_x + half_w, center_y + half_h],
             uv: [1.0, 1.0],
+            color: HUDVertex::WHITE,
         },
         // Bottom Left
         HUDVertex {
             position: [center_x - half_w, center_y + half_h],
             uv: [0.0, 1.0],
+            color: HUDVertex::WHITE,
         },
-    ];
-    
-    let indices = vec![0, 1, 2, 0, 2, 3];
-    
-    (vertices, indices)
-}
\ No newline at end of file
+    ]
+}
@@ -0,0 +1,212 @@
+use crate::render::{mesh::Mesh, pipelines::{hud::HUDVertex, GlobalsLayouts}, renderer::Renderer, texture::Texture};
+
+use super::{create_uv_quad, layout::Layout, push_quad, HUDElement};
+
+
+/// Size in pixels of a glyph cell in the font atlas. The font is monospaced, every
+/// glyph takes a whole cell and the next one starts right after it.
+pub const GLYPH_WIDTH: f32 = 8.0;
+pub const GLYPH_HEIGHT: f32 = 16.0;
+/// Font pixels per GUI unit, text in the HUD scales with the GUI like everything else
+pub const GUI_TEXT_SCALE: f32 = 0.5;
+/// Cells per row and rows in `dejavu_sans_mono.png`
+const ATLAS_COLUMNS: u32 = 16;
+const ATLAS_ROWS: u32 = 12;
+/// The atlas has printable ASCII (32..=126) followed by Latin-1 (160..=255)
+const ASCII_GLYPHS: u32 = 95;
+/// Shadows are the text color this much darker, moved one font pixel down and right
+const SHADOW_DARKEN: f32 = 0.25;
+/// Quads a frame of `TextRenderer` holds, shadows included. What doesn't fit is dropped.
+const MAX_GLYPHS: usize = 4096;
+
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TextStyle {
+    /// RGBA multiplied by the white glyphs
+    pub color: [f32; 4],
+    /// Screen pixels per font pixel
+    pub scale: f32,
+    /// Which end of each line goes at the position
+    pub align: Align,
+    pub shadow: bool,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            color: HUDVertex::WHITE,
+            scale: 1.0,
+            align: Align::Left,
+            shadow: true,
+        }
+    }
+}
+
+
+/// Size in pixels of `text` drawn at `scale`, lines split at `\n`
+pub fn measure(text: &str, scale: f32) -> (f32, f32) {
+    let columns = text.split('\n').map(|line| line.chars().count()).max().unwrap_or(0);
+    let lines = text.split('\n').count();
+    (columns as f32 * GLYPH_WIDTH * scale, lines as f32 * GLYPH_HEIGHT * scale)
+}
+
+/// Appends the quads of `text` to `mesh`. `origin` is the top of the first line in NDC
+/// and `glyph` the size in NDC of a cell at scale 1.
+pub fn push_text(mesh: &mut Mesh<HUDVertex>, text: &str, origin: (f32, f32), glyph: (f32, f32), style: &TextStyle) {
+    let (width, height) = (glyph.0 * style.scale, glyph.1 * style.scale);
+
+    // primero todas las sombras, así ninguna tapa la letra de al lado
+    let mut passes = Vec::with_capacity(2);
+    if style.shadow {
+        let [r, g, b, a] = style.color;
+        let color = [r * SHADOW_DARKEN, g * SHADOW_DARKEN, b * SHADOW_DARKEN, a];
+        passes.push(((width / GLYPH_WIDTH, -height / GLYPH_HEIGHT), color));
+    }
+    passes.push(((0.0, 0.0), style.color));
+
+    for ((offset_x, offset_y), color) in passes {
+        for (row, line) in text.split('\n').enumerate() {
+            let line_width = line.chars().count() as f32 * width;
+            let left = match style.align {
+                Align::Left => origin.0,
+                Align::Center => origin.0 - line_width / 2.0,
+                Align::Right => origin.0 - line_width,
+            };
+            let y = origin.1 - (row as f32 + 0.5) * height + offset_y;
+
+            for (column, c) in line.chars().enumerate() {
+                if c == ' ' {
+                    continue;
+                }
+                let x = left + (column as f32 + 0.5) * width + offset_x;
+                push_quad(mesh, create_uv_quad(x, y, width, height, glyph_uv(c), color));
+            }
+        }
+    }
+}
+
+/// Cell of the atlas holding `c`, characters the font doesn't have show as `?`
+fn glyph_index(c: char) -> u32 {
+    match c as u32 {
+        code @ 32..=126 => code - 32,
+        code @ 160..=255 => code - 160 + ASCII_GLYPHS,
+        _ => '?' as u32 - 32,
+    }
+}
+
+/// `[u_min, v_min, u_max, v_max]` of the cell holding `c`
+fn glyph_uv(c: char) -> [f32; 4] {
+    let index = glyph_index(c);
+    let (column, row) = ((index % ATLAS_COLUMNS) as f32, (index / ATLAS_COLUMNS) as f32);
+    let (cell_u, cell_v) = (1.0 / ATLAS_COLUMNS as f32, 1.0 / ATLAS_ROWS as f32);
+    [column * cell_u, row * cell_v, (column + 1.0) * cell_u, (row + 1.0) * cell_v]
+}
+
+
+/// Text placed in pixels that is queued again every frame, all of it drawn with one
+/// vertex buffer.
+pub struct TextRenderer {
+    element: HUDElement,
+    /// Quads queued since the last flush
+    mesh: Mesh<HUDVertex>,
+    /// To go from pixels to NDC
+    layout: Layout,
+}
+
+impl TextRenderer {
+    pub fn new(renderer: &Renderer, global_layout: &GlobalsLayouts, layout: Layout) -> Self {
+        Self {
+            element: HUDElement::new(renderer, global_layout, font_texture(renderer), MAX_GLYPHS),
+            mesh: Mesh::new(),
+            layout,
+        }
+    }
+
+    pub fn set_layout(&mut self, layout: Layout) {
+        self.layout = layout;
+    }
+
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    /// Queues `text` for the next frame. `position` is in pixels from the top left corner
+    /// of the window: the top of the first line and, depending on the align, the left
+    /// end, center or right end of every line.
+    pub fn queue(&mut self, text: &str, position: (f32, f32), style: &TextStyle) {
+        let origin = self.layout.point_to_ndc(position);
+        let glyph = self.layout.size_to_ndc((GLYPH_WIDTH, GLYPH_HEIGHT));
+        push_text(&mut self.mesh, text, origin, glyph, style);
+    }
+
+    /// Uploads the text queued since the last call, it stays on screen until the next one
+    pub fn flush(&mut self, queue: &wgpu::Queue) {
+        self.mesh.verts.truncate(MAX_GLYPHS * 4);
+        self.mesh.indices.truncate(MAX_GLYPHS * 6);
+        self.element.set_mesh(queue, &self.mesh);
+        self.mesh.clear();
+    }
+
+    pub(super) fn element(&self) -> &HUDElement {
+        &self.element
+    }
+}
+
+
+/// White glyphs over a transparent background, shared by every text in the HUD
+pub(super) fn font_texture(renderer: &Renderer) -> Texture {
+    let font_bytes = include_bytes!("../../assets/fonts/dejavu_sans_mono.png");
+    Texture::from_bytes(&renderer.device, &renderer.queue, font_bytes, "dejavu_sans_mono.png").unwrap()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measures_the_longest_line() {
+        assert_eq!(measure("", 1.0), (0.0, GLYPH_HEIGHT));
+        assert_eq!(measure("abc\nde", 2.0), (3.0 * GLYPH_WIDTH * 2.0, 2.0 * GLYPH_HEIGHT * 2.0));
+        assert_eq!(measure("ñé", 1.0).0, 2.0 * GLYPH_WIDTH);
+    }
+
+    #[test]
+    fn unknown_characters_fall_back() {
+        assert_eq!(glyph_index(' '), 0);
+        assert_eq!(glyph_index('~'), ASCII_GLYPHS - 1);
+        assert_eq!(glyph_index('\u{a0}'), ASCII_GLYPHS);
+        assert_eq!(glyph_index('ÿ'), ASCII_GLYPHS + 95);
+        assert_eq!(glyph_index('€'), glyph_index('?'));
+        assert!(glyph_index('ÿ') < ATLAS_COLUMNS * ATLAS_ROWS);
+    }
+
+    #[test]
+    fn aligns_every_line() {
+        let style = TextStyle { align: Align::Right, shadow: false, ..Default::default() };
+        let mut mesh = Mesh::new();
+        push_text(&mut mesh, "ab\nc", (0.0, 0.0), (0.1, 0.2), &style);
+
+        // tres letras, la última acaba en el origen igual que la primera línea
+        assert_eq!(mesh.verts.len(), 12);
+        let right = |quad: usize| mesh.verts[quad * 4 + 1].position[0];
+        assert!((right(1) - 0.0).abs() < 1e-6);
+        assert!((right(2) - 0.0).abs() < 1e-6);
+        assert!((mesh.verts[8].position[1] + 0.4).abs() < 1e-6);
+    }
+
+    #[test]
+    fn shadows_double_the_quads_but_skip_spaces() {
+        let mut mesh = Mesh::new();
+        push_text(&mut mesh, "a b", (0.0, 0.0), (0.1, 0.2), &TextStyle::default());
+        assert_eq!(mesh.verts.len(), 16);
+        assert_eq!(mesh.indices.len(), 24);
+    }
+}
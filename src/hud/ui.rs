@@ -0,0 +1,775 @@
+use winit::{event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent}, keyboard::{KeyCode, PhysicalKey}};
+
+use crate::render::{mesh::Mesh, pipelines::{hud::HUDVertex, GlobalsLayouts}, renderer::Renderer, texture::Texture};
+
+use super::{create_uv_quad, layout::{Anchor, Layout, Rect}, push_quad, text::{self, push_text, Align, TextStyle, GLYPH_HEIGHT, GLYPH_WIDTH, GUI_TEXT_SCALE}, HUDElement};
+
+
+/// Quads a `UiRenderer` holds for the backgrounds and for the glyphs
+const MAX_UI_QUADS: usize = 2048;
+/// Height of a scroll list row, in GUI units
+pub const LIST_ROW_HEIGHT: f32 = 12.0;
+/// Space between the border of a widget and its text, in GUI units
+const TEXT_PADDING: f32 = 3.0;
+/// Width of the slider handle and the list scroll bar, in GUI units
+const HANDLE_WIDTH: f32 = 6.0;
+/// Border drawn around the widget with keyboard focus, in GUI units
+const FOCUS_BORDER: f32 = 1.0;
+/// Pixels of a touchpad scroll that count as a wheel notch
+const PIXELS_PER_LINE: f32 = 16.0;
+
+const PANEL_COLOR: [f32; 4] = [0.1, 0.1, 0.1, 0.75];
+const BUTTON_COLOR: [f32; 4] = [0.35, 0.35, 0.35, 0.9];
+const HOVER_COLOR: [f32; 4] = [0.45, 0.45, 0.55, 0.9];
+const PRESSED_COLOR: [f32; 4] = [0.25, 0.25, 0.35, 0.9];
+const FIELD_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.8];
+const SELECTED_COLOR: [f32; 4] = [0.3, 0.4, 0.6, 0.9];
+const HANDLE_COLOR: [f32; 4] = [0.8, 0.8, 0.8, 1.0];
+const FOCUS_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const PLACEHOLDER_COLOR: [f32; 4] = [0.5, 0.5, 0.5, 1.0];
+
+
+/// What a widget is and its current value
+#[derive(Clone, Debug, PartialEq)]
+pub enum WidgetKind {
+    /// Background for other widgets
+    Panel,
+    Label { text: String, align: Align },
+    Button { label: String },
+    Toggle { label: String, on: bool },
+    Slider { label: String, value: f32, min: f32, max: f32, step: f32 },
+    TextInput { text: String, placeholder: String, max_len: usize },
+    /// One row per item, as many as fit in its height, scrolled with the wheel
+    ScrollList { items: Vec<String>, selected: Option<usize>, scroll: usize },
+    /// Bar filled from the left, `value` goes from 0 to 1
+    ProgressBar { value: f32 },
+}
+
+impl WidgetKind {
+    /// Reacts to clicks and can take the keyboard focus
+    fn interactive(&self) -> bool {
+        !matches!(self, WidgetKind::Panel | WidgetKind::Label { .. } | WidgetKind::ProgressBar { .. })
+    }
+}
+
+
+/// A widget and where it goes. `action` is the game's own value, sent back in every
+/// `UiEvent` of the widget.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Widget<A> {
+    pub action: Option<A>,
+    pub kind: WidgetKind,
+    pub anchor: Anchor,
+    /// In GUI units, see `Layout::place`
+    pub offset: (f32, f32),
+    pub size: (f32, f32),
+}
+
+impl<A> Widget<A> {
+    fn new(action: Option<A>, kind: WidgetKind) -> Self {
+        Self { action, kind, anchor: Anchor::Center, offset: (0.0, 0.0), size: (0.0, 0.0) }
+    }
+
+    pub fn panel() -> Self {
+        Self::new(None, WidgetKind::Panel)
+    }
+
+    pub fn label(text: &str, align: Align) -> Self {
+        Self::new(None, WidgetKind::Label { text: text.to_string(), align })
+    }
+
+    pub fn button(action: A, label: &str) -> Self {
+        Self::new(Some(action), WidgetKind::Button { label: label.to_string() })
+    }
+
+    pub fn toggle(action: A, label: &str, on: bool) -> Self {
+        Self::new(Some(action), WidgetKind::Toggle { label: label.to_string(), on })
+    }
+
+    /// `value` is clamped to `min..=max` and snapped to `step`
+    pub fn slider(action: A, label: &str, value: f32, min: f32, max: f32, step: f32) -> Self {
+        Self::new(Some(action), WidgetKind::Slider { label: label.to_string(), value: snap(value, min, max, step), min, max, step })
+    }
+
+    pub fn text_input(action: A, text: &str, placeholder: &str, max_len: usize) -> Self {
+        Self::new(Some(action), WidgetKind::TextInput { text: text.to_string(), placeholder: placeholder.to_string(), max_len })
+    }
+
+    pub fn scroll_list(action: A, items: Vec<String>) -> Self {
+        Self::new(Some(action), WidgetKind::ScrollList { items, selected: None, scroll: 0 })
+    }
+
+    pub fn progress_bar(value: f32) -> Self {
+        Self::new(None, WidgetKind::ProgressBar { value: value.clamp(0.0, 1.0) })
+    }
+
+    /// Places the widget, offset and size in GUI units
+    pub fn at(mut self, anchor: Anchor, offset: (f32, f32), size: (f32, f32)) -> Self {
+        self.anchor = anchor;
+        self.offset = offset;
+        self.size = size;
+        self
+    }
+
+    /// Rows of a scroll list that fit in its height
+    fn list_rows(&self) -> usize {
+        (self.size.1 / LIST_ROW_HEIGHT).floor().max(1.0) as usize
+    }
+}
+
+/// `value` inside `min..=max`, rounded to the nearest multiple of `step` from `min`
+fn snap(value: f32, min: f32, max: f32, step: f32) -> f32 {
+    let value = value.clamp(min, max);
+    if step <= 0.0 {
+        return value;
+    }
+    (min + ((value - min) / step).round() * step).clamp(min, max)
+}
+
+
+/// What the game gets back from the widgets
+#[derive(Clone, Debug, PartialEq)]
+pub enum UiEvent<A> {
+    Clicked(A),
+    Toggled(A, bool),
+    SliderChanged(A, f32),
+    TextChanged(A, String),
+    /// Enter pressed in a text input
+    TextSubmitted(A, String),
+    ItemSelected(A, usize),
+}
+
+
+/// Index of a widget in its `Ui`
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct WidgetId(usize);
+
+
+/// Retained set of widgets: they are added once, keep their state between frames and
+/// report what happened to them as `UiEvent`s. Takes the window events while the cursor
+/// is free, positions are in pixels like the cursor.
+pub struct Ui<A> {
+    widgets: Vec<Widget<A>>,
+    layout: Layout,
+    cursor: (f32, f32),
+    hovered: Option<usize>,
+    /// Widget the left button went down on, it's clicked if it's released over it
+    pressed: Option<usize>,
+    focused: Option<usize>,
+    events: Vec<UiEvent<A>>,
+    /// Something changed since the last `take_dirty`
+    dirty: bool,
+}
+
+impl<A: Clone> Ui<A> {
+    pub fn new(layout: Layout) -> Self {
+        Self {
+            widgets: Vec::new(),
+            layout,
+            cursor: (-1.0, -1.0),
+            hovered: None,
+            pressed: None,
+            focused: None,
+            events: Vec::new(),
+            dirty: true,
+        }
+    }
+
+    pub fn set_layout(&mut self, layout: Layout) {
+        self.layout = layout;
+        self.dirty = true;
+    }
+
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    /// Widgets added later are drawn on top and get the clicks first
+    pub fn push(&mut self, widget: Widget<A>) -> WidgetId {
+        self.widgets.push(widget);
+        self.dirty = true;
+        WidgetId(self.widgets.len() - 1)
+    }
+
+    pub fn widget(&self, id: WidgetId) -> &Widget<A> {
+        &self.widgets[id.0]
+    }
+
+    pub fn widget_mut(&mut self, id: WidgetId) -> &mut Widget<A> {
+        self.dirty = true;
+        &mut self.widgets[id.0]
+    }
+
+    /// Removes every widget, to build another screen
+    pub fn clear(&mut self) {
+        self.widgets.clear();
+        self.hovered = None;
+        self.pressed = None;
+        self.focused = None;
+        self.events.clear();
+        self.dirty = true;
+    }
+
+    pub fn focused(&self) -> Option<WidgetId> {
+        self.focused.map(WidgetId)
+    }
+
+    pub fn set_focus(&mut self, id: Option<WidgetId>) {
+        self.focused = id.map(|id| id.0);
+        self.dirty = true;
+    }
+
+    /// Events since the last call, oldest first
+    pub fn drain_events(&mut self) -> Vec<UiEvent<A>> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// True once after every change, so the meshes are only rebuilt when needed
+    pub fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+
+    /// Where the widget is on the window
+    pub fn rect(&self, id: WidgetId) -> Rect {
+        let widget = &self.widgets[id.0];
+        self.layout.place(widget.anchor, widget.offset, widget.size)
+    }
+
+    /// Topmost interactive widget under `point`
+    fn widget_at(&self, point: (f32, f32)) -> Option<usize> {
+        (0..self.widgets.len()).rev()
+            .find(|&i| self.widgets[i].kind.interactive() && self.rect(WidgetId(i)).contains(point))
+    }
+
+    /// Handles mouse and keyboard events, returns false for those no widget used
+    pub fn handle_event(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor_moved((position.x as f32, position.y as f32));
+                self.hovered.is_some()
+            }
+            WindowEvent::MouseInput { state, button: MouseButton::Left, .. } => {
+                self.mouse_button(*state == ElementState::Pressed)
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => *y,
+                    MouseScrollDelta::PixelDelta(position) => position.y as f32 / PIXELS_PER_LINE,
+                };
+                self.scroll(lines)
+            }
+            WindowEvent::KeyboardInput { event, .. } if event.state == ElementState::Pressed => {
+                let used_key = match event.physical_key {
+                    PhysicalKey::Code(code) => self.key(code),
+                    PhysicalKey::Unidentified(_) => false,
+                };
+                let used_text = event.text.as_ref().is_some_and(|text| self.type_text(text));
+                used_key || used_text
+            }
+            _ => false,
+        }
+    }
+
+    pub fn cursor_moved(&mut self, cursor: (f32, f32)) {
+        self.cursor = cursor;
+        let hovered = self.widget_at(cursor);
+        if hovered != self.hovered {
+            self.hovered = hovered;
+            self.dirty = true;
+        }
+
+        // el slider sigue al ratón mientras se arrastra
+        let dragged = self.pressed.filter(|&index| matches!(self.widgets[index].kind, WidgetKind::Slider { .. }));
+        if let Some(index) = dragged {
+            self.drag_slider(index);
+        }
+    }
+
+    /// Left button pressed or released, returns true if it was over a widget
+    pub fn mouse_button(&mut self, pressed: bool) -> bool {
+        if !pressed {
+            let Some(index) = self.pressed.take() else {
+                return false;
+            };
+            self.dirty = true;
+            if self.hovered == Some(index) && matches!(self.widgets[index].kind, WidgetKind::Button { .. } | WidgetKind::Toggle { .. }) {
+                self.activate(index);
+            }
+            return true;
+        }
+
+        // clicar fuera quita el foco
+        self.focused = self.hovered;
+        self.pressed = self.hovered;
+        self.dirty = true;
+        let Some(index) = self.hovered else {
+            return false;
+        };
+
+        match self.widgets[index].kind {
+            WidgetKind::Slider { .. } => self.drag_slider(index),
+            WidgetKind::ScrollList { .. } => self.click_list(index),
+            _ => {}
+        }
+        true
+    }
+
+    /// Wheel notches over a scroll list, positive scrolls up
+    pub fn scroll(&mut self, lines: f32) -> bool {
+        let Some(index) = self.hovered else {
+            return false;
+        };
+        let rows = self.widgets[index].list_rows();
+        let WidgetKind::ScrollList { items, scroll, .. } = &mut self.widgets[index].kind else {
+            return false;
+        };
+
+        let max_scroll = items.len().saturating_sub(rows);
+        let notches = lines.round() as isize;
+        *scroll = (*scroll as isize - notches).clamp(0, max_scroll as isize) as usize;
+        self.dirty = true;
+        true
+    }
+
+    /// Keys for the focused widget: Tab moves the focus, Enter and Space activate,
+    /// arrows change sliders and lists, Backspace deletes. Returns true if used.
+    pub fn key(&mut self, key: KeyCode) -> bool {
+        if key == KeyCode::Tab {
+            self.focus_next();
+            return true;
+        }
+        let Some(index) = self.focused else {
+            return false;
+        };
+
+        let activate = matches!(key, KeyCode::Enter | KeyCode::NumpadEnter | KeyCode::Space);
+        if activate && matches!(self.widgets[index].kind, WidgetKind::Button { .. } | WidgetKind::Toggle { .. }) {
+            self.activate(index);
+            return true;
+        }
+
+        let rows = self.widgets[index].list_rows();
+        let widget = &mut self.widgets[index];
+        let used = match (&mut widget.kind, key) {
+            (WidgetKind::Slider { value, min, max, step, .. }, KeyCode::ArrowLeft | KeyCode::ArrowRight) => {
+                let step = if *step > 0.0 { *step } else { (*max - *min) / 100.0 };
+                let delta = if key == KeyCode::ArrowLeft { -step } else { step };
+                let new_value = snap(*value + delta, *min, *max, step);
+                if new_value != *value {
+                    *value = new_value;
+                    push_event(&mut self.events, widget.action.clone(), |action| UiEvent::SliderChanged(action, new_value));
+                }
+                true
+            }
+            (WidgetKind::TextInput { text, .. }, KeyCode::Backspace) => {
+                if text.pop().is_some() {
+                    let text = text.clone();
+                    push_event(&mut self.events, widget.action.clone(), |action| UiEvent::TextChanged(action, text));
+                }
+                true
+            }
+            (WidgetKind::TextInput { text, .. }, KeyCode::Enter | KeyCode::NumpadEnter) => {
+                let text = text.clone();
+                push_event(&mut self.events, widget.action.clone(), |action| UiEvent::TextSubmitted(action, text));
+                true
+            }
+            (WidgetKind::ScrollList { items, selected, scroll }, KeyCode::ArrowUp | KeyCode::ArrowDown) => {
+                if !items.is_empty() {
+                    let row = match (*selected, key) {
+                        (None, _) => 0,
+                        (Some(row), KeyCode::ArrowUp) => row.saturating_sub(1),
+                        (Some(row), _) => (row + 1).min(items.len() - 1),
+                    };
+                    *selected = Some(row);
+                    // que la fila elegida se vea
+                    *scroll = (*scroll).min(row).max((row + 1).saturating_sub(rows));
+                    push_event(&mut self.events, widget.action.clone(), |action| UiEvent::ItemSelected(action, row));
+                }
+                true
+            }
+            _ => false,
+        };
+
+        self.dirty |= used;
+        used
+    }
+
+    /// Text typed with the focused text input, control characters are ignored
+    pub fn type_text(&mut self, typed: &str) -> bool {
+        let Some(index) = self.focused else {
+            return false;
+        };
+        let widget = &mut self.widgets[index];
+        let WidgetKind::TextInput { text, max_len, .. } = &mut widget.kind else {
+            return false;
+        };
+
+        let before = text.chars().count();
+        let room = max_len.saturating_sub(before);
+        text.extend(typed.chars().filter(|c| !c.is_control()).take(room));
+        if text.chars().count() != before {
+            let text = text.clone();
+            push_event(&mut self.events, widget.action.clone(), |action| UiEvent::TextChanged(action, text));
+            self.dirty = true;
+        }
+        true
+    }
+
+    /// Moves the keyboard focus to the next interactive widget, back to the first after the last
+    fn focus_next(&mut self) {
+        let len = self.widgets.len();
+        let start = self.focused.map_or(0, |index| index + 1);
+        self.focused = (0..len).map(|i| (start + i) % len).find(|&i| self.widgets[i].kind.interactive());
+        self.dirty = true;
+    }
+
+    /// Clicks a button or flips a toggle
+    fn activate(&mut self, index: usize) {
+        let widget = &mut self.widgets[index];
+        match &mut widget.kind {
+            WidgetKind::Button { .. } => push_event(&mut self.events, widget.action.clone(), UiEvent::Clicked),
+            WidgetKind::Toggle { on, .. } => {
+                *on = !*on;
+                let on = *on;
+                push_event(&mut self.events, widget.action.clone(), |action| UiEvent::Toggled(action, on));
+            }
+            _ => {}
+        }
+        self.dirty = true;
+    }
+
+    /// Moves the handle under the cursor
+    fn drag_slider(&mut self, index: usize) {
+        let rect = self.rect(WidgetId(index));
+        let handle = HANDLE_WIDTH * self.layout.scale();
+        let widget = &mut self.widgets[index];
+        let WidgetKind::Slider { value, min, max, step, .. } = &mut widget.kind else {
+            return;
+        };
+
+        let t = ((self.cursor.0 - rect.x - handle / 2.0) / (rect.width - handle).max(1.0)).clamp(0.0, 1.0);
+        let new_value = snap(*min + t * (*max - *min), *min, *max, *step);
+        if new_value != *value {
+            *value = new_value;
+            push_event(&mut self.events, widget.action.clone(), |action| UiEvent::SliderChanged(action, new_value));
+            self.dirty = true;
+        }
+    }
+
+    /// Selects the row under the cursor
+    fn click_list(&mut self, index: usize) {
+        let rect = self.rect(WidgetId(index));
+        let row_height = LIST_ROW_HEIGHT * self.layout.scale();
+        let widget = &mut self.widgets[index];
+        let WidgetKind::ScrollList { items, selected, scroll } = &mut widget.kind else {
+            return;
+        };
+
+        let row = ((self.cursor.1 - rect.y) / row_height) as usize + *scroll;
+        if row < items.len() {
+            *selected = Some(row);
+            push_event(&mut self.events, widget.action.clone(), |action| UiEvent::ItemSelected(action, row));
+        }
+    }
+
+    /// Backgrounds, drawn with a white texture tinted by the vertex color, and glyphs
+    fn meshes(&self) -> (Mesh<HUDVertex>, Mesh<HUDVertex>) {
+        let mut shapes = Mesh::new();
+        let mut glyphs = Mesh::new();
+        let scale = self.layout.scale();
+        let style = TextStyle { scale: scale * GUI_TEXT_SCALE, ..Default::default() };
+        let glyph_size = self.layout.size_to_ndc((GLYPH_WIDTH, GLYPH_HEIGHT));
+        let text_height = GLYPH_HEIGHT * style.scale;
+        let padding = TEXT_PADDING * scale;
+
+        let mut fill = |rect: &Rect, color: [f32; 4]| {
+            let (x, y, width, height) = self.layout.rect_to_ndc(rect);
+            push_quad(&mut shapes, create_uv_quad(x, y, width, height, [0.0, 0.0, 1.0, 1.0], color));
+        };
+        // texto centrado en vertical dentro de `rect`
+        let mut write = |rect: &Rect, text: &str, align: Align, color: [f32; 4]| {
+            let x = match align {
+                Align::Left => rect.x + padding,
+                Align::Center => rect.x + rect.width / 2.0,
+                Align::Right => rect.x + rect.width - padding,
+            };
+            let y = rect.y + (rect.height - text_height) / 2.0;
+            let style = TextStyle { align, color, ..style };
+            push_text(&mut glyphs, text, self.layout.point_to_ndc((x, y)), glyph_size, &style);
+        };
+
+        for (index, widget) in self.widgets.iter().enumerate() {
+            let rect = self.rect(WidgetId(index));
+            let background = if self.pressed == Some(index) {
+                PRESSED_COLOR
+            } else if self.hovered == Some(index) {
+                HOVER_COLOR
+            } else {
+                BUTTON_COLOR
+            };
+
+            match &widget.kind {
+                WidgetKind::Panel => fill(&rect, PANEL_COLOR),
+                WidgetKind::Label { text, align } => write(&rect, text, *align, HUDVertex::WHITE),
+                WidgetKind::ProgressBar { value } => {
+                    fill(&rect, FIELD_COLOR);
+                    fill(&Rect::new(rect.x, rect.y, rect.width * value.clamp(0.0, 1.0), rect.height), SELECTED_COLOR);
+                }
+                WidgetKind::Button { label } => {
+                    fill(&rect, background);
+                    write(&rect, label, Align::Center, HUDVertex::WHITE);
+                }
+                WidgetKind::Toggle { label, on } => {
+                    fill(&rect, background);
+                    write(&rect, &format!("{}: {}", label, if *on { "ON" } else { "OFF" }), Align::Center, HUDVertex::WHITE);
+                }
+                WidgetKind::Slider { label, value, min, max, step } => {
+                    fill(&rect, FIELD_COLOR);
+                    let handle = HANDLE_WIDTH * scale;
+                    let t = if max > min { (value - min) / (max - min) } else { 0.0 };
+                    let handle_rect = Rect::new(rect.x + t * (rect.width - handle), rect.y, handle, rect.height);
+                    fill(&handle_rect, if self.pressed == Some(index) || self.hovered == Some(index) { FOCUS_COLOR } else { HANDLE_COLOR });
+                    let decimals = if *step >= 1.0 { 0 } else { 2 };
+                    write(&rect, &format!("{}: {:.*}", label, decimals, value), Align::Center, HUDVertex::WHITE);
+                }
+                WidgetKind::TextInput { text, placeholder, .. } => {
+                    fill(&rect, FIELD_COLOR);
+                    let focused = self.focused == Some(index);
+                    if text.is_empty() && !focused {
+                        write(&rect, placeholder, Align::Left, PLACEHOLDER_COLOR);
+                    } else {
+                        // solo el final si no cabe, con el cursor
+                        let columns = ((rect.width - 2.0 * padding) / (GLYPH_WIDTH * style.scale)) as usize;
+                        let shown = format!("{}{}", text, if focused { "_" } else { "" });
+                        let skip = shown.chars().count().saturating_sub(columns);
+                        write(&rect, &shown.chars().skip(skip).collect::<String>(), Align::Left, HUDVertex::WHITE);
+                    }
+                }
+                WidgetKind::ScrollList { items, selected, scroll } => {
+                    fill(&rect, FIELD_COLOR);
+                    let rows = widget.list_rows();
+                    let row_height = LIST_ROW_HEIGHT * scale;
+                    let hovered_row = (self.hovered == Some(index) && rect.contains(self.cursor))
+                        .then(|| ((self.cursor.1 - rect.y) / row_height) as usize + scroll);
+                    for (row, item) in items.iter().enumerate().skip(*scroll).take(rows) {
+                        let row_rect = Rect::new(rect.x, rect.y + (row - scroll) as f32 * row_height, rect.width - HANDLE_WIDTH * scale, row_height);
+                        if *selected == Some(row) {
+                            fill(&row_rect, SELECTED_COLOR);
+                        } else if hovered_row == Some(row) {
+                            fill(&row_rect, HOVER_COLOR);
+                        }
+                        write(&row_rect, item, Align::Left, HUDVertex::WHITE);
+                    }
+
+                    // barra de desplazamiento si no caben todas
+                    if items.len() > rows {
+                        let handle = HANDLE_WIDTH * scale;
+                        let height = rect.height * rows as f32 / items.len() as f32;
+                        let y = rect.y + (rect.height - height) * *scroll as f32 / (items.len() - rows) as f32;
+                        fill(&Rect::new(rect.x + rect.width - handle, y, handle, height), HANDLE_COLOR);
+                    }
+                }
+            }
+
+            if self.focused == Some(index) {
+                let border = FOCUS_BORDER * scale;
+                fill(&Rect::new(rect.x, rect.y, rect.width, border), FOCUS_COLOR);
+                fill(&Rect::new(rect.x, rect.y + rect.height - border, rect.width, border), FOCUS_COLOR);
+                fill(&Rect::new(rect.x, rect.y, border, rect.height), FOCUS_COLOR);
+                fill(&Rect::new(rect.x + rect.width - border, rect.y, border, rect.height), FOCUS_COLOR);
+            }
+        }
+
+        (shapes, glyphs)
+    }
+}
+
+/// Widgets without an action don't send events
+fn push_event<A>(events: &mut Vec<UiEvent<A>>, action: Option<A>, event: impl FnOnce(A) -> UiEvent<A>) {
+    if let Some(action) = action {
+        events.push(event(action));
+    }
+}
+
+
+/// Draws a `Ui` with the HUD pipeline
+pub struct UiRenderer {
+    shapes: HUDElement,
+    glyphs: HUDElement,
+    pub visible: bool,
+}
+
+impl UiRenderer {
+    pub fn new(renderer: &Renderer, global_layout: &GlobalsLayouts) -> Self {
+        let white = image::DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(1, 1, image::Rgba([255, 255, 255, 255])));
+        let white_tex = Texture::from_image(&renderer.device, &renderer.queue, &white, Some("ui shapes")).unwrap();
+
+        Self {
+            shapes: HUDElement::new(renderer, global_layout, white_tex, MAX_UI_QUADS),
+            glyphs: HUDElement::new(renderer, global_layout, text::font_texture(renderer), MAX_UI_QUADS),
+            visible: false,
+        }
+    }
+
+    /// Shows `ui`, its meshes are only rebuilt if it changed
+    pub fn update<A: Clone>(&mut self, queue: &wgpu::Queue, ui: &mut Ui<A>) {
+        self.visible = true;
+        if !ui.take_dirty() {
+            return;
+        }
+
+        let (mut shapes, mut glyphs) = ui.meshes();
+        for mesh in [&mut shapes, &mut glyphs] {
+            mesh.verts.truncate(MAX_UI_QUADS * 4);
+            mesh.indices.truncate(MAX_UI_QUADS * 6);
+        }
+        self.shapes.set_mesh(queue, &shapes);
+        self.glyphs.set_mesh(queue, &glyphs);
+    }
+
+    /// Back to front
+    pub(super) fn elements(&self) -> [&HUDElement; 2] {
+        [&self.shapes, &self.glyphs]
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use winit::dpi::PhysicalSize;
+
+    use super::*;
+
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    enum Test {
+        Button,
+        Toggle,
+        Slider,
+        Name,
+        List,
+    }
+
+    /// 800x600 es escala 2: un widget de 100 unidades mide 200 píxeles
+    fn ui() -> (Ui<Test>, [WidgetId; 5]) {
+        let mut ui = Ui::new(Layout::new(PhysicalSize::new(800, 600), 0));
+        ui.push(Widget::panel().at(Anchor::Center, (0.0, 0.0), (200.0, 200.0)));
+        let ids = [
+            ui.push(Widget::button(Test::Button, "Play").at(Anchor::TopLeft, (0.0, 0.0), (100.0, 20.0))),
+            ui.push(Widget::toggle(Test::Toggle, "Vsync", false).at(Anchor::TopLeft, (0.0, 30.0), (100.0, 20.0))),
+            ui.push(Widget::slider(Test::Slider, "FOV", 70.0, 30.0, 110.0, 10.0).at(Anchor::TopLeft, (0.0, 60.0), (100.0, 20.0))),
+            ui.push(Widget::text_input(Test::Name, "", "Name", 5).at(Anchor::TopLeft, (0.0, 90.0), (100.0, 20.0))),
+            ui.push(Widget::scroll_list(Test::List, (0..10).map(|i| i.to_string()).collect()).at(Anchor::TopLeft, (0.0, 120.0), (100.0, 3.0 * LIST_ROW_HEIGHT))),
+        ];
+        (ui, ids)
+    }
+
+    fn click(ui: &mut Ui<Test>, point: (f32, f32)) {
+        ui.cursor_moved(point);
+        ui.mouse_button(true);
+        ui.mouse_button(false);
+    }
+
+    #[test]
+    fn buttons_click_on_release_over_them() {
+        let (mut ui, _) = ui();
+        click(&mut ui, (10.0, 10.0));
+        assert_eq!(ui.drain_events(), vec![UiEvent::Clicked(Test::Button)]);
+
+        // soltar fuera no cuenta
+        ui.cursor_moved((10.0, 10.0));
+        ui.mouse_button(true);
+        ui.cursor_moved((700.0, 10.0));
+        ui.mouse_button(false);
+        assert!(ui.drain_events().is_empty());
+    }
+
+    #[test]
+    fn clicks_on_panels_and_labels_fall_through() {
+        let (mut ui, _) = ui();
+        // dentro del panel pero sin nada encima
+        ui.cursor_moved((400.0, 300.0));
+        assert!(!ui.mouse_button(true));
+        assert_eq!(ui.focused(), None);
+    }
+
+    #[test]
+    fn toggles_flip() {
+        let (mut ui, [_, toggle, ..]) = ui();
+        click(&mut ui, (10.0, 70.0));
+        click(&mut ui, (10.0, 70.0));
+        assert_eq!(ui.drain_events(), vec![UiEvent::Toggled(Test::Toggle, true), UiEvent::Toggled(Test::Toggle, false)]);
+        assert!(matches!(ui.widget(toggle).kind, WidgetKind::Toggle { on: false, .. }));
+    }
+
+    #[test]
+    fn sliders_follow_the_mouse_and_the_arrows() {
+        let (mut ui, [_, _, slider, ..]) = ui();
+        // el asa mide 12 píxeles, la pista va de 6 a 194
+        ui.cursor_moved((194.0, 130.0));
+        ui.mouse_button(true);
+        ui.cursor_moved((6.0, 500.0));
+        ui.mouse_button(false);
+        assert_eq!(ui.drain_events(), vec![UiEvent::SliderChanged(Test::Slider, 110.0), UiEvent::SliderChanged(Test::Slider, 30.0)]);
+
+        ui.key(KeyCode::ArrowRight);
+        assert!(matches!(ui.widget(slider).kind, WidgetKind::Slider { value: 40.0, .. }));
+        ui.key(KeyCode::ArrowLeft);
+        ui.key(KeyCode::ArrowLeft);
+        assert_eq!(ui.drain_events(), vec![UiEvent::SliderChanged(Test::Slider, 40.0), UiEvent::SliderChanged(Test::Slider, 30.0)]);
+    }
+
+    #[test]
+    fn text_inputs_take_typing_while_focused() {
+        let (mut ui, [.., name, _]) = ui();
+        assert!(!ui.type_text("a"));
+
+        click(&mut ui, (10.0, 190.0));
+        assert_eq!(ui.focused(), Some(name));
+        ui.type_text("ab\r");
+        ui.type_text("cdefg");
+        ui.key(KeyCode::Backspace);
+        ui.key(KeyCode::Enter);
+
+        let events = ui.drain_events();
+        assert_eq!(events.last(), Some(&UiEvent::TextSubmitted(Test::Name, "abcd".to_string())));
+        assert!(events.contains(&UiEvent::TextChanged(Test::Name, "abcde".to_string())));
+    }
+
+    #[test]
+    fn tab_cycles_the_focus() {
+        let (mut ui, ids) = ui();
+        for id in ids.iter().chain(&ids[..1]) {
+            ui.key(KeyCode::Tab);
+            assert_eq!(ui.focused(), Some(*id));
+        }
+
+        ui.key(KeyCode::Enter);
+        assert_eq!(ui.drain_events(), vec![UiEvent::Clicked(Test::Button)]);
+    }
+
+    #[test]
+    fn lists_scroll_and_select() {
+        let (mut ui, [.., list]) = ui();
+        let row_height = LIST_ROW_HEIGHT * 2.0;
+        let top = 240.0;
+
+        click(&mut ui, (10.0, top + row_height * 1.5));
+        ui.scroll(-3.0);
+        click(&mut ui, (10.0, top + row_height * 0.5));
+        ui.scroll(-100.0);
+        assert!(matches!(ui.widget(list).kind, WidgetKind::ScrollList { scroll: 7, .. }));
+        assert_eq!(ui.drain_events(), vec![UiEvent::ItemSelected(Test::List, 1), UiEvent::ItemSelected(Test::List, 3)]);
+
+        ui.key(KeyCode::ArrowUp);
+        assert!(matches!(ui.widget(list).kind, WidgetKind::ScrollList { scroll: 2, selected: Some(2), .. }));
+    }
+
+    #[test]
+    fn layout_changes_move_the_hit_areas() {
+        let (mut ui, [button, ..]) = ui();
+        ui.set_layout(Layout::new(PhysicalSize::new(1920, 1080), 0));
+        assert_eq!(ui.rect(button), Rect::new(0.0, 0.0, 400.0, 80.0));
+        click(&mut ui, (390.0, 70.0));
+        assert_eq!(ui.drain_events(), vec![UiEvent::Clicked(Test::Button)]);
+    }
+}
@@ -0,0 +1,408 @@
+use std::collections::{BTreeMap, HashSet};
+
+use serde::{de::IntoDeserializer, Deserialize, Serialize};
+use winit::{event::{ElementState, KeyEvent, MouseButton, MouseScrollDelta, WindowEvent}, keyboard::{KeyCode, PhysicalKey}};
+
+
+/// Everything the player can do with a key or a mouse button
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    MoveForward,
+    MoveBackward,
+    MoveLeft,
+    MoveRight,
+    Jump,
+    Sneak,
+    Sprint,
+    BreakBlock,
+    PlaceBlock,
+    PickBlock,
+    HotbarNext,
+    HotbarPrev,
+    HotbarSlot1,
+    HotbarSlot2,
+    HotbarSlot3,
+    HotbarSlot4,
+    HotbarSlot5,
+    HotbarSlot6,
+    HotbarSlot7,
+    HotbarSlot8,
+    HotbarSlot9,
+    OpenInventory,
+    ToggleSpectator,
+    /// Shows the F3 debug overlay
+    ToggleDebug,
+    RenderDistanceUp,
+    RenderDistanceDown,
+    Pause,
+    /// Opens the console to chat
+    OpenConsole,
+    /// Opens the console with a `/` to type a command
+    OpenCommand,
+}
+
+impl Action {
+    pub const ALL: [Action; 29] = [
+        Action::MoveForward,
+        Action::MoveBackward,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Jump,
+        Action::Sneak,
+        Action::Sprint,
+        Action::BreakBlock,
+        Action::PlaceBlock,
+        Action::PickBlock,
+        Action::HotbarNext,
+        Action::HotbarPrev,
+        Action::HotbarSlot1,
+        Action::HotbarSlot2,
+        Action::HotbarSlot3,
+        Action::HotbarSlot4,
+        Action::HotbarSlot5,
+        Action::HotbarSlot6,
+        Action::HotbarSlot7,
+        Action::HotbarSlot8,
+        Action::HotbarSlot9,
+        Action::OpenInventory,
+        Action::ToggleSpectator,
+        Action::ToggleDebug,
+        Action::RenderDistanceUp,
+        Action::RenderDistanceDown,
+        Action::Pause,
+        Action::OpenConsole,
+        Action::OpenCommand,
+    ];
+
+    /// Slot selected by the `HotbarSlotN` actions
+    pub fn hotbar_slot(self) -> Option<usize> {
+        let slot = match self {
+            Action::HotbarSlot1 => 0,
+            Action::HotbarSlot2 => 1,
+            Action::HotbarSlot3 => 2,
+            Action::HotbarSlot4 => 3,
+            Action::HotbarSlot5 => 4,
+            Action::HotbarSlot6 => 5,
+            Action::HotbarSlot7 => 6,
+            Action::HotbarSlot8 => 7,
+            Action::HotbarSlot9 => 8,
+            _ => return None,
+        };
+        Some(slot)
+    }
+
+    /// Name shown in the settings screen, `MoveForward` is "Move forward"
+    pub fn label(self) -> String {
+        let mut label = String::new();
+        for (i, c) in format!("{:?}", self).chars().enumerate() {
+            if i > 0 && (c.is_uppercase() || (c.is_ascii_digit() && !label.ends_with(' '))) {
+                label.push(' ');
+                label.extend(c.to_lowercase());
+            } else {
+                label.push(c);
+            }
+        }
+        label
+    }
+
+    fn default_bindings(self) -> Vec<Binding> {
+        use Binding::*;
+        match self {
+            Action::MoveForward => vec![Key(KeyCode::KeyW), Key(KeyCode::ArrowUp)],
+            Action::MoveBackward => vec![Key(KeyCode::KeyS), Key(KeyCode::ArrowDown)],
+            Action::MoveLeft => vec![Key(KeyCode::KeyA), Key(KeyCode::ArrowLeft)],
+            Action::MoveRight => vec![Key(KeyCode::KeyD), Key(KeyCode::ArrowRight)],
+            Action::Jump => vec![Key(KeyCode::Space)],
+            Action::Sneak => vec![Key(KeyCode::ShiftLeft)],
+            Action::Sprint => vec![Key(KeyCode::ControlLeft)],
+            Action::BreakBlock => vec![Mouse(MouseButton::Left)],
+            Action::PlaceBlock => vec![Mouse(MouseButton::Right)],
+            Action::PickBlock => vec![Mouse(MouseButton::Middle)],
+            Action::HotbarNext => vec![WheelUp],
+            Action::HotbarPrev => vec![WheelDown],
+            Action::HotbarSlot1 => vec![Key(KeyCode::Digit1)],
+            Action::HotbarSlot2 => vec![Key(KeyCode::Digit2)],
+            Action::HotbarSlot3 => vec![Key(KeyCode::Digit3)],
+            Action::HotbarSlot4 => vec![Key(KeyCode::Digit4)],
+            Action::HotbarSlot5 => vec![Key(KeyCode::Digit5)],
+            Action::HotbarSlot6 => vec![Key(KeyCode::Digit6)],
+            Action::HotbarSlot7 => vec![Key(KeyCode::Digit7)],
+            Action::HotbarSlot8 => vec![Key(KeyCode::Digit8)],
+            Action::HotbarSlot9 => vec![Key(KeyCode::Digit9)],
+            Action::OpenInventory => vec![Key(KeyCode::KeyE)],
+            Action::ToggleSpectator => vec![Key(KeyCode::KeyN)],
+            Action::ToggleDebug => vec![Key(KeyCode::F3)],
+            Action::RenderDistanceUp => vec![Key(KeyCode::Equal)],
+            Action::RenderDistanceDown => vec![Key(KeyCode::Minus)],
+            Action::Pause => vec![Key(KeyCode::Escape)],
+            Action::OpenConsole => vec![Key(KeyCode::KeyT)],
+            Action::OpenCommand => vec![Key(KeyCode::Slash)],
+        }
+    }
+}
+
+
+/// A physical input an action can be bound to
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    /// Wheel notches only trigger actions, they are never held down
+    WheelUp,
+    WheelDown,
+}
+
+impl Binding {
+    /// Name shown in the settings screen
+    pub fn label(self) -> String {
+        match self {
+            Binding::Key(key) => {
+                let name = format!("{:?}", key);
+                match name.strip_prefix("Key").or_else(|| name.strip_prefix("Digit")) {
+                    Some(short) => short.to_string(),
+                    None => name,
+                }
+            }
+            Binding::Mouse(MouseButton::Left) => "Left click".to_string(),
+            Binding::Mouse(MouseButton::Right) => "Right click".to_string(),
+            Binding::Mouse(MouseButton::Middle) => "Middle click".to_string(),
+            Binding::Mouse(button) => format!("Mouse {:?}", button),
+            Binding::WheelUp => "Wheel up".to_string(),
+            Binding::WheelDown => "Wheel down".to_string(),
+        }
+    }
+
+    /// The binding touched by `event` and whether it went down, `None` for other events
+    pub fn from_window_event(event: &WindowEvent) -> Option<(Binding, ElementState)> {
+        match event {
+            WindowEvent::KeyboardInput {
+                event: KeyEvent {
+                    physical_key: PhysicalKey::Code(key),
+                    state,
+                    ..
+                },
+                ..
+            } => Some((Binding::Key(*key), *state)),
+            WindowEvent::MouseInput { state, button, .. } => Some((Binding::Mouse(*button), *state)),
+            WindowEvent::MouseWheel { delta, .. } => {
+                let y = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => *y as f64,
+                    MouseScrollDelta::PixelDelta(pos) => pos.y,
+                };
+                if y > 0.0 {
+                    Some((Binding::WheelUp, ElementState::Pressed))
+                } else if y < 0.0 {
+                    Some((Binding::WheelDown, ElementState::Pressed))
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+
+/// Which bindings trigger each action, saved in the settings file.
+/// Actions missing from the file keep their default bindings, unknown ones are skipped.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(from = "BTreeMap<String, Vec<Binding>>", into = "BTreeMap<Action, Vec<Binding>>")]
+pub struct InputMap {
+    bindings: BTreeMap<Action, Vec<Binding>>,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        Self {
+            bindings: Action::ALL.iter().map(|&action| (action, action.default_bindings())).collect(),
+        }
+    }
+}
+
+impl From<BTreeMap<String, Vec<Binding>>> for InputMap {
+    fn from(bindings: BTreeMap<String, Vec<Binding>>) -> Self {
+        let mut map = Self::default();
+        for (name, bindings) in bindings {
+            // una acción renombrada o de otra versión no invalida el resto de los ajustes
+            let action: Result<Action, serde::de::value::Error> = Action::deserialize(name.as_str().into_deserializer());
+            match action {
+                Ok(action) => map.set_bindings(action, bindings),
+                Err(_) => log::warn!("Skipping bindings of unknown action '{}'", name),
+            }
+        }
+        map
+    }
+}
+
+impl From<InputMap> for BTreeMap<Action, Vec<Binding>> {
+    fn from(map: InputMap) -> Self {
+        map.bindings
+    }
+}
+
+impl InputMap {
+    pub fn bindings(&self, action: Action) -> &[Binding] {
+        self.bindings.get(&action).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Actions triggered by `binding`
+    pub fn actions(&self, binding: Binding) -> impl Iterator<Item = Action> + '_ {
+        self.bindings.iter()
+            .filter(move |(_, bindings)| bindings.contains(&binding))
+            .map(|(action, _)| *action)
+    }
+
+    /// Adds `binding` to `action`. Returns the other actions already bound to it,
+    /// the binding is kept anyway so the caller decides what to do about them.
+    pub fn bind(&mut self, action: Action, binding: Binding) -> Vec<Action> {
+        let conflicts = self.actions(binding).filter(|other| *other != action).collect();
+
+        let bindings = self.bindings.entry(action).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+        conflicts
+    }
+
+    pub fn unbind(&mut self, action: Action, binding: Binding) {
+        if let Some(bindings) = self.bindings.get_mut(&action) {
+            bindings.retain(|b| *b != binding);
+        }
+    }
+
+    /// Replaces every binding of `action`
+    pub fn set_bindings(&mut self, action: Action, bindings: Vec<Binding>) {
+        self.bindings.insert(action, bindings);
+    }
+
+    /// Bindings shared by more than one action
+    pub fn conflicts(&self) -> Vec<(Binding, Vec<Action>)> {
+        let mut seen = HashSet::new();
+        let mut conflicts = Vec::new();
+
+        for binding in self.bindings.values().flatten() {
+            if !seen.insert(*binding) {
+                continue;
+            }
+            let actions: Vec<_> = self.actions(*binding).collect();
+            if actions.len() > 1 {
+                conflicts.push((*binding, actions));
+            }
+        }
+        conflicts
+    }
+}
+
+
+/// Bindings held down right now, translated to actions through the input map
+pub struct InputState {
+    pub map: InputMap,
+    down: HashSet<Binding>,
+}
+
+impl InputState {
+    pub fn new(map: InputMap) -> Self {
+        for (binding, actions) in map.conflicts() {
+            log::warn!("{:?} is bound to several actions: {:?}", binding, actions);
+        }
+
+        Self {
+            map,
+            down: HashSet::new(),
+        }
+    }
+
+    /// Records key and mouse button changes. Returns the actions that were just
+    /// pressed, or `None` if the event isn't an input event.
+    pub fn handle_window_event(&mut self, event: &WindowEvent) -> Option<Vec<Action>> {
+        let (binding, state) = Binding::from_window_event(event)?;
+        let repeat = matches!(event, WindowEvent::KeyboardInput { event: KeyEvent { repeat: true, .. }, .. });
+
+        match state {
+            ElementState::Pressed => {
+                let wheel = matches!(binding, Binding::WheelUp | Binding::WheelDown);
+                if !wheel {
+                    self.down.insert(binding);
+                }
+                if repeat {
+                    return Some(vec![]);
+                }
+                Some(self.map.actions(binding).collect())
+            }
+            ElementState::Released => {
+                self.down.remove(&binding);
+                Some(vec![])
+            }
+        }
+    }
+
+    pub fn is_down(&self, action: Action) -> bool {
+        self.map.bindings(action).iter().any(|binding| self.down.contains(binding))
+    }
+
+    /// 1.0 when `positive` is held, -1.0 for `negative`, 0.0 for both or none
+    pub fn axis(&self, positive: Action, negative: Action) -> f32 {
+        self.is_down(positive) as i32 as f32 - self.is_down(negative) as i32 as f32
+    }
+
+    /// Forgets held bindings, for when the game stops receiving input (pause, focus lost)
+    pub fn release_all(&mut self) {
+        self.down.clear();
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const J: Binding = Binding::Key(KeyCode::KeyJ);
+
+    #[test]
+    fn round_trips_through_the_file() {
+        let mut map = InputMap::default();
+        map.set_bindings(Action::Jump, vec![J, Binding::Mouse(MouseButton::Middle)]);
+        map.unbind(Action::Sneak, Binding::Key(KeyCode::ShiftLeft));
+
+        let json = serde_json::to_string(&map).unwrap();
+        assert!(json.contains(r#""jump":[{"key":"KeyJ"},{"mouse":"Middle"}]"#));
+        assert_eq!(serde_json::from_str::<InputMap>(&json).unwrap(), map);
+    }
+
+    #[test]
+    fn missing_actions_keep_their_defaults() {
+        let map: InputMap = serde_json::from_str(r#"{ "jump": [{ "key": "KeyJ" }] }"#).unwrap();
+        assert_eq!(map.bindings(Action::Jump), &[J]);
+        assert_eq!(map.bindings(Action::MoveForward), Action::MoveForward.default_bindings().as_slice());
+        assert!(Action::ALL.iter().all(|action| !map.bindings(*action).is_empty()));
+    }
+
+    #[test]
+    fn unknown_actions_are_skipped() {
+        let json = r#"{ "jump": [{ "key": "KeyJ" }], "dance": [{ "key": "KeyK" }] }"#;
+        let map: InputMap = serde_json::from_str(json).unwrap();
+        assert_eq!(map.bindings(Action::Jump), &[J]);
+        assert_eq!(map.actions(Binding::Key(KeyCode::KeyK)).count(), 0);
+    }
+
+    #[test]
+    fn binding_reports_conflicts() {
+        let mut map = InputMap::default();
+        assert!(map.conflicts().is_empty());
+
+        let space = Action::Jump.default_bindings()[0];
+        assert_eq!(map.bind(Action::PickBlock, space), vec![Action::Jump]);
+        // se guarda igual, el que llama decide qué hacer
+        assert_eq!(map.actions(space).collect::<Vec<_>>(), vec![Action::Jump, Action::PickBlock]);
+        assert_eq!(map.conflicts(), vec![(space, vec![Action::Jump, Action::PickBlock])]);
+        // volver a añadirla no la repite
+        map.bind(Action::PickBlock, space);
+        assert_eq!(map.bindings(Action::PickBlock).iter().filter(|b| **b == space).count(), 1);
+
+        map.unbind(Action::PickBlock, space);
+        assert!(map.conflicts().is_empty());
+        assert!(map.bind(Action::Jump, J).is_empty());
+    }
+}
@@ -0,0 +1,411 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+
+use super::{inventory::Inventory, Item, ItemStack};
+
+
+/// Side of the crafting grid in the inventory screen
+pub const INVENTORY_GRID_SIZE: usize = 2;
+/// Side of the crafting grid of the crafting table, also the biggest recipe
+pub const TABLE_GRID_SIZE: usize = 3;
+
+/// File name and contents of the recipes in `assets/recipes`, sorted by name
+const SHIPPED_RECIPES: &[(&str, &str)] = include!(concat!(env!("OUT_DIR"), "/recipes.rs"));
+
+
+/// What a recipe turns the grid into
+#[derive(Clone, Debug, PartialEq)]
+pub enum Recipe {
+    /// Items in a fixed layout that can be moved around the grid or mirrored left to right
+    Shaped {
+        width: usize,
+        height: usize,
+        /// Row by row, `None` for the cells that have to stay empty
+        cells: Vec<Option<Item>>,
+        result: ItemStack,
+    },
+    /// Items anywhere in the grid, one per slot
+    Shapeless {
+        ingredients: Vec<Item>,
+        result: ItemStack,
+    },
+}
+
+impl Recipe {
+    pub fn result(&self) -> ItemStack {
+        match self {
+            Recipe::Shaped { result, .. } | Recipe::Shapeless { result, .. } => *result,
+        }
+    }
+
+    /// Whether the grid, `size` slots a side and row by row, holds this recipe
+    pub fn matches(&self, grid: &[Option<Item>], size: usize) -> bool {
+        match self {
+            Recipe::Shaped { width, height, cells, .. } => {
+                let Some((left, top, used_width, used_height)) = bounds(grid, size) else {
+                    return false;
+                };
+                if (used_width, used_height) != (*width, *height) {
+                    return false;
+                }
+
+                let cell = |x: usize, y: usize| grid[(top + y) * size + left + x];
+                let straight = (0..*height).all(|y| (0..*width).all(|x| cell(x, y) == cells[y * width + x]));
+                let mirrored = (0..*height).all(|y| (0..*width).all(|x| cell(width - 1 - x, y) == cells[y * width + x]));
+                straight || mirrored
+            }
+            Recipe::Shapeless { ingredients, .. } => {
+                let mut missing = ingredients.clone();
+                for item in grid.iter().flatten() {
+                    let Some(i) = missing.iter().position(|ingredient| ingredient == item) else {
+                        return false;
+                    };
+                    missing.swap_remove(i);
+                }
+                missing.is_empty()
+            }
+        }
+    }
+
+    /// Parses the JSON of a recipe file
+    pub fn from_json(json: &str) -> Result<Self> {
+        let data: RecipeData = serde_json::from_str(json)?;
+        data.try_into()
+    }
+}
+
+/// Smallest box around the occupied cells as `(left, top, width, height)`, `None` if the grid is empty
+fn bounds(grid: &[Option<Item>], size: usize) -> Option<(usize, usize, usize, usize)> {
+    let occupied = || grid.iter().enumerate().filter(|(_, cell)| cell.is_some()).map(|(i, _)| (i % size, i / size));
+
+    let left = occupied().map(|(x, _)| x).min()?;
+    let right = occupied().map(|(x, _)| x).max()?;
+    let top = occupied().map(|(_, y)| y).min()?;
+    let bottom = occupied().map(|(_, y)| y).max()?;
+    Some((left, top, right - left + 1, bottom - top + 1))
+}
+
+
+/// Recipe file as written in `assets/recipes`
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum RecipeData {
+    Shaped {
+        pattern: Vec<String>,
+        key: HashMap<char, String>,
+        result: ResultData,
+    },
+    Shapeless {
+        ingredients: Vec<String>,
+        result: ResultData,
+    },
+}
+
+#[derive(Deserialize)]
+struct ResultData {
+    item: String,
+    #[serde(default = "one")]
+    count: u32,
+}
+
+fn one() -> u32 {
+    1
+}
+
+fn item_named(name: &str) -> Result<Item> {
+    Item::from_name(name).ok_or_else(|| anyhow!("unknown item `{}`", name))
+}
+
+impl TryFrom<RecipeData> for Recipe {
+    type Error = anyhow::Error;
+
+    fn try_from(data: RecipeData) -> Result<Self> {
+        match data {
+            RecipeData::Shaped { pattern, key, result } => {
+                let height = pattern.len();
+                let width = pattern.first().map_or(0, |row| row.chars().count());
+                if width == 0 || width > TABLE_GRID_SIZE || height > TABLE_GRID_SIZE {
+                    bail!("the pattern has to be between 1x1 and {0}x{0}", TABLE_GRID_SIZE);
+                }
+
+                let mut cells = Vec::with_capacity(width * height);
+                for row in &pattern {
+                    if row.chars().count() != width {
+                        bail!("every row of the pattern needs {} characters", width);
+                    }
+                    for symbol in row.chars() {
+                        cells.push(match symbol {
+                            ' ' => None,
+                            symbol => {
+                                let name = key.get(&symbol).ok_or_else(|| anyhow!("`{}` is not in the key", symbol))?;
+                                Some(item_named(name)?)
+                            }
+                        });
+                    }
+                }
+
+                Ok(Recipe::Shaped {
+                    width,
+                    height,
+                    cells,
+                    result: ItemStack::new(item_named(&result.item)?, result.count),
+                })
+            }
+            RecipeData::Shapeless { ingredients, result } => {
+                if ingredients.is_empty() || ingredients.len() > TABLE_GRID_SIZE * TABLE_GRID_SIZE {
+                    bail!("a shapeless recipe needs between 1 and {} ingredients", TABLE_GRID_SIZE * TABLE_GRID_SIZE);
+                }
+
+                Ok(Recipe::Shapeless {
+                    ingredients: ingredients.iter().map(|name| item_named(name)).collect::<Result<_>>()?,
+                    result: ItemStack::new(item_named(&result.item)?, result.count),
+                })
+            }
+        }
+    }
+}
+
+
+/// Every known recipe
+#[derive(Default)]
+pub struct RecipeBook {
+    recipes: Vec<Recipe>,
+}
+
+impl RecipeBook {
+    pub fn new(recipes: Vec<Recipe>) -> Self {
+        Self { recipes }
+    }
+
+    /// Recipes shipped with the game, the files of `assets/recipes` are embedded by build.rs
+    pub fn load_assets() -> Result<Self> {
+        let recipes = SHIPPED_RECIPES.iter()
+            .map(|(name, json)| Recipe::from_json(json).with_context(|| format!("loading recipe {}", name)))
+            .collect::<Result<_>>()?;
+        Ok(Self { recipes })
+    }
+
+    pub fn len(&self) -> usize {
+        self.recipes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.recipes.is_empty()
+    }
+
+    pub fn find(&self, grid: &[Option<Item>], size: usize) -> Option<&Recipe> {
+        self.recipes.iter().find(|recipe| recipe.matches(grid, size))
+    }
+}
+
+
+/// Square grid where the ingredients go, `size` slots a side
+#[derive(Clone, Debug, PartialEq)]
+pub struct CraftingGrid {
+    size: usize,
+    slots: Inventory,
+}
+
+impl CraftingGrid {
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            slots: Inventory::new(size * size),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Grid slots row by row, they work like any other inventory
+    pub fn slots(&self) -> &Inventory {
+        &self.slots
+    }
+
+    pub fn slots_mut(&mut self) -> &mut Inventory {
+        &mut self.slots
+    }
+
+    /// Stack that crafting would give right now
+    pub fn result(&self, recipes: &RecipeBook) -> Option<ItemStack> {
+        recipes.find(&self.items(), self.size).map(Recipe::result)
+    }
+
+    /// Uses up one unit of every ingredient and returns the result
+    pub fn craft(&mut self, recipes: &RecipeBook) -> Option<ItemStack> {
+        let result = self.result(recipes)?;
+        for slot in 0..self.slots.len() {
+            self.slots.remove(slot, 1);
+        }
+        Some(result)
+    }
+
+    /// Moves the ingredients into `inventory`. What doesn't fit stays in its slot,
+    /// returns false if something did.
+    pub fn empty_into(&mut self, inventory: &mut Inventory) -> bool {
+        for slot in 0..self.slots.len() {
+            let rest = self.slots.take(slot).and_then(|stack| inventory.add(stack));
+            self.slots.set(slot, rest);
+        }
+        self.slots.slots().iter().all(Option::is_none)
+    }
+
+    fn items(&self) -> Vec<Option<Item>> {
+        self.slots.slots().iter().map(|slot| slot.map(|stack| stack.item)).collect()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{item::tool::ToolKind, render::atlas::MaterialType};
+
+    use super::*;
+
+    const DIRT: Item = Item::Block(MaterialType::DIRT);
+    const ROCK: Item = Item::Block(MaterialType::ROCK);
+    const LOG: Item = Item::Block(MaterialType::LOG);
+    const PLANKS: Item = Item::Block(MaterialType::PLANKS);
+
+    /// Builds a grid from rows of `D`irt, `R`ock, `L`og, `P`lanks and spaces
+    fn grid(rows: &[&str]) -> Vec<Option<Item>> {
+        rows.iter().flat_map(|row| row.chars()).map(|symbol| match symbol {
+            'D' => Some(DIRT),
+            'R' => Some(ROCK),
+            'L' => Some(LOG),
+            'P' => Some(PLANKS),
+            _ => None,
+        }).collect()
+    }
+
+    /// An L of rock with dirt in the corner, different when mirrored
+    fn corner() -> Recipe {
+        Recipe::from_json(r#"{
+            "type": "shaped",
+            "pattern": ["RD", " R"],
+            "key": { "R": "rock", "D": "dirt" },
+            "result": { "item": "log", "count": 2 }
+        }"#).unwrap()
+    }
+
+    #[test]
+    fn parses_shaped_recipes() {
+        let Recipe::Shaped { width, height, cells, result } = corner() else {
+            panic!("not shaped");
+        };
+
+        assert_eq!((width, height), (2, 2));
+        assert_eq!(cells, vec![Some(ROCK), Some(DIRT), None, Some(ROCK)]);
+        assert_eq!(result, ItemStack::new(LOG, 2));
+    }
+
+    #[test]
+    fn rejects_bad_recipes() {
+        let unknown_item = r#"{ "type": "shapeless", "ingredients": ["diamond"], "result": { "item": "log" } }"#;
+        let missing_key = r#"{ "type": "shaped", "pattern": ["X"], "key": {}, "result": { "item": "log" } }"#;
+        let ragged = r#"{ "type": "shaped", "pattern": ["XX", "X"], "key": { "X": "log" }, "result": { "item": "log" } }"#;
+        let too_big = r#"{ "type": "shaped", "pattern": ["XXXX"], "key": { "X": "log" }, "result": { "item": "log" } }"#;
+
+        for json in [unknown_item, missing_key, ragged, too_big] {
+            assert!(Recipe::from_json(json).is_err(), "{}", json);
+        }
+    }
+
+    #[test]
+    fn shaped_matches_at_any_offset() {
+        let recipe = corner();
+
+        assert!(recipe.matches(&grid(&["RD", " R"]), 2));
+        assert!(recipe.matches(&grid(&["RD ", " R ", "   "]), 3));
+        assert!(recipe.matches(&grid(&["   ", " RD", "  R"]), 3));
+    }
+
+    #[test]
+    fn shaped_matches_mirrored() {
+        let recipe = corner();
+
+        assert!(recipe.matches(&grid(&["DR", "R "]), 2));
+        assert!(recipe.matches(&grid(&["   ", "DR ", "R  "]), 3));
+        // girado no es lo mismo que reflejado
+        assert!(!recipe.matches(&grid(&["R ", "DR"]), 2));
+    }
+
+    #[test]
+    fn shaped_needs_the_exact_shape() {
+        let recipe = corner();
+
+        assert!(!recipe.matches(&grid(&["RD", "RR"]), 2));
+        assert!(!recipe.matches(&grid(&["RR", " R"]), 2));
+        assert!(!recipe.matches(&grid(&["RD ", " R ", "  R"]), 3));
+        assert!(!recipe.matches(&grid(&["    "]), 2));
+    }
+
+    #[test]
+    fn shapeless_ignores_positions() {
+        let recipe = Recipe::from_json(r#"{
+            "type": "shapeless",
+            "ingredients": ["dirt", "rock", "dirt"],
+            "result": { "item": "grass" }
+        }"#).unwrap();
+
+        assert!(recipe.matches(&grid(&["DR", "D "]), 2));
+        assert!(recipe.matches(&grid(&["  D", "R  ", "  D"]), 3));
+        assert!(!recipe.matches(&grid(&["DR", "  "]), 2));
+        assert!(!recipe.matches(&grid(&["DR", "DD"]), 2));
+        assert!(!recipe.matches(&grid(&["DR", "DP"]), 2));
+    }
+
+    #[test]
+    fn crafting_uses_one_of_each_ingredient() {
+        let book = RecipeBook::new(vec![corner()]);
+        let mut crafting = CraftingGrid::new(2);
+        crafting.slots_mut().set(0, Some(ItemStack::new(ROCK, 3)));
+        crafting.slots_mut().set(1, Some(ItemStack::new(DIRT, 1)));
+        crafting.slots_mut().set(3, Some(ItemStack::new(ROCK, 1)));
+
+        assert_eq!(crafting.craft(&book), Some(ItemStack::new(LOG, 2)));
+        assert_eq!(crafting.slots().get(0), Some(&ItemStack::new(ROCK, 2)));
+        assert!(crafting.slots().get(1).is_none());
+        assert_eq!(crafting.result(&book), None);
+    }
+
+    #[test]
+    fn emptying_keeps_what_does_not_fit() {
+        let mut crafting = CraftingGrid::new(2);
+        crafting.slots_mut().set(0, Some(ItemStack::new(ROCK, 3)));
+        crafting.slots_mut().set(2, Some(ItemStack::new(DIRT, 1)));
+
+        // un solo hueco: la roca entra, la tierra se queda en la rejilla
+        let mut inventory = Inventory::new(1);
+        assert!(inventory.fits(ItemStack::new(ROCK, 3)));
+        assert!(!crafting.empty_into(&mut inventory));
+        assert_eq!(inventory.get(0), Some(&ItemStack::new(ROCK, 3)));
+        assert_eq!(crafting.slots().get(2), Some(&ItemStack::new(DIRT, 1)));
+        assert!(!inventory.fits(ItemStack::new(DIRT, 1)));
+
+        assert!(crafting.empty_into(&mut Inventory::new(1)));
+        assert!(crafting.slots().get(2).is_none());
+    }
+
+    #[test]
+    fn shipped_recipes_load() {
+        let book = RecipeBook::load_assets().unwrap();
+        assert!(!book.is_empty());
+
+        let table = book.find(&grid(&["PP", "PP"]), 2).unwrap();
+        assert_eq!(table.result().item, Item::Block(MaterialType::CRAFTING_TABLE));
+        let planks = book.find(&grid(&["   ", "   ", "  L"]), 3).unwrap();
+        assert_eq!(planks.result(), ItemStack::new(PLANKS, 4));
+
+        // el hacha no es simétrica, tiene que salir de las dos maneras
+        let stick = Some(Item::Stick);
+        let axe = |rows: [[Option<Item>; 3]; 3]| book.find(&rows.concat(), 3).map(Recipe::result);
+        let left = axe([[Some(PLANKS), Some(PLANKS), None], [Some(PLANKS), stick, None], [None, stick, None]]);
+        let right = axe([[None, Some(PLANKS), Some(PLANKS)], [None, stick, Some(PLANKS)], [None, stick, None]]);
+        assert_eq!(left, right);
+        assert_eq!(left.and_then(|result| result.item.tool()).map(|tool| tool.kind), Some(ToolKind::Axe));
+    }
+}
@@ -0,0 +1,454 @@
+use super::{Item, ItemStack};
+
+
+/// The first slots of the inventory are the hotbar
+pub const HOTBAR_SIZE: usize = 9;
+/// Hotbar plus three rows of nine
+pub const INVENTORY_SIZE: usize = HOTBAR_SIZE * 4;
+
+
+/// Fixed number of slots holding item stacks, plus the selected hotbar slot
+#[derive(Clone, Debug, PartialEq)]
+pub struct Inventory {
+    slots: Vec<Option<ItemStack>>,
+    selected: usize,
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Self::new(INVENTORY_SIZE)
+    }
+}
+
+impl Inventory {
+    /// Inventories smaller than the hotbar, like the crafting grid, have a shorter hotbar
+    pub fn new(size: usize) -> Self {
+        Self {
+            slots: vec![None; size],
+            selected: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.iter().all(Option::is_none)
+    }
+
+    pub fn get(&self, slot: usize) -> Option<&ItemStack> {
+        self.slots.get(slot).and_then(Option::as_ref)
+    }
+
+    pub fn slots(&self) -> &[Option<ItemStack>] {
+        &self.slots
+    }
+
+    pub fn hotbar(&self) -> &[Option<ItemStack>] {
+        &self.slots[..HOTBAR_SIZE.min(self.slots.len())]
+    }
+
+    /// Replaces the contents of `slot`, returns what was there
+    pub fn set(&mut self, slot: usize, stack: Option<ItemStack>) -> Option<ItemStack> {
+        std::mem::replace(&mut self.slots[slot], stack.filter(|stack| stack.count > 0))
+    }
+
+    pub fn take(&mut self, slot: usize) -> Option<ItemStack> {
+        self.slots[slot].take()
+    }
+
+    /// Total units of `item` over every slot
+    pub fn count(&self, item: Item) -> u32 {
+        self.slots.iter().flatten().filter(|stack| stack.item == item).map(|stack| stack.count).sum()
+    }
+
+    /// Puts `stack` in the inventory, topping up stacks of the same item before using
+    /// empty slots. Returns what didn't fit.
+    pub fn add(&mut self, stack: ItemStack) -> Option<ItemStack> {
+        let mut rest = Some(stack);
+
+        for slot in self.slots.iter_mut().flatten() {
+            let Some(stack) = rest else { break };
+            rest = slot.merge(stack);
+        }
+
+        for slot in self.slots.iter_mut().filter(|slot| slot.is_none()) {
+            let Some(mut stack) = rest else { break };
+            let max = stack.item.max_stack_size();
+            if stack.count > max {
+                *slot = Some(stack.split(max));
+                rest = Some(stack);
+            } else {
+                *slot = Some(stack);
+                rest = None;
+            }
+        }
+
+        rest
+    }
+
+    /// All of `stack` would fit with `add`
+    pub fn fits(&self, stack: ItemStack) -> bool {
+        self.clone().add(stack).is_none()
+    }
+
+    /// Takes up to `count` units out of `slot`
+    pub fn remove(&mut self, slot: usize, count: u32) -> Option<ItemStack> {
+        let slot = self.slots.get_mut(slot)?;
+        let stack = slot.as_mut()?;
+        let removed = stack.split(count.min(stack.count));
+        if stack.count == 0 {
+            *slot = None;
+        }
+        (removed.count > 0).then_some(removed)
+    }
+
+    /// Takes half of `slot`, rounded up, leaving the rest
+    pub fn split(&mut self, slot: usize) -> Option<ItemStack> {
+        let half = self.get(slot)?.count.div_ceil(2);
+        self.remove(slot, half)
+    }
+
+    /// Moves `from` onto `to`. Stacks of the same item merge as far as they fit,
+    /// different items swap places.
+    pub fn move_stack(&mut self, from: usize, to: usize) {
+        if from == to {
+            return;
+        }
+
+        let Some(moving) = self.slots[from].take() else { return };
+        self.slots[from] = match &mut self.slots[to] {
+            Some(target) if target.item == moving.item => target.merge(moving),
+            target => target.replace(moving),
+        };
+    }
+
+    /// Clicks `slot` while holding `held` on the cursor, returns what the cursor holds after.
+    /// The primary button picks up, drops, merges or swaps whole stacks. The secondary one
+    /// picks up half of the slot or drops a single unit.
+    pub fn click(&mut self, slot: usize, held: Option<ItemStack>, secondary: bool) -> Option<ItemStack> {
+        let Some(mut held) = held else {
+            return if secondary { self.split(slot) } else { self.take(slot) };
+        };
+
+        match &mut self.slots[slot] {
+            None if secondary => {
+                self.slots[slot] = Some(held.split(1));
+                (held.count > 0).then_some(held)
+            }
+            Some(target) if secondary && target.item == held.item => {
+                if target.space() > 0 {
+                    target.count += 1;
+                    held.count -= 1;
+                }
+                (held.count > 0).then_some(held)
+            }
+            Some(target) if target.item == held.item => target.merge(held),
+            target => target.replace(held),
+        }
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Slots the selection moves through, fewer than `HOTBAR_SIZE` in small inventories
+    fn hotbar_len(&self) -> usize {
+        HOTBAR_SIZE.min(self.slots.len())
+    }
+
+    pub fn select(&mut self, slot: usize) {
+        self.selected = slot.min(self.hotbar_len().saturating_sub(1));
+    }
+
+    /// Moves the selection `delta` hotbar slots, wrapping around the ends
+    pub fn scroll(&mut self, delta: i32) {
+        let len = self.hotbar_len().max(1) as i32;
+        self.selected = (self.selected as i32 + delta).rem_euclid(len) as usize;
+    }
+
+    pub fn selected_stack(&self) -> Option<&ItemStack> {
+        self.get(self.selected)
+    }
+
+    /// Takes one unit of the selected stack
+    pub fn consume_selected(&mut self) -> Option<Item> {
+        self.remove(self.selected, 1).map(|stack| stack.item)
+    }
+
+    /// Wears the selected item down after using it, returns true if it broke
+    pub fn wear_selected(&mut self) -> bool {
+        let Some(slot) = self.slots.get_mut(self.selected) else {
+            return false;
+        };
+        let broke = slot.as_mut().is_some_and(ItemStack::wear_out);
+        if broke {
+            *slot = None;
+        }
+        broke
+    }
+
+    /// Selects the hotbar slot holding `item`. Otherwise a stack from the rest of the
+    /// inventory is swapped into the selected slot, and in creative a new stack is made.
+    /// Returns false if nothing changed.
+    pub fn pick(&mut self, item: Item, creative: bool) -> bool {
+        let holds = |slot: &Option<ItemStack>| slot.is_some_and(|stack| stack.item == item);
+
+        if let Some(slot) = self.hotbar().iter().position(holds) {
+            self.selected = slot;
+        } else if self.selected >= self.slots.len() {
+            return false;
+        } else if let Some(slot) = self.slots.iter().position(holds) {
+            self.slots.swap(slot, self.selected);
+        } else if creative {
+            self.slots[self.selected] = Some(ItemStack::new(item, item.max_stack_size()));
+        } else {
+            return false;
+        }
+        true
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{item::MAX_STACK_SIZE, render::atlas::MaterialType};
+
+    use super::*;
+
+    const DIRT: Item = Item::Block(MaterialType::DIRT);
+    const ROCK: Item = Item::Block(MaterialType::ROCK);
+
+    fn stack(item: Item, count: u32) -> Option<ItemStack> {
+        Some(ItemStack::new(item, count))
+    }
+
+    #[test]
+    fn add_fills_the_first_empty_slot() {
+        let mut inventory = Inventory::default();
+
+        assert_eq!(inventory.add(ItemStack::new(DIRT, 10)), None);
+
+        assert_eq!(inventory.slots()[0], stack(DIRT, 10));
+        assert_eq!(inventory.count(DIRT), 10);
+    }
+
+    #[test]
+    fn add_tops_up_existing_stacks_first() {
+        let mut inventory = Inventory::default();
+        inventory.set(5, stack(DIRT, 60));
+        inventory.set(7, stack(DIRT, 30));
+
+        assert_eq!(inventory.add(ItemStack::new(DIRT, 40)), None);
+
+        assert_eq!(inventory.slots()[5], stack(DIRT, MAX_STACK_SIZE));
+        assert_eq!(inventory.slots()[7], stack(DIRT, MAX_STACK_SIZE));
+        assert_eq!(inventory.slots()[0], stack(DIRT, 2));
+    }
+
+    #[test]
+    fn add_splits_oversized_stacks() {
+        let mut inventory = Inventory::default();
+
+        assert_eq!(inventory.add(ItemStack::new(ROCK, 150)), None);
+
+        assert_eq!(inventory.slots()[0], stack(ROCK, 64));
+        assert_eq!(inventory.slots()[1], stack(ROCK, 64));
+        assert_eq!(inventory.slots()[2], stack(ROCK, 22));
+    }
+
+    #[test]
+    fn add_returns_what_does_not_fit() {
+        let mut inventory = Inventory::new(HOTBAR_SIZE);
+        for slot in 0..HOTBAR_SIZE - 1 {
+            inventory.set(slot, stack(ROCK, 1));
+        }
+        inventory.set(HOTBAR_SIZE - 1, stack(DIRT, 50));
+
+        assert_eq!(inventory.add(ItemStack::new(DIRT, 20)), Some(ItemStack::new(DIRT, 6)));
+        assert_eq!(inventory.count(DIRT), MAX_STACK_SIZE);
+    }
+
+    #[test]
+    fn remove_empties_the_slot() {
+        let mut inventory = Inventory::default();
+        inventory.set(3, stack(DIRT, 5));
+
+        assert_eq!(inventory.remove(3, 2), stack(DIRT, 2));
+        assert_eq!(inventory.get(3), Some(&ItemStack::new(DIRT, 3)));
+
+        assert_eq!(inventory.remove(3, 10), stack(DIRT, 3));
+        assert_eq!(inventory.get(3), None);
+        assert_eq!(inventory.remove(3, 1), None);
+    }
+
+    #[test]
+    fn split_takes_half_rounded_up() {
+        let mut inventory = Inventory::default();
+        inventory.set(0, stack(DIRT, 7));
+
+        assert_eq!(inventory.split(0), stack(DIRT, 4));
+        assert_eq!(inventory.get(0), Some(&ItemStack::new(DIRT, 3)));
+
+        inventory.set(1, stack(DIRT, 1));
+        assert_eq!(inventory.split(1), stack(DIRT, 1));
+        assert_eq!(inventory.get(1), None);
+    }
+
+    #[test]
+    fn move_to_an_empty_slot() {
+        let mut inventory = Inventory::default();
+        inventory.set(0, stack(DIRT, 5));
+
+        inventory.move_stack(0, 20);
+
+        assert_eq!(inventory.get(0), None);
+        assert_eq!(inventory.get(20), Some(&ItemStack::new(DIRT, 5)));
+    }
+
+    #[test]
+    fn move_merges_the_same_item() {
+        let mut inventory = Inventory::default();
+        inventory.set(0, stack(DIRT, 40));
+        inventory.set(1, stack(DIRT, 40));
+
+        inventory.move_stack(0, 1);
+
+        assert_eq!(inventory.get(1), Some(&ItemStack::new(DIRT, MAX_STACK_SIZE)));
+        assert_eq!(inventory.get(0), Some(&ItemStack::new(DIRT, 16)));
+    }
+
+    #[test]
+    fn move_swaps_different_items() {
+        let mut inventory = Inventory::default();
+        inventory.set(0, stack(DIRT, 3));
+        inventory.set(1, stack(ROCK, 7));
+
+        inventory.move_stack(0, 1);
+
+        assert_eq!(inventory.get(0), Some(&ItemStack::new(ROCK, 7)));
+        assert_eq!(inventory.get(1), Some(&ItemStack::new(DIRT, 3)));
+    }
+
+    #[test]
+    fn click_picks_up_and_drops_whole_stacks() {
+        let mut inventory = Inventory::default();
+        inventory.set(0, stack(DIRT, 10));
+
+        let held = inventory.click(0, None, false);
+        assert_eq!(held, stack(DIRT, 10));
+        assert_eq!(inventory.get(0), None);
+
+        assert_eq!(inventory.click(5, held, false), None);
+        assert_eq!(inventory.get(5), Some(&ItemStack::new(DIRT, 10)));
+    }
+
+    #[test]
+    fn click_merges_and_swaps() {
+        let mut inventory = Inventory::default();
+        inventory.set(0, stack(DIRT, 60));
+        inventory.set(1, stack(ROCK, 3));
+
+        assert_eq!(inventory.click(0, stack(DIRT, 10), false), stack(DIRT, 6));
+        assert_eq!(inventory.get(0), Some(&ItemStack::new(DIRT, MAX_STACK_SIZE)));
+
+        assert_eq!(inventory.click(1, stack(DIRT, 6), false), stack(ROCK, 3));
+        assert_eq!(inventory.get(1), Some(&ItemStack::new(DIRT, 6)));
+    }
+
+    #[test]
+    fn secondary_click_splits_and_drops_one() {
+        let mut inventory = Inventory::default();
+        inventory.set(0, stack(DIRT, 5));
+
+        let held = inventory.click(0, None, true);
+        assert_eq!(held, stack(DIRT, 3));
+        assert_eq!(inventory.get(0), Some(&ItemStack::new(DIRT, 2)));
+
+        let held = inventory.click(0, held, true);
+        assert_eq!(inventory.get(0), Some(&ItemStack::new(DIRT, 3)));
+        let held = inventory.click(9, held, true);
+        assert_eq!(inventory.get(9), Some(&ItemStack::new(DIRT, 1)));
+        assert_eq!(held, stack(DIRT, 1));
+
+        assert_eq!(inventory.click(9, held, true), None);
+        assert_eq!(inventory.get(9), Some(&ItemStack::new(DIRT, 2)));
+    }
+
+    #[test]
+    fn consume_selected_takes_one() {
+        let mut inventory = Inventory::default();
+        inventory.set(2, stack(ROCK, 1));
+        inventory.select(2);
+
+        assert_eq!(inventory.consume_selected(), Some(ROCK));
+        assert_eq!(inventory.consume_selected(), None);
+        assert!(inventory.is_empty());
+    }
+
+    #[test]
+    fn scroll_wraps_around_the_hotbar() {
+        let mut inventory = Inventory::default();
+
+        inventory.scroll(-1);
+        assert_eq!(inventory.selected(), HOTBAR_SIZE - 1);
+        inventory.scroll(2);
+        assert_eq!(inventory.selected(), 1);
+
+        inventory.select(30);
+        assert_eq!(inventory.selected(), HOTBAR_SIZE - 1);
+    }
+
+    #[test]
+    fn small_inventories_keep_the_selection_inside() {
+        // como la rejilla de 2x2
+        let mut inventory = Inventory::new(4);
+        inventory.select(8);
+        assert_eq!(inventory.selected(), 3);
+        assert!(!inventory.wear_selected());
+        inventory.scroll(1);
+        assert_eq!(inventory.selected(), 0);
+        inventory.scroll(-1);
+        assert_eq!(inventory.selected(), 3);
+
+        let mut empty = Inventory::new(0);
+        empty.select(8);
+        empty.scroll(3);
+        assert!(!empty.wear_selected());
+        assert!(!empty.pick(Item::Stick, true));
+        assert_eq!(empty.consume_selected(), None);
+    }
+
+    #[test]
+    fn pick_prefers_the_hotbar() {
+        let mut inventory = Inventory::default();
+        inventory.set(4, stack(ROCK, 1));
+        inventory.set(20, stack(ROCK, 1));
+
+        assert!(inventory.pick(ROCK, false));
+        assert_eq!(inventory.selected(), 4);
+    }
+
+    #[test]
+    fn pick_swaps_from_the_main_inventory() {
+        let mut inventory = Inventory::default();
+        inventory.set(0, stack(DIRT, 2));
+        inventory.set(20, stack(ROCK, 5));
+
+        assert!(inventory.pick(ROCK, false));
+        assert_eq!(inventory.get(0), Some(&ItemStack::new(ROCK, 5)));
+        assert_eq!(inventory.get(20), Some(&ItemStack::new(DIRT, 2)));
+    }
+
+    #[test]
+    fn pick_only_creates_stacks_in_creative() {
+        let mut inventory = Inventory::default();
+
+        assert!(!inventory.pick(ROCK, false));
+        assert!(inventory.is_empty());
+
+        assert!(inventory.pick(ROCK, true));
+        assert_eq!(inventory.selected_stack(), Some(&ItemStack::new(ROCK, MAX_STACK_SIZE)));
+    }
+}
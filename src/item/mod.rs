@@ -0,0 +1,166 @@
+use tool::Tool;
+
+use crate::{hud::icons_atlas::IconType, render::atlas::MaterialType};
+
+pub mod crafting;
+pub mod inventory;
+pub mod tool;
+
+
+pub const MAX_STACK_SIZE: u32 = 64;
+
+/// Blocks that can be items, by the name used in the data files
+const BLOCK_NAMES: [(MaterialType, &str); 6] = [
+    (MaterialType::DIRT, "dirt"),
+    (MaterialType::GRASS, "grass"),
+    (MaterialType::ROCK, "rock"),
+    (MaterialType::LOG, "log"),
+    (MaterialType::PLANKS, "planks"),
+    (MaterialType::CRAFTING_TABLE, "crafting_table"),
+];
+
+
+/// Anything that can be held in a slot
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Item {
+    Block(MaterialType),
+    Tool(Tool),
+    /// Crafting ingredient for tools
+    Stick,
+}
+
+impl Item {
+    /// What breaking a block of `material` gives
+    pub fn dropped_by(material: MaterialType) -> Option<Item> {
+        match material {
+            MaterialType::GRASS => Some(Item::Block(MaterialType::DIRT)),
+            MaterialType::DIRT | MaterialType::ROCK
+            | MaterialType::LOG | MaterialType::PLANKS | MaterialType::CRAFTING_TABLE => Some(Item::Block(material)),
+            MaterialType::WATER | MaterialType::AIR | MaterialType::DEBUG => None,
+        }
+    }
+
+    /// Block placed when using the item
+    pub fn block(self) -> Option<MaterialType> {
+        match self {
+            Item::Block(material) => Some(material),
+            Item::Tool(_) | Item::Stick => None,
+        }
+    }
+
+    pub fn tool(self) -> Option<Tool> {
+        match self {
+            Item::Tool(tool) => Some(tool),
+            Item::Block(_) | Item::Stick => None,
+        }
+    }
+
+    /// Blocks broken before the item is gone, `None` if it doesn't wear out
+    pub fn durability(self) -> Option<u32> {
+        self.tool().map(|tool| tool.tier.durability())
+    }
+
+    /// Icon drawn in the HUD slots, `None` if the atlas doesn't have one
+    pub fn icon(self) -> Option<IconType> {
+        match self {
+            Item::Block(MaterialType::ROCK) => Some(IconType::ROCK),
+            Item::Block(MaterialType::GRASS) => Some(IconType::GRASS),
+            Item::Block(MaterialType::DIRT) => Some(IconType::DIRT),
+            Item::Block(MaterialType::LOG) => Some(IconType::LOG),
+            Item::Block(MaterialType::PLANKS) => Some(IconType::WOODEN),
+            Item::Block(MaterialType::CRAFTING_TABLE) => Some(IconType::CRAFTING_TABLE),
+            Item::Block(_) => None,
+            Item::Tool(tool) => Some(IconType::TOOL(tool.kind, tool.tier)),
+            Item::Stick => Some(IconType::STICK),
+        }
+    }
+
+    /// Name used by the data files
+    pub fn name(self) -> String {
+        match self {
+            Item::Block(material) => BLOCK_NAMES.iter()
+                .find(|(m, _)| *m == material)
+                .map_or("unknown", |(_, name)| *name)
+                .to_string(),
+            Item::Tool(tool) => tool.name(),
+            Item::Stick => "stick".to_string(),
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Item> {
+        let block = BLOCK_NAMES.iter()
+            .find(|(_, n)| *n == name)
+            .map(|(material, _)| Item::Block(*material));
+        let tool = || Tool::all().find(|tool| tool.name() == name).map(Item::Tool);
+        let stick = || (name == "stick").then_some(Item::Stick);
+        block.or_else(tool).or_else(stick)
+    }
+
+    /// How many times faster than the hand this item breaks `material`
+    pub fn mining_speed(self, material: MaterialType) -> f32 {
+        self.tool().map_or(1.0, |tool| tool.speed(material))
+    }
+
+    pub fn max_stack_size(self) -> u32 {
+        match self {
+            Item::Tool(_) => 1,
+            Item::Block(_) | Item::Stick => MAX_STACK_SIZE,
+        }
+    }
+}
+
+
+/// Several units of the same item in one slot
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ItemStack {
+    pub item: Item,
+    pub count: u32,
+    /// Uses spent out of the item's durability
+    pub wear: u32,
+}
+
+impl ItemStack {
+    pub fn new(item: Item, count: u32) -> Self {
+        Self { item, count, wear: 0 }
+    }
+
+    /// Spends one use, returns true if that wore out the last unit
+    pub fn wear_out(&mut self) -> bool {
+        let Some(durability) = self.item.durability() else {
+            return false;
+        };
+
+        self.wear += 1;
+        if self.wear < durability {
+            return false;
+        }
+        self.wear = 0;
+        self.count -= 1;
+        self.count == 0
+    }
+
+    /// Units that still fit in this stack
+    pub fn space(&self) -> u32 {
+        self.item.max_stack_size().saturating_sub(self.count)
+    }
+
+    /// Adds as much of `other` as fits. Returns the rest, or `other` untouched if it's
+    /// a different item.
+    pub fn merge(&mut self, mut other: ItemStack) -> Option<ItemStack> {
+        if other.item != self.item {
+            return Some(other);
+        }
+
+        let moved = other.count.min(self.space());
+        self.count += moved;
+        other.count -= moved;
+        (other.count > 0).then_some(other)
+    }
+
+    /// Takes `count` units off this stack, at most all of them
+    pub fn split(&mut self, count: u32) -> ItemStack {
+        let count = count.min(self.count);
+        self.count -= count;
+        ItemStack { count, ..*self }
+    }
+}
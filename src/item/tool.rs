@@ -0,0 +1,149 @@
+use crate::render::atlas::MaterialType;
+
+
+/// Blocks each tool is good at, see `MaterialType::preferred_tool`
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ToolKind {
+    Pickaxe,
+    Shovel,
+    Axe,
+}
+
+/// What the tool is made of. Better tiers break faster, last longer and can harvest harder blocks.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ToolTier {
+    Wood,
+    Stone,
+    Iron,
+    Diamond,
+}
+
+impl ToolKind {
+    pub const ALL: [ToolKind; 3] = [ToolKind::Pickaxe, ToolKind::Shovel, ToolKind::Axe];
+
+    fn name(self) -> &'static str {
+        match self {
+            ToolKind::Pickaxe => "pickaxe",
+            ToolKind::Shovel => "shovel",
+            ToolKind::Axe => "axe",
+        }
+    }
+}
+
+impl ToolTier {
+    pub const ALL: [ToolTier; 4] = [ToolTier::Wood, ToolTier::Stone, ToolTier::Iron, ToolTier::Diamond];
+
+    /// How many times faster than the hand it breaks the blocks of its kind
+    pub fn speed(self) -> f32 {
+        match self {
+            ToolTier::Wood => 2.0,
+            ToolTier::Stone => 4.0,
+            ToolTier::Iron => 6.0,
+            ToolTier::Diamond => 8.0,
+        }
+    }
+
+    /// Blocks it can break before it's gone
+    pub fn durability(self) -> u32 {
+        match self {
+            ToolTier::Wood => 59,
+            ToolTier::Stone => 131,
+            ToolTier::Iron => 250,
+            ToolTier::Diamond => 1561,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            ToolTier::Wood => "wooden",
+            ToolTier::Stone => "stone",
+            ToolTier::Iron => "iron",
+            ToolTier::Diamond => "diamond",
+        }
+    }
+}
+
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Tool {
+    pub kind: ToolKind,
+    pub tier: ToolTier,
+}
+
+impl Tool {
+    pub fn new(kind: ToolKind, tier: ToolTier) -> Self {
+        Self { kind, tier }
+    }
+
+    /// Every kind in every tier
+    pub fn all() -> impl Iterator<Item = Tool> {
+        ToolKind::ALL.into_iter().flat_map(|kind| ToolTier::ALL.into_iter().map(move |tier| Tool::new(kind, tier)))
+    }
+
+    /// `wooden_pickaxe`, `stone_axe`...
+    pub fn name(self) -> String {
+        format!("{}_{}", self.tier.name(), self.kind.name())
+    }
+
+    /// How many times faster than the hand it breaks `material`
+    pub fn speed(self, material: MaterialType) -> f32 {
+        if material.preferred_tool() == Some(self.kind) { self.tier.speed() } else { 1.0 }
+    }
+}
+
+
+/// Whether breaking `material` with `tool` in hand drops anything
+pub fn can_harvest(material: MaterialType, tool: Option<Tool>) -> bool {
+    let Some(required) = material.required_tier() else {
+        return true;
+    };
+    tool.is_some_and(|tool| Some(tool.kind) == material.preferred_tool() && tool.tier >= required)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::item::{inventory::Inventory, Item, ItemStack};
+
+    use super::*;
+
+    #[test]
+    fn rock_needs_a_pickaxe() {
+        assert!(!can_harvest(MaterialType::ROCK, None));
+        assert!(!can_harvest(MaterialType::ROCK, Some(Tool::new(ToolKind::Shovel, ToolTier::Diamond))));
+        assert!(can_harvest(MaterialType::ROCK, Some(Tool::new(ToolKind::Pickaxe, ToolTier::Wood))));
+
+        assert!(can_harvest(MaterialType::DIRT, None));
+        assert!(can_harvest(MaterialType::LOG, Some(Tool::new(ToolKind::Pickaxe, ToolTier::Wood))));
+    }
+
+    #[test]
+    fn only_the_preferred_tool_is_faster() {
+        let axe = Tool::new(ToolKind::Axe, ToolTier::Stone);
+
+        assert_eq!(axe.speed(MaterialType::PLANKS), ToolTier::Stone.speed());
+        assert_eq!(axe.speed(MaterialType::DIRT), 1.0);
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for tool in Tool::all() {
+            assert_eq!(Item::from_name(&tool.name()), Some(Item::Tool(tool)));
+        }
+        assert_eq!(Tool::new(ToolKind::Pickaxe, ToolTier::Wood).name(), "wooden_pickaxe");
+    }
+
+    #[test]
+    fn tools_break_when_worn_out() {
+        let shovel = Item::Tool(Tool::new(ToolKind::Shovel, ToolTier::Wood));
+        let mut inventory = Inventory::default();
+        inventory.set(0, Some(ItemStack::new(shovel, 1)));
+
+        for _ in 1..ToolTier::Wood.durability() {
+            assert!(!inventory.wear_selected());
+        }
+        assert_eq!(inventory.selected_stack().map(|stack| stack.wear), Some(ToolTier::Wood.durability() - 1));
+        assert!(inventory.wear_selected());
+        assert!(inventory.is_empty());
+    }
+}
@@ -1,32 +1,43 @@
-use tracy_client::span;
+use crate::profiling::span;
 use winit:: {
     event::{Event, StartCause},
-    event_loop::ControlFlow,
 };
 use log::{trace, debug, info, warn, error};
 
 use winit::{
+    dpi::PhysicalSize,
     event_loop::EventLoop,
-        window::WindowBuilder,
+        window::{Fullscreen, WindowBuilder},
     };
 
-use crate::State;
+use crate::{cli::Cli, settings::Settings, State};
 
-pub fn run() {
-    
-    env_logger::init();
+/// Opens the window and runs the game until it's closed
+pub fn run(cli: &Cli) -> anyhow::Result<()> {
 
-    info!("This is an info message");
+    let settings_path = cli.settings_path();
+    let mut settings = Settings::load_or_create(&settings_path);
+    let overrides = cli.apply_to(&mut settings);
+    let world = cli.startup_world()?;
 
 
     let event_loop = EventLoop::new().unwrap();
 
+    // handle_wait duerme el bucle hasta el siguiente frame, ya no se hace Poll
 
-    event_loop.set_control_flow(ControlFlow::Poll);
-
-    let window = WindowBuilder::new().build(&event_loop).unwrap();
-    let mut state = State::new(&window);
+    // la ventana ya se crea con el tamaño y el modo guardados
+    let (width, height) = settings.graphics.resolution;
+    let window = WindowBuilder::new()
+        .with_title("wgpucraft")
+        .with_inner_size(PhysicalSize::new(width as u32, height as u32))
+        .with_fullscreen(settings.graphics.fullscreen.then_some(Fullscreen::Borderless(None)))
+        .build(&event_loop).unwrap();
+    let mut state = State::new(&window, settings, overrides, settings_path);
     state.initialize();
+    if let Some(info) = world {
+        info!("Starting world {} from the command line", info.name);
+        state.start_world(info);
+    }
     
     event_loop.run(move | event, elwt: &winit::event_loop::EventLoopWindowTarget<()> | {
 
@@ -50,5 +61,6 @@ pub fn run() {
             }
             _ => ()
         }
-    }).unwrap();
+    })?;
+    Ok(())
 }
\ No newline at end of file
@@ -5,18 +5,33 @@ pub mod terrain_gen;
 pub mod player;
 pub mod ecs;
 pub mod hud;
-
-
-
-use std::time::{Duration, Instant};
-use hud::{icons_atlas::IconType, HUD};
-use player::{camera::Camera, raycast::Ray, Player};
-
-use render::{atlas::MaterialType, pipelines::{GlobalModel, Globals}, renderer::Renderer};
+pub mod settings;
+pub mod input;
+pub mod item;
+pub mod world;
+pub mod console;
+pub mod cli;
+pub mod tools;
+pub mod timing;
+mod profiling;
+
+
+
+use std::{path::{Path, PathBuf}, time::{Duration, Instant}};
+use console::{command::{CommandRegistry, CommandTarget}, Console, ConsoleKey};
+use hud::{debug::DebugStats, menu::{next_resolution, Menu, MenuAction, MenuPage}, ui::UiEvent, HUD};
+use item::{crafting::{RecipeBook, INVENTORY_GRID_SIZE, TABLE_GRID_SIZE}, tool::can_harvest, Item, ItemStack};
+use player::{camera::Camera, crack::CrackOverlay, mining::{break_time, MiningProgress}, raycast::{BlockHit, Ray}, selection::BlockSelection, GameMode, Player};
+use input::{Action, Binding, InputMap, InputState};
+use settings::{Overrides, Settings, MAX_RENDER_DISTANCE, MIN_RENDER_DISTANCE};
+
+use render::{atlas::MaterialType, pipelines::{GlobalModel, Globals}, renderer::{Renderer, SKY_COLOR, WATER_FOG_COLOR}};
 use terrain_gen::{biomes::PRAIRIE_PARAMS, chunk, generator::TerrainGen};
+use timing::{FixedTimestep, FrameLimiter, TICK_RATE};
+use world::{WorldInfo, DAY_LENGTH, TICKS_PER_SECOND, WORLDS_DIR};
 use wgpu::BindGroup;
 use winit::{
-        dpi::PhysicalPosition, event::{self, DeviceEvent, ElementState, KeyEvent, MouseButton, WindowEvent}, event_loop::{self, EventLoopWindowTarget}, keyboard::{KeyCode, PhysicalKey}, window::{CursorGrabMode, Window}
+        dpi::{PhysicalPosition, PhysicalSize}, event::{DeviceEvent, ElementState, KeyEvent, MouseButton, WindowEvent}, event_loop::{self, ControlFlow, EventLoopWindowTarget}, keyboard::{KeyCode, PhysicalKey}, window::{CursorGrabMode, Fullscreen, Window}
     };
 
 
@@ -24,16 +39,55 @@ use winit:: {
     event::Event,
 };
 
-use tracy_client::{frame_mark, span};
+use profiling::{frame_mark, span};
 
-const FRAME_TIME: Duration = Duration::from_micros(16_666); // 60 FPS
+// Max distance at which blocks can be targeted
+const REACH_DISTANCE: f32 = 100.0;
+// Fraction of the horizon where the distance fog starts
+const FOG_START: f32 = 0.6;
+// Distance at which everything is fog when the camera is under water
+const WATER_FOG_DISTANCE: f32 = 12.0;
+// Chunks generated per frame on the loading screen
+const LOADING_CHUNKS_PER_FRAME: usize = 16;
 
 
+/// Screen the game is in. The menus before `LOADING` have no world, `State::terrain`
+/// is built when a world is picked and dropped when leaving it.
 #[derive(PartialEq)]
 pub enum GameState {
-
+    /// Title screen
+    MENU,
+    /// Saved worlds, to create, delete or play one
+    WORLDS,
+    /// Generating the chunks around the player, with a progress bar
+    LOADING,
     PLAYING,
-    PAUSED
+    PAUSED,
+    /// Inventory screen open, the mouse moves stacks instead of the camera
+    INVENTORY,
+    /// Typing in the console, the keys go to it instead of the player
+    CONSOLE,
+}
+
+impl GameState {
+    /// A world is loaded and drawn, the game HUD is shown
+    pub fn in_world(&self) -> bool {
+        matches!(self, GameState::PLAYING | GameState::PAUSED | GameState::INVENTORY | GameState::CONSOLE)
+    }
+
+    /// Actions the state reacts to, the rest are ignored while it lasts
+    pub fn allows(&self, action: Action) -> bool {
+        match self {
+            // escape vuelve atrás en los menús
+            GameState::MENU | GameState::WORLDS => matches!(action, Action::Pause | Action::ToggleDebug),
+            GameState::LOADING => action == Action::ToggleDebug,
+            GameState::PLAYING => true,
+            // en pausa solo se puede volver al juego
+            GameState::PAUSED => matches!(action, Action::Pause | Action::ToggleDebug),
+            GameState::INVENTORY => matches!(action, Action::Pause | Action::OpenInventory | Action::ToggleDebug),
+            GameState::CONSOLE => matches!(action, Action::Pause | Action::ToggleDebug),
+        }
+    }
 }
 
 
@@ -45,47 +99,83 @@ pub struct State<'a> {
     pub data: GlobalModel,
     pub globals_bind_group: BindGroup,
     pub player: Player,
-    pub terrain: TerrainGen,
+    /// Terrain of the world being played, `None` in the main menu and the world list
+    pub terrain: Option<TerrainGen>,
+    /// World being played, saved when leaving it
+    pub world: Option<WorldInfo>,
+    /// Worlds shown in the world list
+    worlds: Vec<WorldInfo>,
     pub hud: HUD,
+    /// Pause menu and settings screen
+    pub menu: Menu,
+    pub selection: BlockSelection,
+    pub crack: CrackOverlay,
+    /// Block under the crosshair, updated every frame
+    pub target: Option<BlockHit>,
+    /// Progress breaking the target while the break action is held
+    pub mining: MiningProgress,
+    pub settings: Settings,
+    /// Values of the file the command line replaced, they aren't saved
+    overrides: Overrides,
+    /// File the settings were loaded from, and are saved to
+    settings_path: PathBuf,
+    pub input: InputState,
+    pub recipes: RecipeBook,
+    /// Chat and command line
+    pub console: Console,
+    commands: CommandRegistry,
+    /// Time of day of the world being played in ticks, see `world::DAY_LENGTH`
+    time_of_day: f32,
     state: GameState,
     last_frame_time: Instant,
+    /// Ticks of the simulation owed by the frames drawn
+    timestep: FixedTimestep,
+    /// Frame cap of the settings
+    limiter: FrameLimiter,
 
 }
 
 impl<'a> State<'a> {
 
-    pub fn new(window: &'a Window) -> Self {
+    /// `window` should already be built with the resolution and fullscreen mode of `settings`
+    pub fn new(window: &'a Window, settings: Settings, overrides: Overrides, settings_path: PathBuf) -> Self {
 
-        let mut renderer = Renderer::new(&window);
+        let mut renderer = Renderer::new(window, settings.graphics.vsync);
 
         let data = GlobalModel {
             globals: renderer.create_consts(&[Globals::default()]),
 
         };
 
-        let hud = HUD::new(
+        let mut hud = HUD::new(
             &renderer,
             &renderer.layouts.global,   
             renderer.device.create_shader_module(wgpu::ShaderModuleDescriptor {
                 label: Some("HUD Shader"),
                 source: wgpu::ShaderSource::Wgsl(include_str!("../assets/shaders/hud.wgsl").into()),
             }),
+            settings.graphics.gui_scale,
         );
 
         let globals_bind_group = renderer.bind_globals(&data);
 
-        let camera = Camera::new(&renderer, (8.0, 12.0, 8.0), cgmath::Deg(-90.0), cgmath::Deg(-20.0));
+        let player = new_player(&renderer, &settings);
+        hud.update_hotbar(&renderer.queue, &player.inventory);
+        hud.show_game_hud = false;
 
-        let player = Player::new(camera);
+        let selection = BlockSelection::new(&renderer);
+        let crack = CrackOverlay::new(&renderer);
 
+        let recipes = RecipeBook::load_assets().unwrap_or_else(|e| {
+            log::error!("Failed to load recipes, crafting is disabled: {:#}", e);
+            RecipeBook::default()
+        });
 
+        let mut menu = Menu::new(*hud.layout());
+        menu.open(MenuPage::MainMenu, &settings);
 
-        let terrain = TerrainGen::new(
-            &renderer,
-        );
-
-        
 
+        let limiter = FrameLimiter::new(settings.graphics.frame_time());
 
         Self {
             window,
@@ -93,24 +183,49 @@ impl<'a> State<'a> {
             data,
             globals_bind_group,
             player,
-            terrain,
+            terrain: None,
+            world: None,
+            worlds: Vec::new(),
             hud,
-            state: GameState::PLAYING,
+            menu,
+            selection,
+            crack,
+            target: None,
+            mining: MiningProgress::default(),
+            input: InputState::new(settings.controls.clone()),
+            settings,
+            overrides,
+            settings_path,
+            recipes,
+            console: Console::new(),
+            commands: CommandRegistry::default(),
+            time_of_day: 0.0,
+            state: GameState::MENU,
             last_frame_time: Instant::now(),
+            timestep: FixedTimestep::new(TICK_RATE),
+            limiter,
 
         }
     }
 
-    pub fn handle_wait(&mut self, _elwt: &EventLoopWindowTarget<()>) {
-
-        self.window.request_redraw();
+    /// Asks for the next frame if the frame cap allows it, otherwise sleeps until it does
+    pub fn handle_wait(&mut self, elwt: &EventLoopWindowTarget<()>) {
+        if self.limiter.ready(Instant::now()) {
+            elwt.set_control_flow(ControlFlow::Wait);
+            self.window.request_redraw();
+        } else {
+            elwt.set_control_flow(ControlFlow::WaitUntil(self.limiter.next_frame()));
+        }
     }
 
     //TODO: add global settings as parameter
     pub fn handle_window_event(&mut self, event: WindowEvent, elwt: &EventLoopWindowTarget<()>) {
-        if !self.handle_input_event(&event) {
+        let handled = self.handle_input_event(&event);
+        self.handle_menu_events(elwt);
+        if !handled {
         match event {
             WindowEvent::CloseRequested  => {
+                self.save_world();
                 elwt.exit()
             },
 
@@ -122,191 +237,24 @@ impl<'a> State<'a> {
 
                 let now = std::time::Instant::now();
                 let elapsed = now - self.last_frame_time;
-                self.terrain.update(&self.renderer.queue, &self.player.camera.position);
-
-                if elapsed >= FRAME_TIME {
-                    let _inner_span = span!("rendering frame"); // <- Marca el inicio del bloque
-                    frame_mark();
-                    self.last_frame_time = now;
-                    self.update(elapsed);
-
-                    match self.renderer.render(&self.terrain, &self.hud, &self.globals_bind_group) {
-                        Ok(_) => {}
-                        // Reconfigure the surface if lost
-                        Err(wgpu::SurfaceError::Lost) => self.resize(self.renderer.size),
-                        // The system is out of memory, we should probably quit
-                        Err(wgpu::SurfaceError::OutOfMemory) => elwt.exit(),
-                        // All other errors (Outdated, Timeout) should be resolved by the next frame
-                        Err(e) => eprintln!("{:?}", e)
-                    }
-                }
-            },
-
-            // Eventos del mouse
-            WindowEvent::MouseInput { state, button, .. } => {
-                match (button, state) {
-
-                    // Click IZQUIERDO - Eliminar bloque (poner aire)
-                    (MouseButton::Left, ElementState::Pressed) => {
-                        let ray = Ray::from_camera(&self.player.camera, 100.0);
-                        let ray_hit = ray.cast(&self.terrain.chunks);
-
-                        if let Some(hit) = ray_hit {
-                            if let Some(chunk_index) = self.terrain.chunks.set_block_material(hit.position, MaterialType::AIR) {
-                                let chunk_arc = self.terrain.chunks.get_chunk(chunk_index).unwrap();
-                                let mut chunk = chunk_arc.write().unwrap();
-
-                                chunk.update_mesh(PRAIRIE_PARAMS);
-                                
-                                let mut chunk_model = self.terrain.chunk_models[chunk_index].write().unwrap();
-                                chunk_model.update(&self.renderer.queue, &chunk.mesh, 0);
-                            }
-                            println!("Bloque eliminado en: {:?}", hit.position);
-                        } else {
-                            println!("No se golpeó ningún bloque para eliminar");
-                        }
-
-                        // Aquí puedes añadir tu lógica
-                    },
-                    (MouseButton::Right, ElementState::Pressed) => {
-                        let ray = Ray::from_camera(&self.player.camera, 100.0);
-                        let ray_hit = ray.cast(&self.terrain.chunks);
-
-                        if let Some(hit) = ray_hit {
-                            let material = match self.hud.selected_icon {
-                                    IconType::ROCK => MaterialType::ROCK,
-                                    IconType::DIRT => MaterialType::DIRT,
-                                    IconType::GRASS => MaterialType::GRASS,
-                                    _ => MaterialType::AIR, // No cambiamos si es aire
-                                // Añadir más coincidencias según necesites
-                            };
-
-                            if let Some(chunk_index) = self.terrain.chunks.set_block_material(hit.neighbor_position(), material) {
-
-                                let chunk_arc = self.terrain.chunks.get_chunk(chunk_index).unwrap();
-                                let mut chunk = chunk_arc.write().unwrap();
-
-                                chunk.update_mesh(PRAIRIE_PARAMS);
-    
-
-                                let mut chunk_model = self.terrain.chunk_models[chunk_index].write().unwrap();
-                                
-                                chunk_model.update(&self.renderer.queue, &chunk.mesh, 0);
-                            }
-                            println!("Clic izquierdo presionado en: {:?}", hit.neighbor_position());
-                            // Aquí puedes añadir tu lógica para el clic izquierdo
-                        } else {
-                            println!("No se golpeó ningún bloque");
-                        }
-                    },
-                    (MouseButton::Middle, ElementState::Pressed) => {
-                        let ray = Ray::from_camera(&self.player.camera, 100.0);
-                        let ray_hit = ray.cast(&self.terrain.chunks);
-
-                        if let Some(hit) = ray_hit {
-                            if let Some(block) = self.terrain.chunks.get_block_material(hit.position) {
-                                // Actualizar el icono seleccionado en el HUD según el material
-                                self.hud.selected_icon = match block {
-                                    MaterialType::ROCK => IconType::ROCK,
-                                    MaterialType::DIRT => IconType::DIRT,
-                                    MaterialType::GRASS => IconType::GRASS,
-                                    _ => self.hud.selected_icon, // No cambiamos si es aire
-                                    
-                                    // Añadir más coincidencias según necesites
-                                };
-
-                                self.hud.update(&self.renderer);
-                                
-                                // Actualizar visualmente el HUD
-    
-                            }
-                        } else {
-                            println!("No se encontró bloque para copiar");
-                        }
-                    },
-                    _ => {}
-                }
-            },
-
-            WindowEvent::MouseWheel { delta, .. } => {
-                match delta {
-                    // Para sistemas Windows y Linux
-                    event::MouseScrollDelta::LineDelta(_, y) => {
-                        let direction = match delta {
-                            // Para sistemas Windows y Linux
-                            event::MouseScrollDelta::LineDelta(_, y) => {
-                                if y > 0.0 { 1 } else { -1 }
-                            },
-                            // Para sistemas macOS (puede venir en píxeles)
-                            event::MouseScrollDelta::PixelDelta(pos) => {
-                                if pos.y > 0.0 { 1 } else { -1 }
-                            }
-                        };
-
-                        self.hud.selected_icon = match direction {
-                            1 => self.hud.selected_icon.next(),   // Rueda hacia arriba
-                            -1 => self.hud.selected_icon.prev(),  // Rueda hacia abajo
-                            _ => self.hud.selected_icon,         // No cambia si no es 1 o -1
-                        };
-
-                        
-                        // Actualizar el HUD
-                        self.hud.update(&self.renderer);
-                    },
-                     // Para sistemas macOS (puede venir en píxeles)
-                    event::MouseScrollDelta::PixelDelta(pos) => {
-                        if pos.y > 0.0 {
-                            println!("Rueda del mouse hacia arriba (avance)");
-                            // Lógica para cuando la rueda sube
-                        } else if pos.y < 0.0 {
-                            println!("Rueda del mouse hacia abajo (retroceso)");
-                            // Lógica para cuando la rueda baja
-                        }
-                    }
+                self.last_frame_time = now;
+                self.limiter.frame_started(now);
+
+                let _inner_span = span!("rendering frame"); // <- Marca el inicio del bloque
+                frame_mark();
+                self.update(elapsed);
+
+                match self.renderer.render(self.terrain.as_ref(), &self.selection, &self.crack, &self.hud, &self.globals_bind_group) {
+                    Ok(_) => {}
+                    // Reconfigure the surface if lost
+                    Err(wgpu::SurfaceError::Lost) => self.resize(self.renderer.size),
+                    // The system is out of memory, we should probably quit
+                    Err(wgpu::SurfaceError::OutOfMemory) => elwt.exit(),
+                    // All other errors (Outdated, Timeout) should be resolved by the next frame
+                    Err(e) => eprintln!("{:?}", e)
                 }
             },
 
-
-            // WindowEvent::MouseWheel { delta, .. } => {
-            //     self.camera.camera_controller.process_scroll(&delta);
-            // },
-            WindowEvent::KeyboardInput {
-                event: KeyEvent {
-                    physical_key:PhysicalKey::Code(KeyCode::Escape),
-                    state: ElementState::Pressed,
-                    ..
-                },
-                ..
-            } => {
-                self.state = match self.state {
-                    GameState::PAUSED =>
-                    {
-                        self.window.set_cursor_position(PhysicalPosition::new(self.renderer.size.width / 2, self.renderer.size.height / 2))
-                            .expect("No se pudo mover el cursor");
-
-                        // Ahora intenta bloquear el cursor
-                        self.window.set_cursor_grab(CursorGrabMode::Confined)
-                            .expect("No se pudo bloquear el cursor");
-
-                        self.window.set_cursor_visible(false);
-                        GameState::PLAYING
-                    },
-                    GameState::PLAYING =>
-                    {
-                        let center = winit::dpi::PhysicalPosition::new(self.renderer.size.width / 2, self.renderer.size.height / 2);
-                        self.window.set_cursor_position(center).unwrap_or_else(|e| {
-                            eprintln!("Failed to set cursor position: {:?}", e);
-                        });
-                        self.window.set_cursor_grab(winit::window::CursorGrabMode::None).unwrap();
-                        self.window.set_cursor_visible(true);
-
-                        
-                        GameState::PAUSED
-                    },
-                    
-                }
-            }
-            
             _ => {}
         }
 
@@ -316,23 +264,49 @@ impl<'a> State<'a> {
     }
 
 
+    /// The game starts in the main menu, with the cursor free
     pub fn initialize(&mut self) {
-        self.window.set_cursor_visible(false);
-        // in windows os this doesnt work
-        self.window.set_cursor_position(PhysicalPosition::new(self.renderer.size.width / 2, self.renderer.size.height / 2))
-            .expect("No se pudo mover el cursor");
+        self.release_cursor();
+    }
 
-        // Ahora intenta bloquear el cursor
-        self.window.set_cursor_grab(CursorGrabMode::Confined)
-            .expect("No se pudo bloquear el cursor");
 
 
-        let center = winit::dpi::PhysicalPosition::new(self.renderer.size.width / 2, self.renderer.size.height / 2);
-        self.window.set_cursor_position(center).unwrap_or_else(|e| {
-            eprintln!("Failed to set cursor position: {:?}", e);
-        });
+    /// Casts a ray from the camera and moves the selection outline to the hit block
+    pub fn update_target(&mut self) {
+        let ray = Ray::from_camera(&self.player.camera, REACH_DISTANCE);
+        self.target = self.terrain.as_ref().and_then(|terrain| ray.cast(&terrain.chunks));
+        self.selection.update(&self.renderer.queue, self.target.as_ref(), self.player.camera.dependants.focus_off);
+    }
+
+
+    /// Breaks the target little by little while the break action is held, in survival.
+    /// In creative blocks break on the click, see `on_action`.
+    fn update_mining(&mut self, dt: Duration) {
+        let mining = self.state == GameState::PLAYING
+            && self.player.game_mode == GameMode::Survival
+            && self.input.is_down(Action::BreakBlock);
 
+        let target = self.target.filter(|_| mining).map(|hit| hit.position);
+        let held = self.player.inventory.selected_stack().map(|stack| stack.item);
+        let time = target
+            .and_then(|position| self.block_at(position))
+            .and_then(|material| break_time(material, held));
 
+        if self.mining.update(target, time, dt.as_secs_f32()) {
+            self.break_block();
+        }
+    }
+
+
+    pub fn set_render_distance(&mut self, render_distance: usize) {
+        let render_distance = render_distance.clamp(MIN_RENDER_DISTANCE, MAX_RENDER_DISTANCE);
+        self.settings.graphics.render_distance = render_distance;
+        self.overrides.render_distance = None;
+        if let Some(terrain) = &mut self.terrain {
+            terrain.set_render_distance(&self.renderer, render_distance);
+            self.player.camera.projection.set_zfar(terrain.view_distance());
+        }
+        log::info!("Render distance: {} chunks", render_distance);
     }
 
 
@@ -340,6 +314,8 @@ impl<'a> State<'a> {
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
         self.player.camera.resize(new_size);
         self.renderer.resize(new_size);
+        self.hud.resize(&self.renderer.queue, new_size, &self.player.inventory);
+        self.menu.set_layout(*self.hud.layout());
 
         
     }
@@ -351,29 +327,692 @@ impl<'a> State<'a> {
         let _span = span!("update state"); // <- Marca el inicio del bloque
 
         self.renderer.update();
-        
 
-        self.player.camera.update_dependants(dt);
+        match self.state {
+            GameState::MENU | GameState::WORLDS => {}
+            GameState::LOADING => self.update_loading(),
+            GameState::PLAYING | GameState::PAUSED | GameState::INVENTORY | GameState::CONSOLE => self.update_game(dt),
+        }
+        self.hud.show_game_hud = self.state.in_world();
+        if matches!(self.state, GameState::PLAYING | GameState::CONSOLE) {
+            self.console.queue_text(&mut self.hud.text, self.state == GameState::CONSOLE);
+        }
+
+        if self.menu.page().is_some() {
+            self.hud.ui.update(&self.renderer.queue, &mut self.menu.ui);
+        } else {
+            self.hud.ui.visible = false;
+        }
+
+        if self.hud.show_debug {
+            self.debug_stats().queue_text(&mut self.hud.text);
+        }
+        // el texto se vuelve a pedir en cada frame
+        self.hud.text.flush(&self.renderer.queue);
+    }
+
+    /// Generates a few chunks of the world being loaded, and starts playing once they
+    /// are all there
+    fn update_loading(&mut self) {
+        let Some(terrain) = &mut self.terrain else {
+            return;
+        };
+        let done = terrain.load_step(&self.renderer.queue, LOADING_CHUNKS_PER_FRAME);
+        let (loaded, total) = terrain.loading_progress();
+        self.menu.set_progress(loaded as f32 / total.max(1) as f32, &format!("{} / {} chunks", loaded, total));
+        if !done {
+            return;
+        }
+
+        self.player.camera.projection.set_zfar(terrain.view_distance());
+        // los mundos nuevos empiezan en la superficie, los guardados donde se dejaron
+        if self.world.as_ref().is_some_and(|world| world.player_position.is_none()) {
+            self.player.spawn_on_surface(&terrain.chunks);
+        }
+        self.menu.close();
+        self.resume_playing();
+    }
+
+    /// Runs the ticks the time of the frame adds up to, then places the camera between
+    /// the last two ticks and updates what is drawn
+    fn update_game(&mut self, dt: Duration) {
+        if self.terrain.is_none() {
+            return;
+        }
+        for _ in 0..self.timestep.advance(dt) {
+            self.tick(self.timestep.step());
+        }
+
+        // el ratón gira la cámara en cada frame, no en cada tick
+        let playing = self.state == GameState::PLAYING;
+        if playing {
+            self.player.look();
+        }
+        // sin ticks del jugador se queda donde terminó el último
+        self.player.interpolate(if playing { self.timestep.alpha() } else { 1.0 });
+        self.update_target();
+        // como la selección, con el focus_off de este frame
+        self.crack.update(&self.renderer.queue, self.mining.crack(), self.player.camera.dependants.focus_off);
+
+        // una vez por frame: con varios ticks seguidos cargaría chunks varias veces
+        if let Some(terrain) = &mut self.terrain {
+            terrain.update(&self.renderer.queue, &self.player.camera.position);
+        }
+
+        let daylight = world::daylight(self.time_of_day);
+
+        // Niebla densa bajo el agua, si no la niebla de distancia oculta el borde del terreno
+        let underwater = self.player.eyes_in_water;
+        self.hud.underwater = underwater;
+        let (fog_color, fog_range) = if underwater {
+            (WATER_FOG_COLOR, [0.0, WATER_FOG_DISTANCE])
+        } else {
+            let horizon = self.terrain.as_ref().map_or(f32::MAX, TerrainGen::horizon);
+            (SKY_COLOR, [horizon * FOG_START, horizon])
+        };
+        let fog_color = fog_color.map(|c| c * daylight);
+        self.renderer.set_clear_color(fog_color);
 
         let cam_deps = &self.player.camera.dependants;
 
         self.renderer.update_consts(&mut self.data.globals, &[Globals::new(
-            cam_deps.view_proj
+            cam_deps.view_proj,
+            cam_deps.focus_off,
+            cam_deps.cam_pos,
+            fog_color,
+            fog_range,
+            daylight,
+        )]);
+    }
+
+    /// One fixed step of the world being played: movement, mining and the time of day
+    fn tick(&mut self, dt: Duration) {
+        let _span = span!("simulation tick");
+
+        let Some(terrain) = &self.terrain else {
+            return;
+        };
+        if self.state == GameState::PLAYING {
+            self.player.update(dt, &terrain.chunks, &self.input);
+        }
+        self.update_mining(dt);
 
-        )])
+        if self.state != GameState::PAUSED {
+            self.time_of_day = (self.time_of_day + TICKS_PER_SECOND * dt.as_secs_f32()) % DAY_LENGTH as f32;
+        }
+    }
 
+    /// Everything the F3 overlay shows, from the player, the terrain and the renderer
+    fn debug_stats(&self) -> DebugStats {
+        let camera = &self.player.camera;
+        let mut stats = DebugStats {
+            position: camera.position.into(),
+            yaw: cgmath::Deg::from(camera.yaw).0,
+            pitch: cgmath::Deg::from(camera.pitch).0,
+            target: self.target,
+            target_material: self.target.and_then(|hit| self.block_at(hit.position)),
+            ..Default::default()
+        };
+        if let Some(terrain) = &self.terrain {
+            terrain.fill_debug_stats(&mut stats);
+        }
+        self.renderer.fill_debug_stats(&mut stats);
+        stats
     }
 
+    /// Gives the event to the screen of the current state, then translates what is left
+    /// to actions. Returns false for events nothing used.
     pub fn handle_input_event(
         &mut self,
         event: &WindowEvent,
     ) -> bool {
-        if self.state == GameState::PLAYING{
-            self.player.camera.input_keyboard(&event)
-        } else {
-            false
+        let handled = match self.state {
+            GameState::MENU | GameState::WORLDS | GameState::LOADING | GameState::PAUSED => self.handle_menu_input(event),
+            GameState::INVENTORY => self.handle_inventory_input(event),
+            GameState::CONSOLE => self.handle_console_input(event),
+            GameState::PLAYING => false,
+        };
+        if handled {
+            return true;
+        }
+
+        let Some(actions) = self.input.handle_window_event(event) else {
+            return false;
+        };
+        for action in actions {
+            if self.state.allows(action) {
+                self.on_action(action);
+            }
+        }
+        true
+    }
+
+    /// Widgets of the menu page and the key being rebound
+    fn handle_menu_input(&mut self, event: &WindowEvent) -> bool {
+        if self.menu.page().is_none() {
+            return false;
+        }
+        let pressed = self.menu.rebinding()
+            .and(Binding::from_window_event(event))
+            .filter(|(_, state)| *state == ElementState::Pressed);
+        if let Some((binding, _)) = pressed {
+            if self.menu.capture_binding(binding, &mut self.settings.controls) {
+                self.input.map = self.settings.controls.clone();
+            }
+            return true;
+        }
+        self.menu.ui.handle_event(event)
+    }
+
+    /// The cursor and the clicks move stacks
+    fn handle_inventory_input(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                self.hud.inventory_screen.set_cursor(*position);
+                if self.hud.inventory_screen.held.is_some() {
+                    self.hud.inventory_screen.update(&self.renderer.queue, &self.player.inventory);
+                }
+                true
+            }
+            WindowEvent::MouseInput { state: ElementState::Pressed, button, .. } => {
+                if self.hud.inventory_screen.click(&mut self.player.inventory, &self.recipes, *button == MouseButton::Right) {
+                    self.hud.update_inventory(&self.renderer.queue, &self.player.inventory);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The keys type in the console line
+    fn handle_console_input(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::KeyboardInput { event: key_event, .. } => self.handle_console_key(key_event),
+            _ => false,
+        }
+    }
+
+    fn on_action(&mut self, action: Action) {
+        match action {
+            Action::BreakBlock if self.player.game_mode == GameMode::Creative => self.break_block(),
+            // en supervivencia se va rompiendo en update_mining mientras se mantiene
+            Action::BreakBlock => {}
+            Action::PlaceBlock => self.place_block(),
+            Action::PickBlock => self.pick_block(),
+            Action::HotbarNext => {
+                self.player.inventory.scroll(1);
+                self.hud.update_hotbar(&self.renderer.queue, &self.player.inventory);
+            }
+            Action::HotbarPrev => {
+                self.player.inventory.scroll(-1);
+                self.hud.update_hotbar(&self.renderer.queue, &self.player.inventory);
+            }
+            Action::HotbarSlot1 | Action::HotbarSlot2 | Action::HotbarSlot3
+            | Action::HotbarSlot4 | Action::HotbarSlot5 | Action::HotbarSlot6
+            | Action::HotbarSlot7 | Action::HotbarSlot8 | Action::HotbarSlot9 => {
+                self.player.inventory.select(action.hotbar_slot().unwrap());
+                self.hud.update_hotbar(&self.renderer.queue, &self.player.inventory);
+            }
+            Action::OpenInventory => self.toggle_inventory(),
+            Action::ToggleSpectator => self.player.toggle_spectator(),
+            Action::ToggleDebug => self.hud.show_debug = !self.hud.show_debug,
+            Action::RenderDistanceUp => self.set_render_distance(self.settings.graphics.render_distance + 1),
+            Action::RenderDistanceDown => self.set_render_distance(self.settings.graphics.render_distance.saturating_sub(1)),
+            Action::Pause => self.go_back(),
+            Action::OpenConsole => self.open_console(""),
+            Action::OpenCommand => self.open_console("/"),
+            // se leen cada frame con is_down
+            Action::MoveForward | Action::MoveBackward | Action::MoveLeft | Action::MoveRight
+            | Action::Jump | Action::Sneak | Action::Sprint => {}
+        }
+    }
+
+    // Eliminar bloque (poner aire)
+    fn break_block(&mut self) {
+        // el raycast ya se hizo en update para dibujar la selección
+        let Some(hit) = self.target else {
+            println!("No se golpeó ningún bloque para eliminar");
+            return;
+        };
+
+        let material = self.block_at(hit.position);
+        // con el inventario lleno el bloque se queda, así no se pierde lo que suelta
+        let tool = self.player.inventory.selected_stack().and_then(|stack| stack.item.tool());
+        let drop = material.filter(|material| can_harvest(*material, tool)).and_then(Item::dropped_by);
+        let survival = self.player.game_mode == GameMode::Survival;
+        if let Some(item) = drop.filter(|_| survival)
+            && !self.player.inventory.fits(ItemStack::new(item, 1)) {
+            log::warn!("Inventory full, can't pick up {:?}", item);
+            return;
+        }
+        if !self.set_block(hit.position, MaterialType::AIR) {
+            return;
+        }
+
+        if survival {
+            // sin la herramienta adecuada el bloque no suelta nada
+            if let Some(item) = drop {
+                self.player.inventory.add(ItemStack::new(item, 1));
+            }
+            if tool.is_some() && self.player.inventory.wear_selected() {
+                log::debug!("{:?} broke", tool);
+            }
+            self.hud.update_hotbar(&self.renderer.queue, &self.player.inventory);
+        }
+        println!("Bloque eliminado en: {:?}", hit.position);
+    }
+
+    fn place_block(&mut self) {
+        // usar una mesa abre su rejilla, agachado se coloca el bloque encima
+        let table = self.target.is_some_and(|hit| {
+            self.block_at(hit.position) == Some(MaterialType::CRAFTING_TABLE)
+        });
+        if table && !self.input.is_down(Action::Sneak) {
+            self.open_inventory(TABLE_GRID_SIZE);
+            return;
+        }
+
+        // no poner bloques dentro del jugador
+        let ray_hit = self.target.filter(|hit| {
+            !self.player.mode.has_collisions() || !self.player.body.aabb().blocks().any(|pos| pos == hit.neighbor_position())
+        });
+
+        let Some(hit) = ray_hit else {
+            println!("No se golpeó ningún bloque");
+            return;
+        };
+        let Some(material) = self.player.inventory.selected_stack().and_then(|stack| stack.item.block()) else {
+            return;
+        };
+
+        if self.set_block(hit.neighbor_position(), material) {
+            if self.player.game_mode == GameMode::Survival {
+                self.player.inventory.consume_selected();
+                self.hud.update_hotbar(&self.renderer.queue, &self.player.inventory);
+            }
+            println!("Bloque colocado en: {:?}", hit.neighbor_position());
+        }
+    }
+
+    /// Material of a loaded block of the world being played
+    pub fn block_at(&self, position: cgmath::Vector3<i32>) -> Option<MaterialType> {
+        self.terrain.as_ref()?.chunks.get_block_material(position)
+    }
+
+    /// Changes a block and remeshes its chunk, false if the position isn't loaded
+    pub fn set_block(&mut self, position: cgmath::Vector3<i32>, material: MaterialType) -> bool {
+        self.set_blocks(std::iter::once(position), material) == 1
+    }
+
+    /// Changes the loaded blocks among `positions` and remeshes each chunk touched once.
+    /// Returns how many were changed.
+    pub fn set_blocks(&mut self, positions: impl IntoIterator<Item = cgmath::Vector3<i32>>, material: MaterialType) -> usize {
+        let Some(terrain) = &mut self.terrain else {
+            return 0;
+        };
+        let (changed, touched) = terrain.chunks.set_blocks_material(positions, material);
+
+        for chunk_index in touched {
+            let chunk_arc = terrain.chunks.get_chunk(chunk_index).unwrap();
+            let mut chunk = chunk_arc.write().unwrap();
+
+            chunk.update_mesh(PRAIRIE_PARAMS);
+
+            let mut chunk_model = terrain.chunk_models[chunk_index].write().unwrap();
+            chunk_model.update(&self.renderer.queue, &chunk.mesh, 0);
+        }
+
+        if changed > 0 {
+            self.update_target();
+        }
+        changed
+    }
+
+    // Seleccionar en la barra el bloque apuntado
+    fn pick_block(&mut self) {
+        let Some(hit) = self.target else {
+            println!("No se encontró bloque para copiar");
+            return;
+        };
+
+        if let Some(block) = self.block_at(hit.position) {
+            let creative = self.player.game_mode == GameMode::Creative;
+            self.player.inventory.pick(Item::Block(block), creative);
+            self.hud.update_hotbar(&self.renderer.queue, &self.player.inventory);
+        }
+    }
+
+    /// Escape: leaves the screen of the current state for the one it came from
+    fn go_back(&mut self) {
+        self.input.release_all();
+        // escape en los ajustes vuelve a la pantalla de la que se abrieron
+        if self.menu.page() == Some(MenuPage::Settings) {
+            self.leave_settings();
+            return;
+        }
+
+        match self.state {
+            GameState::MENU | GameState::LOADING => {}
+            GameState::WORLDS => self.open_main_menu(),
+            GameState::PLAYING => self.pause(),
+            GameState::PAUSED => self.unpause(),
+            // escape cierra el inventario
+            GameState::INVENTORY => {
+                if self.close_inventory() {
+                    self.resume_playing();
+                }
+            }
+            // y la consola, sin ejecutar la línea
+            GameState::CONSOLE => self.resume_playing(),
+        }
+    }
+
+    fn pause(&mut self) {
+        self.release_cursor();
+        self.menu.open(MenuPage::Pause, &self.settings);
+        self.state = GameState::PAUSED;
+    }
+
+    fn unpause(&mut self) {
+        self.menu.close();
+        self.resume_playing();
+    }
+
+    fn open_main_menu(&mut self) {
+        self.menu.open(MenuPage::MainMenu, &self.settings);
+        self.state = GameState::MENU;
+    }
+
+    /// Starts typing a line that begins with `prefix`
+    fn open_console(&mut self, prefix: &str) {
+        self.input.release_all();
+        self.release_cursor();
+        self.console.open(prefix);
+        self.state = GameState::CONSOLE;
+    }
+
+    /// Edits the console line with a pressed key. Returns false for keys the console
+    /// doesn't use, so escape and F3 still work.
+    fn handle_console_key(&mut self, event: &KeyEvent) -> bool {
+        if event.state != ElementState::Pressed {
+            return false;
+        }
+        let key = match event.physical_key {
+            PhysicalKey::Code(code) => self.console.key(code, &self.commands),
+            PhysicalKey::Unidentified(_) => ConsoleKey::Unused,
+        };
+        match key {
+            ConsoleKey::Used => true,
+            ConsoleKey::Submitted(line) => {
+                self.input.release_all();
+                self.resume_playing();
+                self.run_console_line(&line);
+                true
+            }
+            ConsoleKey::Unused => {
+                // escape llega con un carácter de control, que no se escribe
+                let typed = event.text.as_deref().filter(|text| text.chars().any(|c| !c.is_control()));
+                if let Some(text) = typed {
+                    self.console.type_text(text);
+                }
+                typed.is_some() || !matches!(event.physical_key, PhysicalKey::Code(KeyCode::Escape | KeyCode::F3))
+            }
+        }
+    }
+
+    /// Runs a command, lines without `/` are chat
+    fn run_console_line(&mut self, line: &str) {
+        if line.is_empty() {
+            return;
+        }
+        let Some(command) = line.strip_prefix('/') else {
+            self.console.print(&format!("<Player> {}", line));
+            return;
+        };
+
+        // el registro se saca mientras se ejecuta, los comandos reciben todo el estado
+        let commands = std::mem::replace(&mut self.commands, CommandRegistry::empty());
+        let result = commands.execute(command, self);
+        self.commands = commands;
+        match result {
+            Ok(message) => self.console.print(&message),
+            Err(e) => self.console.print_error(&e.to_string()),
+        }
+    }
+
+    /// Applies what the menu widgets changed, settings take effect right away
+    fn handle_menu_events(&mut self, elwt: &EventLoopWindowTarget<()>) {
+        for event in self.menu.drain_events() {
+            match event {
+                UiEvent::Clicked(MenuAction::Singleplayer) => self.show_world_list(),
+                UiEvent::Clicked(MenuAction::QuitGame) => {
+                    self.save_settings();
+                    elwt.exit();
+                }
+                UiEvent::ItemSelected(MenuAction::Worlds, _) => self.menu.world_selected(),
+                UiEvent::Clicked(MenuAction::CreateWorld)
+                | UiEvent::TextSubmitted(MenuAction::WorldName | MenuAction::WorldSeed, _) => self.create_world(),
+                UiEvent::Clicked(MenuAction::PlayWorld) => {
+                    let selected = self.menu.selected_world().and_then(|row| self.worlds.get(row)).cloned();
+                    if let Some(info) = selected {
+                        self.start_world(info);
+                    }
+                }
+                UiEvent::Clicked(MenuAction::DeleteWorld) => self.delete_selected_world(),
+                UiEvent::Clicked(MenuAction::Back) => self.open_main_menu(),
+                UiEvent::Clicked(MenuAction::Resume) => self.unpause(),
+                UiEvent::Clicked(MenuAction::OpenSettings) => self.menu.open(MenuPage::Settings, &self.settings),
+                UiEvent::Clicked(MenuAction::SaveAndQuit) => self.leave_world(),
+                UiEvent::Clicked(MenuAction::Resolution) => {
+                    self.settings.graphics.resolution = next_resolution(self.settings.graphics.resolution);
+                    self.menu.update_resolution(self.settings.graphics.resolution);
+                    self.apply_window_settings();
+                }
+                UiEvent::Toggled(MenuAction::Fullscreen, on) => {
+                    self.settings.graphics.fullscreen = on;
+                    self.overrides.fullscreen = None;
+                    self.apply_window_settings();
+                }
+                UiEvent::SliderChanged(MenuAction::RenderDistance, value) => self.set_render_distance(value as usize),
+                UiEvent::SliderChanged(MenuAction::Fov, value) => {
+                    self.settings.graphics.fov = value;
+                    self.player.camera.projection.set_fovy(cgmath::Deg(value));
+                }
+                UiEvent::SliderChanged(MenuAction::MouseSensitivity, value) => {
+                    self.settings.mouse_sensitivity = value;
+                    self.player.camera.camera_controller.set_sensitivity(value);
+                }
+                UiEvent::Toggled(MenuAction::Vsync, on) => {
+                    self.settings.graphics.vsync = on;
+                    self.renderer.set_vsync(on);
+                }
+                UiEvent::ItemSelected(MenuAction::Bindings, row) => self.menu.start_rebinding(row),
+                UiEvent::Clicked(MenuAction::ResetBindings) => {
+                    self.settings.controls = InputMap::default();
+                    self.input.map = self.settings.controls.clone();
+                    self.menu.update_bindings(&self.settings.controls);
+                }
+                UiEvent::Clicked(MenuAction::Done) => self.leave_settings(),
+                _ => {}
+            }
+        }
+    }
+
+    /// Back to the pause menu, or the main menu without a world, saving what changed
+    fn leave_settings(&mut self) {
+        self.save_settings();
+        let back = if self.world.is_some() { MenuPage::Pause } else { MenuPage::MainMenu };
+        self.menu.open(back, &self.settings);
+    }
+
+    fn show_world_list(&mut self) {
+        self.worlds = world::list_worlds(Path::new(WORLDS_DIR));
+        self.menu.open(MenuPage::WorldList, &self.settings);
+        self.menu.set_worlds(self.worlds.iter().map(WorldInfo::summary).collect());
+        self.state = GameState::WORLDS;
+    }
+
+    /// Saves a world with the name and seed typed in the world list and plays it
+    fn create_world(&mut self) {
+        let (name, seed) = self.menu.new_world();
+        match world::create_world(Path::new(WORLDS_DIR), &name, world::parse_seed(&seed)) {
+            Ok(info) => self.start_world(info),
+            Err(e) => {
+                log::error!("Failed to create world: {:#}", e);
+                self.menu.show_message("Couldn't create the world");
+            }
         }
-        
+    }
+
+    /// Deletes the world selected in the list on the second click
+    fn delete_selected_world(&mut self) {
+        let Some(info) = self.menu.selected_world().and_then(|row| self.worlds.get(row)).cloned() else {
+            return;
+        };
+        if !self.menu.confirm_delete() {
+            return;
+        }
+        match world::delete_world(&info) {
+            Ok(()) => self.show_world_list(),
+            Err(e) => {
+                log::error!("Failed to delete world: {:#}", e);
+                self.menu.show_message("Couldn't delete the world");
+            }
+        }
+    }
+
+    /// Drops the terrain of the last world, if any, and starts loading `info`
+    pub fn start_world(&mut self, mut info: WorldInfo) {
+        // primero se suelta el terreno anterior, que no estén los dos en la GPU
+        self.terrain = None;
+        self.target = None;
+        self.mining = MiningProgress::default();
+
+        self.player = new_player(&self.renderer, &self.settings);
+        if let Some(feet) = info.player_position {
+            self.player.set_feet(cgmath::Point3::from(feet));
+        }
+        self.hud.update_hotbar(&self.renderer.queue, &self.player.inventory);
+        self.time_of_day = info.time as f32;
+
+        info.last_played = world::unix_time();
+        if let Err(e) = info.save() {
+            log::error!("Failed to save world {}: {:#}", info.name, e);
+        }
+        log::info!("Loading world {} (seed {})", info.name, info.seed);
+        self.terrain = Some(TerrainGen::new(&self.renderer, self.settings.graphics.render_distance, info.seed, self.player.camera.position));
+        self.world = Some(info);
+
+        self.menu.open(MenuPage::Loading, &self.settings);
+        self.state = GameState::LOADING;
+    }
+
+    /// Saves the world and the settings and goes back to the main menu, dropping the terrain
+    fn leave_world(&mut self) {
+        if self.state == GameState::INVENTORY && !self.close_inventory() {
+            log::warn!("Inventory full, the items left in the crafting grid are lost");
+            self.hud.inventory_screen.discard();
+        }
+        self.save_world();
+        self.save_settings();
+
+        self.terrain = None;
+        self.world = None;
+        self.target = None;
+        self.mining = MiningProgress::default();
+        self.input.release_all();
+        self.release_cursor();
+        self.open_main_menu();
+    }
+
+    /// Writes where the player is in the world being played
+    fn save_world(&mut self) {
+        let Some(info) = &mut self.world else {
+            return;
+        };
+        // mientras carga el jugador aún no está en su sitio
+        if self.state != GameState::LOADING {
+            info.player_position = Some(self.player.body.position.into());
+            info.time = self.time_of_day as u32;
+        }
+        info.last_played = world::unix_time();
+        if let Err(e) = info.save() {
+            log::error!("Failed to save world {}: {:#}", info.name, e);
+        }
+    }
+
+    fn save_settings(&self) {
+        if let Err(e) = self.overrides.file_settings(&self.settings).save_to_file(&self.settings_path) {
+            log::error!("Failed to save settings: {}", e);
+        }
+    }
+
+    /// Fullscreen on the current monitor, or a window of the size in the settings
+    fn apply_window_settings(&mut self) {
+        if self.settings.graphics.fullscreen {
+            self.window.set_fullscreen(Some(Fullscreen::Borderless(None)));
+            return;
+        }
+
+        self.window.set_fullscreen(None);
+        let (width, height) = self.settings.graphics.resolution;
+        // si se aplica en el momento puede que no llegue el evento Resized
+        if let Some(size) = self.window.request_inner_size(PhysicalSize::new(width as u32, height as u32)) {
+            self.resize(size);
+        }
+    }
+
+    fn toggle_inventory(&mut self) {
+        if self.state == GameState::PLAYING {
+            self.open_inventory(INVENTORY_GRID_SIZE);
+        } else if self.state == GameState::INVENTORY && self.close_inventory() {
+            self.resume_playing();
+        }
+    }
+
+    /// Shows the inventory with a crafting grid `grid_size` slots a side
+    fn open_inventory(&mut self, grid_size: usize) {
+        self.input.release_all();
+        self.release_cursor();
+        self.hud.inventory_screen.open(grid_size);
+        self.hud.update_inventory(&self.renderer.queue, &self.player.inventory);
+        self.state = GameState::INVENTORY;
+    }
+
+    /// Returns false if the screen stays open because its items don't fit in the inventory
+    fn close_inventory(&mut self) -> bool {
+        self.input.release_all();
+        let closed = self.hud.inventory_screen.close(&mut self.player.inventory);
+        self.hud.update_inventory(&self.renderer.queue, &self.player.inventory);
+        closed
+    }
+
+    /// Back to the game from a menu, the inventory or the console
+    fn resume_playing(&mut self) {
+        self.grab_cursor();
+        // sin ticks la posición anterior quedó vieja, el primer frame no debe interpolar con ella
+        self.player.reset_interpolation();
+        self.state = GameState::PLAYING;
+    }
+
+    fn grab_cursor(&self) {
+        self.window.set_cursor_position(PhysicalPosition::new(self.renderer.size.width / 2, self.renderer.size.height / 2))
+            .expect("No se pudo mover el cursor");
+
+        // Ahora intenta bloquear el cursor
+        self.window.set_cursor_grab(CursorGrabMode::Confined)
+            .expect("No se pudo bloquear el cursor");
+
+        self.window.set_cursor_visible(false);
+    }
+
+    fn release_cursor(&self) {
+        let center = winit::dpi::PhysicalPosition::new(self.renderer.size.width / 2, self.renderer.size.height / 2);
+        self.window.set_cursor_position(center).unwrap_or_else(|e| {
+            eprintln!("Failed to set cursor position: {:?}", e);
+        });
+        self.window.set_cursor_grab(winit::window::CursorGrabMode::None).unwrap();
+        self.window.set_cursor_visible(true);
     }
 
     pub fn handle_device_input(&mut self, event: &DeviceEvent, _: &EventLoopWindowTarget<()>) {
@@ -384,4 +1023,55 @@ impl<'a> State<'a> {
     }
 
     
-}
\ No newline at end of file
+}
+
+
+impl CommandTarget for State<'_> {
+    fn player_position(&self) -> cgmath::Point3<f32> {
+        self.player.body.position
+    }
+
+    fn teleport(&mut self, feet: cgmath::Point3<f32>) {
+        self.player.set_feet(feet);
+        self.mining = MiningProgress::default();
+    }
+
+    fn set_block(&mut self, position: cgmath::Vector3<i32>, material: MaterialType) -> bool {
+        State::set_block(self, position, material)
+    }
+
+    fn fill(&mut self, min: cgmath::Vector3<i32>, max: cgmath::Vector3<i32>, material: MaterialType) -> usize {
+        let positions = (min.x..=max.x).flat_map(move |x| {
+            (min.y..=max.y).flat_map(move |y| (min.z..=max.z).map(move |z| cgmath::Vector3::new(x, y, z)))
+        });
+        self.set_blocks(positions, material)
+    }
+
+    fn seed(&self) -> Option<u32> {
+        self.world.as_ref().map(|world| world.seed)
+    }
+
+    fn set_time(&mut self, time: u32) {
+        self.time_of_day = (time % DAY_LENGTH) as f32;
+    }
+
+    fn set_game_mode(&mut self, mode: GameMode) {
+        self.player.game_mode = mode;
+        self.mining = MiningProgress::default();
+        self.hud.update_hotbar(&self.renderer.queue, &self.player.inventory);
+    }
+
+    fn set_render_distance(&mut self, render_distance: usize) -> usize {
+        State::set_render_distance(self, render_distance);
+        self.settings.graphics.render_distance
+    }
+}
+
+
+/// Player at the default spawn with the camera set up from the settings
+fn new_player(renderer: &Renderer, settings: &Settings) -> Player {
+    let mut camera = Camera::new(renderer, (8.0, 12.0, 8.0), cgmath::Deg(-90.0), cgmath::Deg(-20.0));
+    camera.projection.set_fovy(cgmath::Deg(settings.graphics.fov));
+    camera.camera_controller.set_sensitivity(settings.mouse_sensitivity);
+    Player::new(camera)
+}
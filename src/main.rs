@@ -1,10 +1,18 @@
-use wgpucraft::launcher::run;
+
+use clap::Parser;
+use wgpucraft::{cli::Cli, launcher::run, tools};
 use tracy_client::Client;
 
 
-fn main() {
-    let _client = Client::start(); // Inicia el cliente de Tracy
+fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    cli.init_logging();
+
+    // Inicia el cliente de Tracy, sin él los spans no hacen nada
+    let _client = (!cli.no_tracy).then(Client::start);
 
-    run();
+    match &cli.tool {
+        Some(tool) => tools::run(tool, &cli),
+        None => run(&cli),
+    }
 }
-     
\ No newline at end of file
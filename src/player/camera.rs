@@ -1,14 +1,11 @@
 use cgmath::*;
-use tracy_client::span;
+use crate::profiling::span;
 use winit::event::*;
-use winit::dpi::PhysicalPosition;
-use instant::Duration;
-use winit::keyboard::{KeyCode, PhysicalKey};
 use std::f32::consts::FRAC_PI_2;
 
 use crate::render::renderer::Renderer;
 
-use crate::terrain_gen::{chunk::CHUNK_Y_SIZE, generator::CHUNKS_VIEW_SIZE};
+use crate::terrain_gen::chunk::CHUNK_AREA;
 
 #[rustfmt::skip]
 pub const OPENGL_TO_WGPU_MATRIX: cgmath::Matrix4<f32> = cgmath::Matrix4::new(
@@ -19,9 +16,18 @@ pub const OPENGL_TO_WGPU_MATRIX: cgmath::Matrix4<f32> = cgmath::Matrix4::new(
 );
 
 const SAFE_FRAC_PI_2: f32 = FRAC_PI_2 - 0.0001;
+// Radians per pixel of mouse movement at sensitivity 1, what the rotation used to get
+// from a 60 FPS frame time
+const MOUSE_SCALE: f32 = 1.0 / 60.0;
+// The far plane is moved to the terrain view distance once the world is loaded
+const DEFAULT_ZFAR: f32 = 1000.0;
 
 pub struct Dependants {
-    pub view_proj:  [[f32; 4]; 4]
+    pub view_proj:  [[f32; 4]; 4],
+    /// Integer origin used for rendering, the view matrix is relative to it
+    pub focus_off: [i32; 3],
+    /// Camera position relative to `focus_off`
+    pub cam_pos: [f32; 3],
 }
 
 pub struct Camera {
@@ -43,9 +49,9 @@ impl Camera {
             renderer.config.height,
             cgmath::Deg(45.0),
             0.1,
-            (CHUNKS_VIEW_SIZE * CHUNK_Y_SIZE as usize) as f32,
+            DEFAULT_ZFAR,
         );
-        let camera_controller = CameraController::new(12.0, 2.1);
+        let camera_controller = CameraController::new(2.1);
 
         let mut camera = Self {
             position: position.into(),
@@ -57,21 +63,34 @@ impl Camera {
             camera_controller,
 
             dependants: Dependants {
-                view_proj: Matrix4::identity().into()
+                view_proj: Matrix4::identity().into(),
+                focus_off: [0, 0, 0],
+                cam_pos: [0.0, 0.0, 0.0],
             }
         };
 
-        camera.update_dependants(Duration::from_secs(0));
+        camera.update_dependants();
 
         return camera;
     }
 
-    pub fn calc_matrix(&self) -> Matrix4<f32> {
+    /// Rendering origin: the corner of the chunk the camera is in
+    pub fn focus_off(&self) -> [i32; 3] {
+        [
+            (self.position.x / CHUNK_AREA as f32).floor() as i32 * CHUNK_AREA as i32,
+            0,
+            (self.position.z / CHUNK_AREA as f32).floor() as i32 * CHUNK_AREA as i32,
+        ]
+    }
+
+    /// View matrix relative to `focus_off`
+    pub fn calc_matrix(&self, focus_off: [i32; 3]) -> Matrix4<f32> {
         let (sin_pitch, cos_pitch) = self.pitch.0.sin_cos();
         let (sin_yaw, cos_yaw) = self.yaw.0.sin_cos();
+        let focus = Vector3::new(focus_off[0] as f32, focus_off[1] as f32, focus_off[2] as f32);
 
         Matrix4::look_to_rh(
-            self.position,
+            self.position - focus,
             Vector3::new(
                 cos_pitch * cos_yaw,
                 sin_pitch,
@@ -90,64 +109,44 @@ impl Camera {
         }
     }
 
-    pub fn input_keyboard(&mut self, event: &WindowEvent) -> bool {
-        match event {
-            WindowEvent::KeyboardInput {
-                event: KeyEvent {
-                    state,
-                    physical_key: PhysicalKey::Code(key),
-                    ..
-                },
-                ..
-            } => self.camera_controller.process_keyboard(*key, *state),
-            _ => false,
-        }
-    }
-
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
         self.projection.resize(new_size.width, new_size.height)
     }
 
-    pub fn update_dependants(&mut self, dt: Duration) {
+    pub fn update_dependants(&mut self) {
         let _span = span!("update camera deps"); // <- Marca el inicio del bloque
 
-        self.update_camera_controller(dt);
-        let view_proj:  [[f32; 4]; 4] = (self.projection.calc_matrix() * self.calc_matrix()).into();
-        self.dependants = Dependants {view_proj}
+        let focus_off = self.focus_off();
+        let view_proj:  [[f32; 4]; 4] = (self.projection.calc_matrix() * self.calc_matrix(focus_off)).into();
+        let cam_pos = [
+            self.position.x - focus_off[0] as f32,
+            self.position.y - focus_off[1] as f32,
+            self.position.z - focus_off[2] as f32,
+        ];
+        self.dependants = Dependants {view_proj, focus_off, cam_pos}
     }
 
     pub fn dependants(&self) -> &Dependants { &self.dependants }
 
-    pub fn update_camera_controller(&mut self, dt: Duration) {
-        let dt = dt.as_secs_f32();
-
-        // Move forward/backward and left/right
+    /// World space movement for the `forward`/`right` input amounts, only on the horizontal plane
+    pub fn walk_direction(&self, forward: f32, right: f32) -> Vector3<f32> {
         let (yaw_sin, yaw_cos) = self.yaw.0.sin_cos();
-        let forward = Vector3::new(yaw_cos, 0.0, yaw_sin).normalize();
-        let right = Vector3::new(-yaw_sin, 0.0, yaw_cos).normalize();
-        self.position += forward * (self.camera_controller.amount_forward - self.camera_controller.amount_backward) * self.camera_controller.speed * dt;
-        self.position += right * (self.camera_controller.amount_right - self.camera_controller.amount_left) * self.camera_controller.speed * dt;
-
-        // Move in/out (aka. "zoom")
-        // Note: this isn't an actual zoom. The camera's position
-        // changes when zooming. I've added this to make it easier
-        // to get closer to an object you want to focus on.
-        let (pitch_sin, pitch_cos) = self.pitch.0.sin_cos();
-        let scrollward = Vector3::new(pitch_cos * yaw_cos, pitch_sin, pitch_cos * yaw_sin).normalize();
-        self.position += scrollward * self.camera_controller.scroll * self.camera_controller.speed * self.camera_controller.sensitivity * dt;
-        self.camera_controller.scroll = 0.0;
-
-        // Move up/down. Since we don't use roll, we can just
-        // modify the y coordinate directly.
-        self.position.y += (self.camera_controller.amount_up - self.camera_controller.amount_down) * self.camera_controller.speed * dt;
+        let forward_dir = Vector3::new(yaw_cos, 0.0, yaw_sin).normalize();
+        let right_dir = Vector3::new(-yaw_sin, 0.0, yaw_cos).normalize();
+
+        forward_dir * forward + right_dir * right
+    }
+
+    /// Applies the mouse movement since the last frame. It doesn't depend on the frame
+    /// time, the same movement turns the same at any frame rate.
+    pub fn update_rotation(&mut self) {
+        let scale = self.camera_controller.sensitivity * MOUSE_SCALE;
 
         // Rotate
-        self.yaw += Rad(self.camera_controller.rotate_horizontal) * self.camera_controller.sensitivity * dt;
-        self.pitch += Rad(-self.camera_controller.rotate_vertical) * self.camera_controller.sensitivity * dt;
+        self.yaw += Rad(self.camera_controller.rotate_horizontal) * scale;
+        self.pitch += Rad(-self.camera_controller.rotate_vertical) * scale;
 
-        // If process_mouse isn't called every frame, these values
-        // will not get set to zero, and the camera will rotate
-        // when moving in a non-cardinal direction.
+        // The movement is used up, process_mouse adds up from zero again
         self.camera_controller.rotate_horizontal = 0.0;
         self.camera_controller.rotate_vertical = 0.0;
 
@@ -189,6 +188,15 @@ impl Projection {
         self.aspect = width as f32 / height as f32;
     }
 
+    pub fn set_zfar(&mut self, zfar: f32) {
+        self.zfar = zfar;
+    }
+
+    /// Vertical field of view
+    pub fn set_fovy<F: Into<Rad<f32>>>(&mut self, fovy: F) {
+        self.fovy = fovy.into();
+    }
+
     pub fn calc_matrix(&self) -> Matrix4<f32> {
         OPENGL_TO_WGPU_MATRIX * perspective(self.fovy, self.aspect, self.znear, self.zfar)
     }
@@ -197,80 +205,28 @@ impl Projection {
 
 #[derive(Debug)]
 pub struct CameraController {
-    amount_left: f32,
-    amount_right: f32,
-    amount_forward: f32,
-    amount_backward: f32,
-    amount_up: f32,
-    amount_down: f32,
     rotate_horizontal: f32,
     rotate_vertical: f32,
-    scroll: f32,
-    speed: f32,
     sensitivity: f32,
 }
 
 impl CameraController {
-    pub fn new(speed: f32, sensitivity: f32) -> Self {
+    pub fn new(sensitivity: f32) -> Self {
         Self {
-            amount_left: 0.0,
-            amount_right: 0.0,
-            amount_forward: 0.0,
-            amount_backward: 0.0,
-            amount_up: 0.0,
-            amount_down: 0.0,
             rotate_horizontal: 0.0,
             rotate_vertical: 0.0,
-            scroll: 0.0,
-            speed,
             sensitivity,
         }
     }
 
-    pub fn process_keyboard(&mut self, key: KeyCode, state: ElementState) -> bool{
-        let amount = if state == ElementState::Pressed { 1.0 } else { 0.0 };
-        match key {
-            KeyCode::KeyW | KeyCode::ArrowUp => {
-                self.amount_forward = amount;
-                true
-            }
-            KeyCode::KeyS | KeyCode::ArrowDown => {
-                self.amount_backward = amount;
-                true
-            }
-            KeyCode::KeyA | KeyCode::ArrowLeft => {
-                self.amount_left = amount;
-                true
-            }
-            KeyCode::KeyD | KeyCode::ArrowRight => {
-                self.amount_right = amount;
-                true
-            }
-            KeyCode::Space => {
-                self.amount_up = amount;
-                true
-            }
-            KeyCode::ShiftLeft => {
-                self.amount_down = amount;
-                true
-            }
-            _ => false,
-        }
+    pub fn set_sensitivity(&mut self, sensitivity: f32) {
+        self.sensitivity = sensitivity;
     }
 
+    /// Adds up the movement until `Camera::update_rotation` uses it, there can be
+    /// several events per frame
     pub fn process_mouse(&mut self, mouse_dx: f64, mouse_dy: f64) {
-        self.rotate_horizontal = mouse_dx as f32;
-        self.rotate_vertical = mouse_dy as f32;
-    }
-
-    pub fn process_scroll(&mut self, delta: &MouseScrollDelta) {
-        self.scroll = -match delta {
-            // I'm assuming a line is about 100 pixels
-            MouseScrollDelta::LineDelta(_, scroll) => scroll * 100.0,
-            MouseScrollDelta::PixelDelta(PhysicalPosition {
-                y: scroll,
-                ..
-            }) => *scroll as f32,
-        };
+        self.rotate_horizontal += mouse_dx as f32;
+        self.rotate_vertical += mouse_dy as f32;
     }
 }
\ No newline at end of file
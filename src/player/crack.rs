@@ -0,0 +1,152 @@
+use cgmath::Vector3;
+use crate::profiling::span;
+
+use crate::{render::{mesh::Mesh, model::DynamicModel, pipelines::crack::{create_crack_pipeline, CrackVertex}, renderer::{Draw, Renderer}, texture::Texture}, terrain_gen::block::Direction};
+
+use super::mining::CRACK_STAGES;
+
+
+/// Same as the outline, the cracks sit just outside the block faces
+const CRACK_INFLATE: f32 = 0.002;
+/// Pixels a side of each crack stage
+const CRACK_TEXTURE_SIZE: u32 = 16;
+/// Crack lines that start from the center of the texture
+const CRACK_BRANCHES: u32 = 6;
+const CRACK_COLOR: [u8; 4] = [0, 0, 0, 170];
+// 6 caras de 4 vértices
+const CRACK_VERTICES: usize = 24;
+
+
+/// Cracks drawn over the block being broken, they grow with the break progress
+pub struct CrackOverlay {
+    pipeline: wgpu::RenderPipeline,
+    // la textura tiene que vivir tanto como el bind group
+    _texture: Texture,
+    bind_group: wgpu::BindGroup,
+    model: DynamicModel<CrackVertex>,
+    visible: bool,
+}
+
+impl CrackOverlay {
+    pub fn new(renderer: &Renderer) -> Self {
+        let shader = renderer.device.create_shader_module(
+            wgpu::include_wgsl!("../../assets/shaders/crack.wgsl")
+        );
+
+        let pipeline = create_crack_pipeline(
+            &renderer.device,
+            &renderer.layouts.global,
+            shader,
+            &renderer.config,
+        );
+
+        let texture = Texture::from_image(&renderer.device, &renderer.queue, &crack_image(), Some("crack stages")).unwrap();
+        let bind_group = renderer.layouts.global.bind_hud_texture(&renderer.device, &texture, None);
+
+        Self {
+            pipeline,
+            _texture: texture,
+            bind_group,
+            model: DynamicModel::new(&renderer.device, CRACK_VERTICES),
+            visible: false,
+        }
+    }
+
+    /// Shows `stage` over the block at the position, or hides the cracks with `None`.
+    /// `focus_off` has to be the one used for the current frame's `Globals`.
+    pub fn update(&mut self, queue: &wgpu::Queue, crack: Option<(Vector3<i32>, u32)>, focus_off: [i32; 3]) {
+        let _span = span!("update crack overlay");
+
+        let Some((position, stage)) = crack else {
+            self.visible = false;
+            return;
+        };
+
+        let origin = position - Vector3::new(focus_off[0], focus_off[1], focus_off[2]);
+        self.model.update(queue, &crack_mesh(origin, stage), 0);
+        self.visible = true;
+    }
+}
+
+
+/// Every face of the inflated unit cube at `origin` showing `stage` of the strip
+fn crack_mesh(origin: Vector3<i32>, stage: u32) -> Mesh<CrackVertex> {
+    let stage_width = 1.0 / CRACK_STAGES as f32;
+
+    let mut mesh = Mesh::new();
+    for direction in Direction::ALL {
+        let base_index = mesh.verts.len() as u16;
+        for (corner, uv) in direction.corners() {
+            let pos = std::array::from_fn(|k| {
+                let inflate = if corner[k] == 1 { CRACK_INFLATE } else { -CRACK_INFLATE };
+                (origin[k] + corner[k] as i32) as f32 + inflate
+            });
+            let u = (stage as f32 + uv[0] as f32) * stage_width;
+            mesh.push(CrackVertex { pos, uv: [u, uv[1] as f32] });
+        }
+        mesh.push_indices(&[base_index, base_index + 1, base_index + 2, base_index + 2, base_index + 3, base_index]);
+    }
+    mesh
+}
+
+
+/// All the stages side by side. The cracks are random walks from the center and each
+/// stage shows the pixels of the previous one plus the next stretch of every walk.
+fn crack_image() -> image::DynamicImage {
+    let size = CRACK_TEXTURE_SIZE as i32;
+    let mut order = vec![u32::MAX; (size * size) as usize];
+
+    // generador fijo, las grietas son siempre las mismas
+    let mut seed: u32 = 0x2545_f491;
+    let mut random = move |n: u32| {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        seed % n
+    };
+
+    let steps = size as u32;
+    let mut walkers: Vec<(i32, i32, i32, i32)> = (0..CRACK_BRANCHES).map(|i| {
+        let (dx, dy) = [(1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1), (0, -1), (1, -1)][(i * 8 / CRACK_BRANCHES) as usize];
+        (size / 2, size / 2, dx, dy)
+    }).collect();
+    for step in 0..steps {
+        for (x, y, dx, dy) in walkers.iter_mut() {
+            // de vez en cuando gira un poco
+            if random(3) == 0 {
+                (*dx, *dy) = if random(2) == 0 { (*dx - *dy, *dx + *dy) } else { (*dx + *dy, *dy - *dx) };
+                (*dx, *dy) = (dx.signum(), dy.signum());
+            }
+            *x = (*x + *dx).clamp(0, size - 1);
+            *y = (*y + *dy).clamp(0, size - 1);
+            let pixel = &mut order[(*y * size + *x) as usize];
+            *pixel = (*pixel).min(step);
+        }
+    }
+
+    let image = image::RgbaImage::from_fn(CRACK_TEXTURE_SIZE * CRACK_STAGES, CRACK_TEXTURE_SIZE, |x, y| {
+        let stage = x / CRACK_TEXTURE_SIZE;
+        let step = order[(y * CRACK_TEXTURE_SIZE + x % CRACK_TEXTURE_SIZE) as usize];
+        let visible = step != u32::MAX && step * CRACK_STAGES <= stage * steps + steps / 2;
+        if visible { image::Rgba(CRACK_COLOR) } else { image::Rgba([0, 0, 0, 0]) }
+    });
+    image::DynamicImage::ImageRgba8(image)
+}
+
+
+impl Draw for CrackOverlay {
+    fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, globals: &'a wgpu::BindGroup) -> Result<(), wgpu::Error> {
+        if !self.visible {
+            return Ok(());
+        }
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, globals, &[]);
+        render_pass.set_bind_group(1, &self.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.model.vbuf().slice(..));
+        render_pass.set_index_buffer(self.model.ibuf().slice(..), wgpu::IndexFormat::Uint16);
+        render_pass.draw_indexed(0..self.model.num_indices as u32, 0, 0..1);
+
+        Ok(())
+    }
+}
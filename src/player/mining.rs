@@ -0,0 +1,126 @@
+use cgmath::Vector3;
+
+use crate::{item::{tool::can_harvest, Item}, render::atlas::MaterialType};
+
+
+/// Seconds per point of hardness when breaking with the hand
+pub const SECONDS_PER_HARDNESS: f32 = 1.5;
+/// Same without the tool the block needs to drop anything
+pub const SECONDS_PER_HARDNESS_WRONG_TOOL: f32 = 5.0;
+/// Number of crack textures shown while breaking
+pub const CRACK_STAGES: u32 = 10;
+
+
+/// Seconds it takes to break `material` holding `held`, `None` if it can't be broken
+pub fn break_time(material: MaterialType, held: Option<Item>) -> Option<f32> {
+    let seconds = if can_harvest(material, held.and_then(Item::tool)) {
+        SECONDS_PER_HARDNESS
+    } else {
+        SECONDS_PER_HARDNESS_WRONG_TOOL
+    };
+    let speed = held.map_or(1.0, |item| item.mining_speed(material));
+    Some(material.hardness()? * seconds / speed)
+}
+
+
+/// Progress breaking the targeted block while the break action is held
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MiningProgress {
+    target: Option<Vector3<i32>>,
+    /// From 0 to 1
+    progress: f32,
+}
+
+impl MiningProgress {
+    /// Adds `dt` seconds of work on `target`. Changing the target starts over, `None`
+    /// stops. Returns true once the block breaks.
+    pub fn update(&mut self, target: Option<Vector3<i32>>, break_time: Option<f32>, dt: f32) -> bool {
+        if target != self.target {
+            self.target = target;
+            self.progress = 0.0;
+        }
+
+        let (Some(_), Some(break_time)) = (target, break_time) else {
+            self.progress = 0.0;
+            return false;
+        };
+
+        self.progress += if break_time > 0.0 { dt / break_time } else { 1.0 };
+        if self.progress < 1.0 {
+            return false;
+        }
+
+        self.reset();
+        true
+    }
+
+    pub fn reset(&mut self) {
+        self.target = None;
+        self.progress = 0.0;
+    }
+
+    pub fn progress(&self) -> f32 {
+        self.progress
+    }
+
+    /// Block being broken and its crack texture, from 0 to `CRACK_STAGES - 1`
+    pub fn crack(&self) -> Option<(Vector3<i32>, u32)> {
+        let target = self.target.filter(|_| self.progress > 0.0)?;
+        let stage = (self.progress * CRACK_STAGES as f32) as u32;
+        Some((target, stage.min(CRACK_STAGES - 1)))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::item::tool::{Tool, ToolKind, ToolTier};
+
+    use super::*;
+
+    const BLOCK: Vector3<i32> = Vector3::new(1, 2, 3);
+
+    #[test]
+    fn breaks_after_the_break_time() {
+        let mut mining = MiningProgress::default();
+
+        for _ in 0..7 {
+            assert!(!mining.update(Some(BLOCK), Some(1.0), 0.125));
+        }
+        assert_eq!(mining.crack().map(|(_, stage)| stage), Some(8));
+        assert!(mining.update(Some(BLOCK), Some(1.0), 0.125));
+        assert_eq!(mining.crack(), None);
+    }
+
+    #[test]
+    fn changing_the_target_starts_over() {
+        let mut mining = MiningProgress::default();
+        mining.update(Some(BLOCK), Some(1.0), 0.5);
+
+        assert!(!mining.update(Some(BLOCK + Vector3::unit_x()), Some(1.0), 0.1));
+        assert!((mining.progress() - 0.1).abs() < 1e-6);
+
+        mining.update(None, None, 0.1);
+        assert_eq!(mining.progress(), 0.0);
+    }
+
+    #[test]
+    fn unbreakable_blocks_never_break() {
+        let mut mining = MiningProgress::default();
+
+        for _ in 0..100 {
+            assert!(!mining.update(Some(BLOCK), break_time(MaterialType::WATER, None), 1.0));
+        }
+        assert_eq!(break_time(MaterialType::DIRT, None), Some(0.5 * SECONDS_PER_HARDNESS));
+    }
+
+    #[test]
+    fn the_right_tool_is_faster() {
+        let pickaxe = Item::Tool(Tool::new(ToolKind::Pickaxe, ToolTier::Stone));
+        let shovel = Item::Tool(Tool::new(ToolKind::Shovel, ToolTier::Stone));
+
+        assert_eq!(break_time(MaterialType::ROCK, None), Some(1.5 * SECONDS_PER_HARDNESS_WRONG_TOOL));
+        assert_eq!(break_time(MaterialType::ROCK, Some(shovel)), Some(1.5 * SECONDS_PER_HARDNESS_WRONG_TOOL));
+        assert_eq!(break_time(MaterialType::ROCK, Some(pickaxe)), Some(1.5 * SECONDS_PER_HARDNESS / 4.0));
+    }
+}
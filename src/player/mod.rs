@@ -1,21 +1,201 @@
+use cgmath::{EuclideanSpace, InnerSpace, Point3, Vector3};
+use instant::Duration;
+use crate::profiling::span;
+
+use movement::{Gait, MovementMode};
+use physics::{MoveInput, PhysicsBody, VoxelWorld, PLAYER_HALF_WIDTH, PLAYER_HEIGHT};
+
+use crate::{input::{Action, InputState}, item::{inventory::Inventory, Item, ItemStack, MAX_STACK_SIZE}, render::atlas::MaterialType, terrain_gen::chunk::CHUNK_Y_SIZE};
+
 pub mod camera;
+pub mod crack;
+pub mod mining;
+pub mod movement;
+pub mod physics;
 pub mod raycast;
+pub mod selection;
+
+
+/// Height of the camera over the feet
+pub const EYE_HEIGHT: f32 = 1.62;
+/// Max time between two jump presses to toggle flying
+const DOUBLE_TAP_TIME: f32 = 0.3;
+
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GameMode {
+    /// Breaking blocks gives items and placing them uses them up
+    Survival,
+    /// Unlimited blocks, broken blocks drop nothing
+    Creative,
+}
 
 
 pub struct Player {
     pub camera: camera::Camera,
-    pub last_pos: cgmath::Point3<f32>,
-    pub speed: f32,
-    
+    pub body: PhysicsBody,
+    /// Feet at the start of the last tick, the camera is drawn between them and the body
+    previous_position: Point3<f32>,
+    pub inventory: Inventory,
+    pub game_mode: GameMode,
+    pub mode: MovementMode,
+    /// The camera is inside a liquid block, used for the underwater fog and HUD tint
+    pub eyes_in_water: bool,
+    jump_held: bool,
+    /// Seconds since the last jump press, for the double tap
+    since_jump_press: f32,
 }
 
 
 impl Player {
     pub fn new(camera: camera::Camera) -> Self {
+        let feet = camera.position - Vector3::new(0.0, EYE_HEIGHT, 0.0);
         Self {
             camera,
-            last_pos: cgmath::Point3::new(0.0, 0.0, 0.0),
-            speed: 0.1,
+            body: PhysicsBody::new(feet, PLAYER_HALF_WIDTH, PLAYER_HEIGHT),
+            previous_position: feet,
+            inventory: starting_inventory(),
+            game_mode: GameMode::Survival,
+            mode: MovementMode::Walk,
+            eyes_in_water: false,
+            jump_held: false,
+            since_jump_press: f32::MAX,
+        }
+    }
+
+    /// Moves the player to the top of the highest solid block of its column
+    pub fn spawn_on_surface(&mut self, world: &impl VoxelWorld) {
+        let x = self.body.position.x.floor() as i32;
+        let z = self.body.position.z.floor() as i32;
+        let top = (0..CHUNK_Y_SIZE as i32).rev().find(|&y| world.is_solid(Vector3::new(x, y, z)));
+
+        if let Some(top) = top {
+            self.set_feet(Point3::new(self.body.position.x, (top + 1) as f32, self.body.position.z));
         }
     }
-}
\ No newline at end of file
+
+    pub fn set_mode(&mut self, mode: MovementMode) {
+        self.mode = mode;
+        self.body.velocity = Vector3::new(0.0, 0.0, 0.0);
+        self.body.on_ground = false;
+    }
+
+    /// Spectator goes back to walking
+    pub fn toggle_spectator(&mut self) {
+        self.set_mode(match self.mode {
+            MovementMode::Spectator => MovementMode::Walk,
+            _ => MovementMode::Spectator,
+        });
+    }
+
+    pub fn gait(&self, input: &InputState) -> Gait {
+        if input.is_down(Action::Sprint) {
+            Gait::Sprint
+        } else if input.is_down(Action::Sneak) {
+            Gait::Sneak
+        } else {
+            Gait::Normal
+        }
+    }
+
+    /// One simulation tick of movement. The camera follows in `interpolate`.
+    pub fn update(&mut self, dt: Duration, world: &impl VoxelWorld, input: &InputState) {
+        let _span = span!("update player");
+
+        self.previous_position = self.body.position;
+        let dt = dt.as_secs_f32();
+        self.update_double_tap(dt, input);
+
+        let speed = self.mode.speed(self.gait(input));
+        let horizontal = self.camera.walk_direction(
+            input.axis(Action::MoveForward, Action::MoveBackward),
+            input.axis(Action::MoveRight, Action::MoveLeft),
+        );
+        let horizontal = if horizontal.x != 0.0 || horizontal.z != 0.0 { horizontal.normalize() * speed } else { horizontal };
+        let vertical = input.axis(Action::Jump, Action::Sneak) * self.mode.vertical_speed();
+
+        match self.mode {
+            MovementMode::Walk => {
+                let input = MoveInput {
+                    wish: horizontal,
+                    jump: input.is_down(Action::Jump),
+                    descend: input.is_down(Action::Sneak),
+                };
+                self.body.step(world, input, dt);
+            }
+            MovementMode::Fly => {
+                self.body.fly(world, horizontal + Vector3::unit_y() * vertical, dt);
+                // al tocar el suelo se deja de volar
+                if self.body.on_ground && vertical < 0.0 {
+                    self.set_mode(MovementMode::Walk);
+                }
+            }
+            MovementMode::Spectator => {
+                self.body.position += (horizontal + Vector3::unit_y() * vertical) * dt;
+            }
+        }
+
+        let eyes = self.body.position + Vector3::new(0.0, EYE_HEIGHT, 0.0);
+        self.eyes_in_water = world.is_liquid(eyes.map(|c| c.floor() as i32).to_vec());
+    }
+
+    /// Turns the camera with the mouse movement since the last frame
+    pub fn look(&mut self) {
+        self.camera.update_rotation();
+    }
+
+    /// Puts the camera `alpha` of the way from the position before the last tick to
+    /// the current one, so it moves smoothly at any frame rate
+    pub fn interpolate(&mut self, alpha: f32) {
+        let feet = self.previous_position + (self.body.position - self.previous_position) * alpha.clamp(0.0, 1.0);
+        self.camera.position = feet + Vector3::new(0.0, EYE_HEIGHT, 0.0);
+        self.camera.update_dependants();
+    }
+
+    /// Draws the camera at the current position until the next tick
+    pub fn reset_interpolation(&mut self) {
+        self.previous_position = self.body.position;
+    }
+
+    /// Two jump presses in a row switch between walking and flying
+    fn update_double_tap(&mut self, dt: f32, input: &InputState) {
+        let jump = input.is_down(Action::Jump);
+        let pressed = jump && !self.jump_held;
+        self.jump_held = jump;
+        self.since_jump_press += dt;
+
+        if !pressed {
+            return;
+        }
+
+        if self.since_jump_press < DOUBLE_TAP_TIME {
+            match self.mode {
+                MovementMode::Walk => self.set_mode(MovementMode::Fly),
+                MovementMode::Fly => self.set_mode(MovementMode::Walk),
+                MovementMode::Spectator => {}
+            }
+            self.since_jump_press = f32::MAX;
+        } else {
+            self.since_jump_press = 0.0;
+        }
+    }
+
+    /// Moves the player's feet to `feet`, stopping it
+    pub fn set_feet(&mut self, feet: Point3<f32>) {
+        self.body.position = feet;
+        self.previous_position = feet;
+        self.body.velocity = Vector3::new(0.0, 0.0, 0.0);
+        self.camera.position = feet + Vector3::new(0.0, EYE_HEIGHT, 0.0);
+        self.camera.update_dependants();
+    }
+}
+
+
+fn starting_inventory() -> Inventory {
+    let mut inventory = Inventory::default();
+    // todavía no hay árboles, la madera para las recetas sale de aquí
+    for material in [MaterialType::ROCK, MaterialType::GRASS, MaterialType::DIRT, MaterialType::LOG] {
+        inventory.add(ItemStack::new(Item::Block(material), MAX_STACK_SIZE));
+    }
+    inventory
+}
@@ -0,0 +1,49 @@
+/// How the player moves through the world
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MovementMode {
+    /// Gravity and collisions
+    Walk,
+    /// Creative flight: collisions but no gravity
+    Fly,
+    /// Goes through everything
+    Spectator,
+}
+
+
+/// Speed modifier held by the player
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Gait {
+    Normal,
+    Sprint,
+    Sneak,
+}
+
+
+impl MovementMode {
+    /// Horizontal speed in blocks per second
+    pub fn speed(self, gait: Gait) -> f32 {
+        match (self, gait) {
+            (MovementMode::Walk, Gait::Normal) => 4.3,
+            (MovementMode::Walk, Gait::Sprint) => 5.6,
+            (MovementMode::Walk, Gait::Sneak) => 1.3,
+            // volando shift es bajar, no agacharse
+            (MovementMode::Fly, Gait::Sprint) => 21.6,
+            (MovementMode::Fly, _) => 10.9,
+            (MovementMode::Spectator, Gait::Sprint) => 24.0,
+            (MovementMode::Spectator, _) => 12.0,
+        }
+    }
+
+    /// Speed going up/down while flying
+    pub fn vertical_speed(self) -> f32 {
+        match self {
+            MovementMode::Walk => 0.0,
+            MovementMode::Fly => 7.5,
+            MovementMode::Spectator => 12.0,
+        }
+    }
+
+    pub fn has_collisions(self) -> bool {
+        self != MovementMode::Spectator
+    }
+}
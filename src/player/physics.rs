@@ -0,0 +1,608 @@
+use cgmath::{Point3, Vector3};
+
+use crate::{render::atlas::MaterialType, terrain_gen::chunk::ChunkManager};
+
+
+pub const GRAVITY: f32 = 32.0;
+pub const JUMP_VELOCITY: f32 = 9.0;
+pub const TERMINAL_VELOCITY: f32 = 78.0;
+/// Highest ledge the body climbs without jumping
+pub const STEP_HEIGHT: f32 = 1.0;
+
+pub const PLAYER_HALF_WIDTH: f32 = 0.3;
+pub const PLAYER_HEIGHT: f32 = 1.8;
+
+/// Sinking acceleration in water, much weaker than gravity
+const WATER_GRAVITY: f32 = 4.0;
+/// Upward acceleration when fully submerged, a resting body floats with its head out
+const BUOYANCY: f32 = 6.0;
+/// Fraction of the velocity lost per second in water (exponential decay rate)
+const WATER_DRAG: f32 = 3.0;
+/// Acceleration while holding jump/sneak in water
+const SWIM_ACCEL: f32 = 12.0;
+/// Horizontal speed multiplier when in water
+const WATER_SPEED_FACTOR: f32 = 0.5;
+/// Vertical speed given when swimming against a ledge, lets the body climb out of the water
+const SHORE_EXIT_VELOCITY: f32 = 6.0;
+
+/// Longest distance moved along an axis in one collision pass, keeps fast bodies from tunneling
+const MAX_AXIS_STEP: f32 = 0.45;
+/// Longest time step simulated at once, long frames are split
+const MAX_DT: f32 = 0.05;
+/// Tolerance for faces that touch, absorbs float error after snapping to a block face
+const EPSILON: f32 = 1e-4;
+
+
+/// Anything the physics can collide against
+pub trait VoxelWorld {
+    fn is_solid(&self, pos: Vector3<i32>) -> bool;
+    fn is_liquid(&self, pos: Vector3<i32>) -> bool;
+}
+
+impl VoxelWorld for ChunkManager {
+    fn is_solid(&self, pos: Vector3<i32>) -> bool {
+        self.get_block_material(pos).is_some_and(|material| !material.is_transparent())
+    }
+
+    fn is_liquid(&self, pos: Vector3<i32>) -> bool {
+        self.get_block_material(pos) == Some(MaterialType::WATER)
+    }
+}
+
+
+/// What the player asks the body to do this step
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MoveInput {
+    /// Horizontal velocity
+    pub wish: Vector3<f32>,
+    /// Jump on the ground, swim up in water
+    pub jump: bool,
+    /// Swim down in water
+    pub descend: bool,
+}
+
+
+/// Axis aligned bounding box in world space
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vector3<f32>,
+    pub max: Vector3<f32>,
+}
+
+impl Aabb {
+    /// Positions of every block the box overlaps, touching faces don't count
+    pub fn blocks(&self) -> impl Iterator<Item = Vector3<i32>> {
+        let min = self.min.map(|c| (c + EPSILON).floor() as i32);
+        let max = self.max.map(|c| (c - EPSILON).ceil() as i32 - 1);
+
+        (min.x..=max.x).flat_map(move |x| {
+            (min.y..=max.y).flat_map(move |y| {
+                (min.z..=max.z).map(move |z| Vector3::new(x, y, z))
+            })
+        })
+    }
+
+    pub fn collides(&self, world: &impl VoxelWorld) -> bool {
+        self.blocks().any(|pos| world.is_solid(pos))
+    }
+}
+
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    fn index(self) -> usize {
+        match self {
+            Axis::X => 0,
+            Axis::Y => 1,
+            Axis::Z => 2,
+        }
+    }
+}
+
+
+/// Box shaped body that moves through the voxel world, resolving collisions one axis at a time
+#[derive(Copy, Clone, Debug)]
+pub struct PhysicsBody {
+    /// Center of the bottom face
+    pub position: Point3<f32>,
+    pub velocity: Vector3<f32>,
+    pub on_ground: bool,
+    /// A wall stopped the last horizontal movement
+    pub blocked: bool,
+    /// Fraction of the body height under liquid, 0 to 1
+    pub submersion: f32,
+    pub half_width: f32,
+    pub height: f32,
+}
+
+impl PhysicsBody {
+    pub fn new(position: Point3<f32>, half_width: f32, height: f32) -> Self {
+        Self {
+            position,
+            velocity: Vector3::new(0.0, 0.0, 0.0),
+            on_ground: false,
+            blocked: false,
+            submersion: 0.0,
+            half_width,
+            height,
+        }
+    }
+
+    pub fn aabb(&self) -> Aabb {
+        Aabb {
+            min: Vector3::new(self.position.x - self.half_width, self.position.y, self.position.z - self.half_width),
+            max: Vector3::new(self.position.x + self.half_width, self.position.y + self.height, self.position.z + self.half_width),
+        }
+    }
+
+    pub fn in_liquid(&self) -> bool {
+        self.submersion > 0.0
+    }
+
+    /// Measures how much of the body is under liquid: every block layer it overlaps
+    /// counts as wet if any of its blocks is liquid
+    pub fn update_submersion(&mut self, world: &impl VoxelWorld) {
+        let aabb = self.aabb();
+        // blocks() recorre x antes que y: una capa vuelve a salir por cada columna
+        let mut layers: Vec<i32> = aabb.blocks().filter(|&pos| world.is_liquid(pos)).map(|pos| pos.y).collect();
+        layers.sort_unstable();
+        layers.dedup();
+
+        let wet: f32 = layers.iter().map(|&y| {
+            let bottom = (y as f32).max(aabb.min.y);
+            let top = ((y + 1) as f32).min(aabb.max.y);
+            top - bottom
+        }).sum();
+
+        self.submersion = (wet / self.height).clamp(0.0, 1.0);
+    }
+
+    /// Walking step: gravity and jumps on land, buoyancy and swimming in liquids
+    pub fn step(&mut self, world: &impl VoxelWorld, input: MoveInput, dt: f32) {
+        let mut remaining = dt;
+        while remaining > 0.0 {
+            let dt = remaining.min(MAX_DT);
+            remaining -= dt;
+
+            self.update_submersion(world);
+            if self.in_liquid() {
+                self.swim(input, dt);
+            } else {
+                self.velocity.x = input.wish.x;
+                self.velocity.z = input.wish.z;
+                if input.jump && self.on_ground {
+                    self.velocity.y = JUMP_VELOCITY;
+                }
+                self.velocity.y = (self.velocity.y - GRAVITY * dt).max(-TERMINAL_VELOCITY);
+            }
+
+            self.move_and_collide(world, self.velocity * dt);
+        }
+    }
+
+    fn swim(&mut self, input: MoveInput, dt: f32) {
+        self.velocity.x = input.wish.x * WATER_SPEED_FACTOR;
+        self.velocity.z = input.wish.z * WATER_SPEED_FACTOR;
+
+        let mut accel = BUOYANCY * self.submersion - WATER_GRAVITY;
+        if input.jump {
+            accel += SWIM_ACCEL;
+        }
+        if input.descend {
+            accel -= SWIM_ACCEL;
+        }
+        self.velocity.y = (self.velocity.y + accel * dt) * (-WATER_DRAG * dt).exp();
+
+        // nadando contra la orilla: impulso para salir del agua
+        if input.jump && self.blocked {
+            self.velocity.y = self.velocity.y.max(SHORE_EXIT_VELOCITY);
+        }
+    }
+
+    /// Flying step: moves at `velocity` colliding with blocks but without gravity
+    pub fn fly(&mut self, world: &impl VoxelWorld, velocity: Vector3<f32>, dt: f32) {
+        let mut remaining = dt;
+        while remaining > 0.0 {
+            let dt = remaining.min(MAX_DT);
+            remaining -= dt;
+
+            self.velocity = velocity;
+            self.move_and_collide(world, self.velocity * dt);
+        }
+    }
+
+    /// Moves by `delta`, vertical movement first so the ground state is known before
+    /// trying to step up while moving horizontally
+    pub fn move_and_collide(&mut self, world: &impl VoxelWorld, delta: Vector3<f32>) {
+        let moved_y = self.move_axis(world, Axis::Y, delta.y);
+        if moved_y != delta.y {
+            if delta.y < 0.0 {
+                self.on_ground = true;
+            }
+            self.velocity.y = 0.0;
+        } else if delta.y != 0.0 {
+            self.on_ground = false;
+        }
+
+        let start = self.position;
+        let moved_x = self.move_axis(world, Axis::X, delta.x);
+        let moved_z = self.move_axis(world, Axis::Z, delta.z);
+        self.blocked = moved_x != delta.x || moved_z != delta.z;
+
+        if self.blocked && self.on_ground {
+            self.blocked = !self.try_step_up(world, start, Vector3::new(delta.x, 0.0, delta.z));
+        }
+    }
+
+    /// Repeats the horizontal movement from `start` lifted by `STEP_HEIGHT`, then drops
+    /// back down. Kept only if it got further than walking into the obstacle.
+    fn try_step_up(&mut self, world: &impl VoxelWorld, start: Point3<f32>, delta: Vector3<f32>) -> bool {
+        let flat = self.position;
+        let flat_distance = horizontal_distance(start, flat);
+
+        self.position = start;
+        let lifted = self.move_axis(world, Axis::Y, STEP_HEIGHT);
+        self.move_axis(world, Axis::X, delta.x);
+        self.move_axis(world, Axis::Z, delta.z);
+        self.move_axis(world, Axis::Y, -lifted);
+
+        if horizontal_distance(start, self.position) <= flat_distance {
+            self.position = flat;
+            return false;
+        }
+        true
+    }
+
+    /// Moves along one axis until the first solid block, returns the distance actually moved.
+    /// Blocks the body already overlapped don't stop it, so it can walk out of them.
+    pub fn move_axis(&mut self, world: &impl VoxelWorld, axis: Axis, delta: f32) -> f32 {
+        let i = axis.index();
+        let steps = (delta.abs() / MAX_AXIS_STEP).ceil() as usize;
+        let step = delta / steps.max(1) as f32;
+        let mut moved = 0.0;
+
+        for _ in 0..steps {
+            let before = self.aabb();
+            self.position[i] += step;
+
+            let aabb = self.aabb();
+            let solid = aabb.blocks().filter(|pos| world.is_solid(*pos)).map(|pos| pos[i]);
+            let contact = if step > 0.0 {
+                solid.filter(|&block| block as f32 >= before.max[i] - EPSILON)
+                    .min()
+                    .map(|block| block as f32 - (aabb.max[i] - self.position[i]))
+            } else {
+                solid.filter(|&block| (block + 1) as f32 <= before.min[i] + EPSILON)
+                    .max()
+                    .map(|block| (block + 1) as f32 - (aabb.min[i] - self.position[i]))
+            };
+
+            if let Some(contact) = contact {
+                moved += contact - self.position[i] + step;
+                self.position[i] = contact;
+                return moved;
+            }
+            moved += step;
+        }
+
+        delta
+    }
+}
+
+
+fn horizontal_distance(a: Point3<f32>, b: Point3<f32>) -> f32 {
+    ((b.x - a.x).powi(2) + (b.z - a.z).powi(2)).sqrt()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    struct Grid(HashSet<Vector3<i32>>, HashSet<Vector3<i32>>);
+
+    impl Grid {
+        /// Solid floor at y = 0 covering -8..8 on x/z
+        fn floor() -> Self {
+            let mut blocks = HashSet::new();
+            for x in -8..8 {
+                for z in -8..8 {
+                    blocks.insert(Vector3::new(x, 0, z));
+                }
+            }
+            Self(blocks, HashSet::new())
+        }
+
+        fn with(mut self, x: i32, y: i32, z: i32) -> Self {
+            self.0.insert(Vector3::new(x, y, z));
+            self
+        }
+
+        /// Fills every non solid block of the floor area from y = 1 to `level` with water
+        fn flooded(mut self, level: i32) -> Self {
+            for x in -8..8 {
+                for y in 1..=level {
+                    for z in -8..8 {
+                        let pos = Vector3::new(x, y, z);
+                        if !self.0.contains(&pos) {
+                            self.1.insert(pos);
+                        }
+                    }
+                }
+            }
+            self
+        }
+    }
+
+    impl VoxelWorld for Grid {
+        fn is_solid(&self, pos: Vector3<i32>) -> bool {
+            self.0.contains(&pos)
+        }
+
+        fn is_liquid(&self, pos: Vector3<i32>) -> bool {
+            self.1.contains(&pos)
+        }
+    }
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-3, "{a} != {b}");
+    }
+
+    fn player_at(x: f32, y: f32, z: f32) -> PhysicsBody {
+        PhysicsBody::new(Point3::new(x, y, z), PLAYER_HALF_WIDTH, PLAYER_HEIGHT)
+    }
+
+    fn input(wish: Vector3<f32>, jump: bool) -> MoveInput {
+        MoveInput { wish, jump, descend: false }
+    }
+
+    fn simulate(body: &mut PhysicsBody, world: &Grid, wish: Vector3<f32>, jump: bool, seconds: f32) {
+        let dt = 1.0 / 60.0;
+        for _ in 0..(seconds / dt) as usize {
+            body.step(world, input(wish, jump), dt);
+        }
+    }
+
+    #[test]
+    fn aabb_blocks_ignore_touching_faces() {
+        let aabb = Aabb {
+            min: Vector3::new(0.0, 1.0, 0.0),
+            max: Vector3::new(1.0, 2.0, 1.0),
+        };
+        let blocks: Vec<_> = aabb.blocks().collect();
+        assert_eq!(blocks, vec![Vector3::new(0, 1, 0)]);
+    }
+
+    #[test]
+    fn falls_and_lands_on_the_floor() {
+        let world = Grid::floor();
+        let mut body = player_at(0.5, 5.0, 0.5);
+
+        simulate(&mut body, &world, Vector3::new(0.0, 0.0, 0.0), false, 2.0);
+
+        assert_close(body.position.y, 1.0);
+        assert!(body.on_ground);
+        assert_eq!(body.velocity.y, 0.0);
+    }
+
+    #[test]
+    fn fast_fall_does_not_tunnel() {
+        let world = Grid::floor();
+        let mut body = player_at(0.5, 40.0, 0.5);
+        body.velocity.y = -TERMINAL_VELOCITY;
+
+        body.step(&world, input(Vector3::new(0.0, 0.0, 0.0), false), 1.0);
+
+        assert_close(body.position.y, 1.0);
+    }
+
+    #[test]
+    fn wall_blocks_horizontal_movement() {
+        // two blocks high, too tall to step onto
+        let world = Grid::floor().with(2, 1, 0).with(2, 2, 0);
+        let mut body = player_at(0.5, 1.0, 0.5);
+
+        simulate(&mut body, &world, Vector3::new(4.0, 0.0, 0.0), false, 1.0);
+
+        assert_close(body.position.x, 2.0 - PLAYER_HALF_WIDTH);
+        assert_close(body.position.y, 1.0);
+    }
+
+    #[test]
+    fn sliding_along_a_wall_keeps_the_free_axis() {
+        let world = Grid::floor().with(2, 1, 0).with(2, 2, 0).with(2, 1, 1).with(2, 2, 1);
+        let mut body = player_at(0.5, 1.0, 0.5);
+
+        simulate(&mut body, &world, Vector3::new(4.0, 0.0, 1.0), false, 0.5);
+
+        assert_close(body.position.x, 2.0 - PLAYER_HALF_WIDTH);
+        assert!(body.position.z > 0.9);
+    }
+
+    #[test]
+    fn steps_up_onto_a_single_block() {
+        let world = (2..8).fold(Grid::floor(), |grid, x| grid.with(x, 1, 0));
+        let mut body = player_at(0.5, 1.0, 0.5);
+
+        simulate(&mut body, &world, Vector3::new(4.0, 0.0, 0.0), false, 1.0);
+
+        assert!(body.position.x > 2.0);
+        assert_close(body.position.y, 2.0);
+    }
+
+    #[test]
+    fn does_not_step_up_while_airborne() {
+        let world = Grid::floor().with(2, 3, 0);
+        let mut body = player_at(0.5, 3.0, 0.5);
+        body.on_ground = false;
+
+        let start_y = body.position.y;
+        body.move_and_collide(&world, Vector3::new(2.0, 0.0, 0.0));
+
+        assert_close(body.position.x, 2.0 - PLAYER_HALF_WIDTH);
+        assert_close(body.position.y, start_y);
+    }
+
+    #[test]
+    fn step_up_needs_headroom() {
+        // ledge of one block but a ceiling right above it
+        let world = Grid::floor().with(2, 1, 0).with(2, 3, 0).with(1, 3, 0).with(0, 3, 0);
+        let mut body = player_at(0.5, 1.0, 0.5);
+
+        simulate(&mut body, &world, Vector3::new(4.0, 0.0, 0.0), false, 1.0);
+
+        assert_close(body.position.x, 2.0 - PLAYER_HALF_WIDTH);
+        assert_close(body.position.y, 1.0);
+    }
+
+    #[test]
+    fn jumps_only_from_the_ground() {
+        let world = Grid::floor();
+        let mut body = player_at(0.5, 1.0, 0.5);
+        body.on_ground = true;
+
+        body.step(&world, input(Vector3::new(0.0, 0.0, 0.0), true), 1.0 / 60.0);
+        assert!(body.position.y > 1.0);
+        assert!(!body.on_ground);
+
+        // still going up, holding jump in the air doesn't add velocity
+        let velocity = body.velocity.y;
+        body.step(&world, input(Vector3::new(0.0, 0.0, 0.0), true), 1.0 / 60.0);
+        assert!(body.velocity.y < velocity);
+    }
+
+    #[test]
+    fn flying_ignores_gravity_but_collides() {
+        let world = Grid::floor().with(2, 3, 0).with(2, 4, 0);
+        let mut body = player_at(0.5, 3.0, 0.5);
+
+        body.fly(&world, Vector3::new(0.0, 0.0, 0.0), 1.0);
+        assert_close(body.position.y, 3.0);
+
+        body.fly(&world, Vector3::new(4.0, 0.0, 0.0), 1.0);
+        assert_close(body.position.x, 2.0 - PLAYER_HALF_WIDTH);
+
+        body.fly(&world, Vector3::new(0.0, -10.0, 0.0), 1.0);
+        assert_close(body.position.y, 1.0);
+        assert!(body.on_ground);
+    }
+
+    #[test]
+    fn ceiling_stops_a_jump() {
+        let world = Grid::floor().with(0, 3, 0);
+        let mut body = player_at(0.5, 1.0, 0.5);
+        body.on_ground = true;
+
+        simulate(&mut body, &world, Vector3::new(0.0, 0.0, 0.0), true, 0.2);
+
+        assert!(body.aabb().max.y <= 3.0);
+    }
+
+    #[test]
+    fn submersion_counts_wet_layers() {
+        let world = Grid::floor().flooded(1);
+        let mut body = player_at(0.5, 1.0, 0.5);
+
+        body.update_submersion(&world);
+        assert_close(body.submersion, 1.0 / PLAYER_HEIGHT);
+
+        body.position.y = 5.0;
+        body.update_submersion(&world);
+        assert_eq!(body.submersion, 0.0);
+    }
+
+    #[test]
+    fn submersion_counts_each_layer_once_across_columns() {
+        let world = Grid::floor().flooded(2);
+        // en la esquina de cuatro columnas la caja toca 2x2 bloques por capa
+        let mut body = player_at(1.0, 1.5, 1.0);
+
+        body.update_submersion(&world);
+        assert_close(body.submersion, 1.5 / PLAYER_HEIGHT);
+
+        body.position.y = 2.5;
+        body.update_submersion(&world);
+        assert_close(body.submersion, 0.5 / PLAYER_HEIGHT);
+    }
+
+    #[test]
+    fn floats_with_the_head_out_of_water() {
+        let world = Grid::floor().flooded(6);
+        let mut body = player_at(0.5, 2.0, 0.5);
+
+        simulate(&mut body, &world, Vector3::new(0.0, 0.0, 0.0), false, 10.0);
+
+        // la cabeza queda fuera del agua pero el cuerpo sigue mojado
+        assert!(body.aabb().max.y > 7.0);
+        assert!(body.in_liquid());
+        assert!(body.velocity.y.abs() < 0.5);
+    }
+
+    #[test]
+    fn water_slows_a_fall() {
+        let world = Grid::floor().flooded(6);
+        let mut body = player_at(0.5, 6.0, 0.5);
+        body.velocity.y = -20.0;
+
+        simulate(&mut body, &world, Vector3::new(0.0, 0.0, 0.0), false, 0.5);
+
+        assert!(body.velocity.y > -5.0);
+    }
+
+    #[test]
+    fn swimming_is_slower_than_walking() {
+        let world = Grid::floor().flooded(2);
+        let mut body = player_at(0.5, 1.0, 0.5);
+
+        simulate(&mut body, &world, Vector3::new(0.0, 0.0, 4.0), false, 0.5);
+
+        assert!(body.position.z < 0.5 + 4.0 * 0.5 * 0.75);
+    }
+
+    #[test]
+    fn diving_sinks_to_the_bottom() {
+        let world = Grid::floor().flooded(6);
+        let mut body = player_at(0.5, 4.0, 0.5);
+        let dive = MoveInput { wish: Vector3::new(0.0, 0.0, 0.0), jump: false, descend: true };
+
+        for _ in 0..300 {
+            body.step(&world, dive, 1.0 / 60.0);
+        }
+
+        assert_close(body.position.y, 1.0);
+    }
+
+    #[test]
+    fn jumping_against_the_shore_climbs_out() {
+        // agua de 3 bloques de hondo, orilla a la altura del agua desde x = 2
+        let mut world = Grid::floor().flooded(3);
+        for x in 2..8 {
+            for y in 1..=3 {
+                for z in -8..8 {
+                    world = world.with(x, y, z);
+                    world.1.remove(&Vector3::new(x, y, z));
+                }
+            }
+        }
+        let mut body = player_at(0.5, 2.0, 0.5);
+
+        for _ in 0..240 {
+            if body.position.x > 2.5 {
+                break;
+            }
+            body.step(&world, input(Vector3::new(4.0, 0.0, 0.0), true), 1.0 / 60.0);
+        }
+        simulate(&mut body, &world, Vector3::new(0.0, 0.0, 0.0), false, 1.0);
+
+        assert!(body.position.x > 2.5);
+        assert_close(body.position.y, 4.0);
+    }
+}
@@ -10,6 +10,7 @@ pub struct Ray {
     pub length: f32,
 }
 
+#[derive(Copy, Clone, Debug)]
 pub struct BlockHit {
     pub position: Vector3<i32>,
     pub face: Direction,
@@ -0,0 +1,107 @@
+use cgmath::Vector3;
+use crate::profiling::span;
+
+use crate::render::{mesh::Mesh, model::DynamicModel, pipelines::{outline::{create_outline_pipeline, OutlineVertex}, GlobalsLayouts}, renderer::{Draw, Renderer}};
+
+use super::raycast::BlockHit;
+
+
+/// How much the outline grows past the block so it doesn't z-fight with its faces
+const OUTLINE_INFLATE: f32 = 0.002;
+const EDGE_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.6];
+const FACE_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 0.9];
+// 12 aristas, 2 vértices cada una
+const OUTLINE_VERTICES: usize = 24;
+
+
+/// Wireframe cube drawn around the block the player is looking at
+pub struct BlockSelection {
+    pipeline: wgpu::RenderPipeline,
+    model: DynamicModel<OutlineVertex>,
+    visible: bool,
+}
+
+impl BlockSelection {
+    pub fn new(renderer: &Renderer) -> Self {
+        let shader = renderer.device.create_shader_module(
+            wgpu::include_wgsl!("../../assets/shaders/outline.wgsl")
+        );
+
+        let pipeline = create_outline_pipeline(
+            &renderer.device,
+            &GlobalsLayouts::new(&renderer.device),
+            shader,
+            &renderer.config,
+        );
+
+        Self {
+            pipeline,
+            model: DynamicModel::new(&renderer.device, OUTLINE_VERTICES),
+            visible: false,
+        }
+    }
+
+    /// Moves the outline to `hit`, or hides it when nothing is targeted.
+    /// `focus_off` has to be the one used for the current frame's `Globals`.
+    pub fn update(&mut self, queue: &wgpu::Queue, hit: Option<&BlockHit>, focus_off: [i32; 3]) {
+        let _span = span!("update block selection");
+
+        let Some(hit) = hit else {
+            self.visible = false;
+            return;
+        };
+
+        let origin = hit.position - Vector3::new(focus_off[0], focus_off[1], focus_off[2]);
+        self.model.update(queue, &outline_mesh(origin, hit.face.to_vec()), 0);
+        self.visible = true;
+    }
+}
+
+
+/// Edges of the inflated unit cube at `origin`, the edges of the face pointing
+/// along `normal` get a brighter color
+fn outline_mesh(origin: Vector3<i32>, normal: Vector3<i32>) -> Mesh<OutlineVertex> {
+    let corner = |i: usize| -> [i32; 3] { [(i & 1) as i32, ((i >> 1) & 1) as i32, ((i >> 2) & 1) as i32] };
+    let on_face = |c: [i32; 3]| (0..3).any(|axis| normal[axis] != 0 && c[axis] == (normal[axis] > 0) as i32);
+
+    let mut mesh = Mesh::new();
+    for a in 0..8 {
+        for axis in 0..3 {
+            let b = a | 1 << axis;
+            if a == b {
+                continue;
+            }
+
+            let (ca, cb) = (corner(a), corner(b));
+            let color = if on_face(ca) && on_face(cb) { FACE_COLOR } else { EDGE_COLOR };
+            for c in [ca, cb] {
+                let pos = std::array::from_fn(|k| {
+                    let inflate = if c[k] == 1 { OUTLINE_INFLATE } else { -OUTLINE_INFLATE };
+                    (origin[k] + c[k]) as f32 + inflate
+                });
+                mesh.push(OutlineVertex { pos, color });
+            }
+        }
+    }
+
+    let indices: Vec<u16> = (0..mesh.verts.len() as u16).collect();
+    mesh.push_indices(&indices);
+    mesh
+}
+
+
+impl Draw for BlockSelection {
+    fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, globals: &'a wgpu::BindGroup) -> Result<(), wgpu::Error> {
+        if !self.visible {
+            return Ok(());
+        }
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, globals, &[]);
+        render_pass.set_vertex_buffer(0, self.model.vbuf().slice(..));
+        render_pass.set_index_buffer(self.model.ibuf().slice(..), wgpu::IndexFormat::Uint16);
+        render_pass.draw_indexed(0..self.model.num_indices as u32, 0, 0..1);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,18 @@
+use tracy_client::Client;
+
+
+/// Same as `tracy_client::span!`, but does nothing when the Tracy client isn't running
+/// instead of panicking, so the game also runs with `--no-tracy`
+macro_rules! span {
+    ($name: expr) => {
+        tracy_client::Client::running().map(|client| client.span(tracy_client::span_location!($name), 0))
+    };
+}
+pub(crate) use span;
+
+/// Marks the end of a frame for Tracy, if it's running
+pub fn frame_mark() {
+    if let Some(client) = Client::running() {
+        client.frame_mark();
+    }
+}
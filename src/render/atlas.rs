@@ -1,11 +1,13 @@
 use anyhow::*;
 
+use crate::item::tool::{ToolKind, ToolTier};
 use crate::render::texture::*;
 use crate::terrain_gen::block::*;
 
 use super::pipelines::GlobalsLayouts;
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[allow(non_camel_case_types)]
 pub enum MaterialType {
     DIRT,
     GRASS,
@@ -13,9 +15,33 @@ pub enum MaterialType {
     WATER,
     AIR,
     DEBUG,
+    LOG,
+    PLANKS,
+    CRAFTING_TABLE,
 }
 
 impl MaterialType {
+    /// Every block by the name commands use
+    pub const NAMES: [(MaterialType, &'static str); 9] = [
+        (MaterialType::AIR, "air"),
+        (MaterialType::DIRT, "dirt"),
+        (MaterialType::GRASS, "grass"),
+        (MaterialType::ROCK, "rock"),
+        (MaterialType::WATER, "water"),
+        (MaterialType::LOG, "log"),
+        (MaterialType::PLANKS, "planks"),
+        (MaterialType::CRAFTING_TABLE, "crafting_table"),
+        (MaterialType::DEBUG, "debug"),
+    ];
+
+    pub fn name(self) -> &'static str {
+        Self::NAMES.iter().find(|(material, _)| *material == self).map_or("unknown", |(_, name)| *name)
+    }
+
+    pub fn from_name(name: &str) -> Option<MaterialType> {
+        Self::NAMES.iter().find(|(_, n)| *n == name).map(|(material, _)| *material)
+    }
+
     pub fn is_transparent(&self) -> bool {
         match self {
             MaterialType::AIR => true, // Devuelve true si es AIR
@@ -23,47 +49,77 @@ impl MaterialType {
             _ => false, // Devuelve false para cualquier otro material
         }
     }
+
+    /// How hard the block is to break, `None` if it can't be broken
+    pub fn hardness(&self) -> Option<f32> {
+        match self {
+            MaterialType::DIRT => Some(0.5),
+            MaterialType::GRASS => Some(0.6),
+            MaterialType::ROCK => Some(1.5),
+            MaterialType::LOG | MaterialType::PLANKS => Some(2.0),
+            MaterialType::CRAFTING_TABLE => Some(2.5),
+            MaterialType::WATER | MaterialType::AIR | MaterialType::DEBUG => None,
+        }
+    }
+
+    /// Tool that breaks the block faster
+    pub fn preferred_tool(&self) -> Option<ToolKind> {
+        match self {
+            MaterialType::DIRT | MaterialType::GRASS => Some(ToolKind::Shovel),
+            MaterialType::ROCK => Some(ToolKind::Pickaxe),
+            MaterialType::LOG | MaterialType::PLANKS | MaterialType::CRAFTING_TABLE => Some(ToolKind::Axe),
+            MaterialType::WATER | MaterialType::AIR | MaterialType::DEBUG => None,
+        }
+    }
+
+    /// Worst tier of the preferred tool that gets a drop, `None` if the hand is enough
+    pub fn required_tier(&self) -> Option<ToolTier> {
+        match self {
+            MaterialType::ROCK => Some(ToolTier::Wood),
+            _ => None,
+        }
+    }
 }
 
 
 
 
 impl MaterialType {
-    pub fn get_texture_coordinates(&self, texture_corner: [u32; 2], quad_side: Direction) -> [f32; 2] {
+    /// Index of the atlas tile used for the given face of the block.
+    pub fn get_texture_tile(&self, quad_side: Direction) -> u32 {
         match self {
             MaterialType::GRASS => match quad_side {
-                Direction::TOP => atlas_pos_to_coordinates([0.0, 0.0], texture_corner),
-                Direction::BOTTOM => atlas_pos_to_coordinates([2.0, 0.0], texture_corner),
-                Direction::RIGHT => atlas_pos_to_coordinates([3.0, 0.0], texture_corner),
-                Direction::LEFT => atlas_pos_to_coordinates([3.0, 0.0], texture_corner),
-                Direction::FRONT => atlas_pos_to_coordinates([3.0, 0.0], texture_corner),
-                Direction::BACK => atlas_pos_to_coordinates([3.0, 0.0], texture_corner),
+                Direction::TOP => atlas_tile([0, 0]),
+                Direction::BOTTOM => atlas_tile([2, 0]),
+                Direction::RIGHT => atlas_tile([3, 0]),
+                Direction::LEFT => atlas_tile([3, 0]),
+                Direction::FRONT => atlas_tile([3, 0]),
+                Direction::BACK => atlas_tile([3, 0]),
+            },
+            MaterialType::DIRT => atlas_tile([2, 0]),
+            MaterialType::ROCK => atlas_tile([1, 0]),
+            MaterialType::WATER => atlas_tile([13, 0]),
+            MaterialType::AIR => 0,
+            MaterialType::DEBUG => atlas_tile([15, 3]),
+            MaterialType::LOG => match quad_side {
+                Direction::TOP | Direction::BOTTOM => atlas_tile([5, 1]),
+                _ => atlas_tile([4, 1]),
+            },
+            MaterialType::PLANKS => atlas_tile([4, 0]),
+            // el atlas no tiene textura de mesa, se parece a la madera
+            MaterialType::CRAFTING_TABLE => match quad_side {
+                Direction::TOP => atlas_tile([5, 1]),
+                _ => atlas_tile([4, 0]),
             },
-            MaterialType::DIRT => atlas_pos_to_coordinates([2.0, 0.0], texture_corner),
-            MaterialType::ROCK => atlas_pos_to_coordinates([1.0, 0.0], texture_corner),
-            MaterialType::WATER => atlas_pos_to_coordinates([13.0, 0.0], texture_corner),
-            MaterialType::AIR => [0.0, 0.0],
-            MaterialType::DEBUG => atlas_pos_to_coordinates([15.0, 3.0], texture_corner),
         }
     }
 }
 
-const BLOCK_PIXEL_SIZE: f32 = 16.0;
-const ATLAS_PIXEL_SIZE: f32 = 256.0;
-
-fn atlas_pos_to_coordinates(atlas_pos: [f32; 2], texture_corner: [u32; 2]) -> [f32; 2] {
-    let mut pixel_x = atlas_pos[0] * BLOCK_PIXEL_SIZE;
-    let mut pixel_y = atlas_pos[1] * BLOCK_PIXEL_SIZE;
-
-    if texture_corner[0] == 1 {
-        pixel_x += 15.0;
-    }
-
-    if texture_corner[1] == 1 {
-        pixel_y += 16.0;
-    }
+// El atlas tiene 16x16 texturas de 16 pixeles, los shaders calculan las uv a partir del indice
+pub const ATLAS_TILES_PER_ROW: u32 = 16;
 
-    return [pixel_x / ATLAS_PIXEL_SIZE, pixel_y / ATLAS_PIXEL_SIZE];
+fn atlas_tile(atlas_pos: [u32; 2]) -> u32 {
+    atlas_pos[1] * ATLAS_TILES_PER_ROW + atlas_pos[0]
 }
 
 pub struct Atlas {
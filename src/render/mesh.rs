@@ -21,8 +21,8 @@ impl<V: Vertex> Mesh<V>
     /// Create a new `Mesh`.
     pub fn new() -> Self { Self { verts: Vec::new(), indices: Vec::new() } }
 
-    /// Clear vertices, allows reusing allocated memory of the underlying Vec.
-    pub fn clear(&mut self) { self.verts.clear(); }
+    /// Clear vertices and indices, allows reusing allocated memory of the underlying Vecs.
+    pub fn clear(&mut self) { self.verts.clear(); self.indices.clear(); }
 
 
     /// Get a slice referencing the vertices of this mesh.
@@ -36,6 +36,8 @@ pub struct DynamicModel<V: Vertex> {
     vbuf: DynamicBuffer<V>,
     ibuf: DynamicBuffer<u16>,
     pub num_indices: u16,
+    /// Vertices of the last mesh uploaded, the buffer can hold more
+    pub num_vertices: usize,
 }
 
 impl<V: Vertex> DynamicModel<V> {
@@ -44,6 +46,7 @@ impl<V: Vertex> DynamicModel<V> {
             vbuf: DynamicBuffer::new(device, size, wgpu::BufferUsages::VERTEX),
             ibuf: DynamicBuffer::new(device,  size, wgpu::BufferUsages::INDEX),
             num_indices: 0,
+            num_vertices: 0,
         }
     }
 
@@ -51,6 +54,7 @@ impl<V: Vertex> DynamicModel<V> {
         self.vbuf.update(queue, mesh.vertices(), offset);
         self.ibuf.update(queue, mesh.indices(), offset);
         self.num_indices = mesh.indices().len() as u16;
+        self.num_vertices = mesh.vertices().len();
     }
 
     pub fn vbuf(&self) -> &wgpu::Buffer { &self.vbuf.buff }
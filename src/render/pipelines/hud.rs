@@ -10,11 +10,14 @@ use crate::render::{texture::Texture, Vertex};
 pub struct HUDVertex {
     pub position: [f32; 2],  // Posición en espacio de pantalla (coordenadas normalizadas)
     pub uv: [f32; 2],        // Coordenadas de textura
+    pub color: [f32; 4],     // Se multiplica por la textura, blanco la deja igual
 }
 
 impl HUDVertex {
-    const ATTRIBS: [wgpu::VertexAttribute; 2] =
-        wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x2];
+    pub const WHITE: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+    const ATTRIBS: [wgpu::VertexAttribute; 3] =
+        wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x2, 2 => Float32x4];
 }
 
 impl Vertex for HUDVertex {
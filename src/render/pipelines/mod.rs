@@ -1,12 +1,15 @@
 
 pub mod terrain;
 pub mod hud;
+pub mod outline;
+pub mod crack;
 
 use bytemuck::{Pod, Zeroable};
 use cgmath::{Matrix4, SquareMatrix};
 use wgpu::BindGroup;
 
 use super::{consts::Consts, texture::Texture};
+use terrain::TerrainLocals;
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Zeroable, Pod)]
@@ -14,6 +17,17 @@ pub struct Globals {
     /// Transformation from world coordinate space (with focus_off as the
     /// origin) to the camera space
     view_proj: [[f32; 4]; 4],
+    /// Integer world position (in blocks) used as the origin for rendering,
+    /// keeps vertex positions small so they stay precise far from the world origin.
+    focus_off: [i32; 4],
+    /// Camera position relative to `focus_off`
+    cam_pos: [f32; 4],
+    /// Color the terrain fades into, matches the clear color
+    fog_color: [f32; 4],
+    /// x: distance where the fog starts, y: distance where everything is fog
+    fog_range: [f32; 4],
+    /// x: daylight the terrain is multiplied by
+    light: [f32; 4],
 
 }
 
@@ -21,10 +35,20 @@ impl Globals {
     /// Create global consts from the provided parameters.
     #[allow(clippy::too_many_arguments)]
     pub fn new(
-        view_proj: [[f32; 4]; 4]
+        view_proj: [[f32; 4]; 4],
+        focus_off: [i32; 3],
+        cam_pos: [f32; 3],
+        fog_color: [f32; 3],
+        fog_range: [f32; 2],
+        daylight: f32,
     ) -> Self {
         Self {
-            view_proj
+            view_proj,
+            focus_off: [focus_off[0], focus_off[1], focus_off[2], 0],
+            cam_pos: [cam_pos[0], cam_pos[1], cam_pos[2], 0.0],
+            fog_color: [fog_color[0], fog_color[1], fog_color[2], 1.0],
+            fog_range: [fog_range[0], fog_range[1], 0.0, 0.0],
+            light: [daylight, 0.0, 0.0, 0.0],
 
         }
     }
@@ -34,6 +58,12 @@ impl Default for Globals {
     fn default() -> Self {
         Self::new(
             Matrix4::identity().into(),
+            [0, 0, 0],
+            [0.0, 0.0, 0.0],
+            [0.0, 0.0, 0.0],
+            // sin niebla; smoothstep no está definido si el inicio no es menor que el final
+            [f32::MAX / 2.0, f32::MAX],
+            1.0,
 
         )
     }
@@ -51,6 +81,7 @@ pub struct GlobalsLayouts {
     pub globals: wgpu::BindGroupLayout,
     pub atlas_layout: wgpu::BindGroupLayout,
     pub hud_layout: wgpu::BindGroupLayout,
+    pub terrain_locals: wgpu::BindGroupLayout,
 
 }
 
@@ -124,10 +155,28 @@ impl GlobalsLayouts {
             label: Some("hud_bind_group_layout"),
         });
 
+        // Datos por chunk (origen del chunk en el mundo)
+        let terrain_locals = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+            label: Some("terrain_locals_layout"),
+        });
+
         Self {
             globals,
             atlas_layout,
             hud_layout, // Añadimos el nuevo layout
+            terrain_locals,
         }
     }
 
@@ -181,6 +230,23 @@ impl GlobalsLayouts {
     }
 
 
+    pub fn bind_terrain_locals(
+        &self,
+        device: &wgpu::Device,
+        locals: &Consts<TerrainLocals>,
+    ) -> BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("terrain_locals_bind_group"),
+            layout: &self.terrain_locals,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: locals.buf().as_entire_binding(),
+                },
+            ],
+        })
+    }
+
     // Nueva función para crear bind groups de HUD
     pub fn bind_hud_texture(
         &self,
@@ -1,22 +1,143 @@
-use wgpu::RenderPipeline;
+use wgpu::{BindGroup, RenderPipeline};
 
 use super::GlobalsLayouts;
 
 
-use crate::render::{texture::Texture, Vertex};
+use crate::{render::{consts::Consts, renderer::Renderer, texture::Texture, Vertex}, terrain_gen::chunk::{CHUNK_AREA, CHUNK_Y_SIZE}};
 
+/// Packed terrain vertex.
+///
+/// `data` layout (from the least significant bit):
+/// - bits 0..5   x, chunk-local (0..=16)
+/// - bits 5..12  y, chunk-local (0..=127)
+/// - bits 12..17 z, chunk-local (0..=16)
+/// - bits 17..20 face index (see `Direction::index`)
+/// - bits 20..22 uv corner (u | v << 1)
+/// - bits 22..24 ambient occlusion (0 = fully occluded, 3 = open)
+/// - bits 24..28 light level (0..=15)
+///
+/// `tile` is the index of the texture inside the atlas (row-major, 16 tiles per row).
+/// World position is recovered in the shader from the chunk origin in `TerrainLocals`.
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct BlockVertex {
-    pub pos: [f32; 3],
-    pub texture_coordinates: [f32; 2],
+    pub data: u32,
+    pub tile: u32,
 }
 
+const POS_XZ_MASK: u32 = 0x1F;
+const POS_Y_MASK: u32 = 0x7F;
+const FACE_MASK: u32 = 0x7;
+const CORNER_MASK: u32 = 0x3;
+const AO_MASK: u32 = 0x3;
+const LIGHT_MASK: u32 = 0xF;
+
+const Y_SHIFT: u32 = 5;
+const Z_SHIFT: u32 = 12;
+const FACE_SHIFT: u32 = 17;
+const CORNER_SHIFT: u32 = 20;
+const AO_SHIFT: u32 = 22;
+const LIGHT_SHIFT: u32 = 24;
+
+pub const MAX_AO: u8 = 3;
+pub const MAX_LIGHT: u8 = 15;
+
+// las caras superiores llegan a y = CHUNK_Y_SIZE y los bordes a x, z = CHUNK_AREA
+const _: () = assert!(CHUNK_Y_SIZE as u32 <= POS_Y_MASK, "CHUNK_Y_SIZE doesn't fit in the packed vertex");
+const _: () = assert!(CHUNK_AREA as u32 <= POS_XZ_MASK, "CHUNK_AREA doesn't fit in the packed vertex");
+const _: () = assert!(MAX_AO as u32 <= AO_MASK && MAX_LIGHT as u32 <= LIGHT_MASK);
+
 impl BlockVertex {
 
     const ATTRIBS: [wgpu::VertexAttribute; 2] =
-        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x2];
+        wgpu::vertex_attr_array![0 => Uint32, 1 => Uint32];
+
+    pub fn new(pos: [u32; 3], face: u8, tile: u32, corner: [u32; 2], ao: u8, light: u8) -> Self {
+        // x y z no se comprueban: en los bloques de relleno (x o z == -1) dan la vuelta, pero nunca se dibujan
+        debug_assert!(
+            pos[1] <= POS_Y_MASK && face as u32 <= FACE_MASK && corner[0] <= 1 && corner[1] <= 1 && ao <= MAX_AO && light <= MAX_LIGHT,
+            "vertex field out of range: {:?} face {} corner {:?} ao {} light {}", pos, face, corner, ao, light,
+        );
+        let data = (pos[0] & POS_XZ_MASK)
+            | (pos[1] & POS_Y_MASK) << Y_SHIFT
+            | (pos[2] & POS_XZ_MASK) << Z_SHIFT
+            | (face as u32 & FACE_MASK) << FACE_SHIFT
+            | ((corner[0] | corner[1] << 1) & CORNER_MASK) << CORNER_SHIFT
+            | (ao as u32 & AO_MASK) << AO_SHIFT
+            | (light as u32 & LIGHT_MASK) << LIGHT_SHIFT;
+
+        Self { data, tile }
+    }
+
+    /// Chunk-local position of the vertex.
+    pub fn position(&self) -> [u32; 3] {
+        [
+            self.data & POS_XZ_MASK,
+            (self.data >> Y_SHIFT) & POS_Y_MASK,
+            (self.data >> Z_SHIFT) & POS_XZ_MASK,
+        ]
+    }
+
+    pub fn face(&self) -> u8 {
+        ((self.data >> FACE_SHIFT) & FACE_MASK) as u8
+    }
+
+    /// Corner of the texture tile, `[u, v]`
+    pub fn corner(&self) -> [u32; 2] {
+        let corner = (self.data >> CORNER_SHIFT) & CORNER_MASK;
+        [corner & 1, corner >> 1]
+    }
+
+    pub fn ao(&self) -> u8 {
+        ((self.data >> AO_SHIFT) & AO_MASK) as u8
+    }
+
+    pub fn light(&self) -> u8 {
+        ((self.data >> LIGHT_SHIFT) & LIGHT_MASK) as u8
+    }
+
+    pub fn with_ao(self, ao: u8) -> Self {
+        Self {
+            data: (self.data & !(AO_MASK << AO_SHIFT)) | (ao as u32 & AO_MASK) << AO_SHIFT,
+            tile: self.tile,
+        }
+    }
+}
 
+/// Per-draw terrain data: the world-space origin (in blocks) of the chunk being drawn
+/// and the horizontal size of a mesh cell in blocks (1 for full detail, 2^n for LOD meshes).
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct TerrainLocals {
+    origin: [i32; 4],
+}
+
+impl TerrainLocals {
+    pub fn new(origin: [i32; 3], scale: i32) -> Self {
+        Self {
+            origin: [origin[0], origin[1], origin[2], scale],
+        }
+    }
+}
+
+/// Uniform buffer with its bind group, one per chunk slot.
+pub struct ChunkLocals {
+    pub consts: Consts<TerrainLocals>,
+    pub bind_group: BindGroup,
+}
+
+impl ChunkLocals {
+    pub fn new(renderer: &Renderer, layouts: &GlobalsLayouts, locals: TerrainLocals) -> Self {
+        let mut consts = Consts::new(&renderer.device, 1);
+        consts.update(&renderer.queue, &[locals], 0);
+        let bind_group = layouts.bind_terrain_locals(&renderer.device, &consts);
+
+        Self { consts, bind_group }
+    }
+
+    pub fn update(&mut self, queue: &wgpu::Queue, locals: TerrainLocals) {
+        self.consts.update(queue, &[locals], 0);
+    }
 }
 
 impl Vertex for BlockVertex {
@@ -46,7 +167,7 @@ pub fn create_terrain_pipeline(
         bind_group_layouts: &[
             &global_layout.atlas_layout,
             &global_layout.globals,
-            // pendiente agregar layout con algo relacionado del terreno
+            &global_layout.terrain_locals,
         ],
         push_constant_ranges: &[],
     });
@@ -101,3 +222,31 @@ pub fn create_terrain_pipeline(
     pipeline
 
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields_round_trip_at_their_limits() {
+        let top = [CHUNK_AREA as u32, CHUNK_Y_SIZE as u32, CHUNK_AREA as u32];
+        let vertex = BlockVertex::new(top, 5, 255, [1, 1], MAX_AO, MAX_LIGHT);
+        assert_eq!(vertex.position(), top);
+        assert_eq!(vertex.face(), 5);
+        assert_eq!(vertex.corner(), [1, 1]);
+        assert_eq!(vertex.ao(), MAX_AO);
+        assert_eq!(vertex.light(), MAX_LIGHT);
+        assert_eq!(vertex.tile, 255);
+
+        // los campos no se pisan entre ellos
+        let max = BlockVertex::new([POS_XZ_MASK, POS_Y_MASK, POS_XZ_MASK], FACE_MASK as u8, 0, [1, 1], MAX_AO, MAX_LIGHT);
+        assert_eq!(max.data, (1 << 28) - 1);
+        let zero = BlockVertex::new([0, 0, 0], 0, 0, [0, 1], 0, 0);
+        assert_eq!((zero.position(), zero.face(), zero.corner(), zero.ao(), zero.light()), ([0, 0, 0], 0, [0, 1], 0, 0));
+
+        let shaded = vertex.with_ao(1);
+        assert_eq!(shaded.ao(), 1);
+        assert_eq!((shaded.position(), shaded.light()), (top, MAX_LIGHT));
+    }
+}
@@ -1,13 +1,20 @@
 use std::time::Duration;
 
-use tracy_client::span;
+use crate::profiling::span;
 use wgpu::{BindGroup, CommandEncoder, Error};
 use instant::Instant;
 use winit::window::Window as SysWindow;
 
 
-use crate::{hud::HUD, terrain_gen::generator::TerrainGen};
+use crate::{hud::{debug::DebugStats, HUD}, player::{crack::CrackOverlay, selection::BlockSelection}, terrain_gen::generator::TerrainGen};
 use super::{consts::Consts, pipelines::{GlobalModel, GlobalsLayouts}, texture::{self, Texture}};
+/// Sky color, also used as the distance fog color
+pub const SKY_COLOR: [f32; 3] = [0.1, 0.2, 0.3];
+/// Fog color when the camera is under water
+pub const WATER_FOG_COLOR: [f32; 3] = [0.02, 0.08, 0.2];
+/// Weight of the newest frame in the smoothed frame time
+const FRAME_TIME_SMOOTHING: f32 = 0.1;
+
 pub trait Draw {
     fn draw<'a>(
         &'a self, 
@@ -31,11 +38,19 @@ pub struct Renderer<'a> {
     pub queue: wgpu::Queue,
     pub layouts: Layouts,
     depth_texture: Texture,
+    clear_color: [f32; 3],
+    /// When the last frame was presented
+    last_present: Instant,
+    /// Seconds between presents, smoothed so the debug overlay is readable
+    frame_time: f32,
+    /// Present modes the surface supports
+    present_modes: Vec<wgpu::PresentMode>,
 }
 
 impl<'a> Renderer<'a> {
     pub fn new(
         window: &'a SysWindow,
+        vsync: bool,
     ) -> Self {
         let size = window.inner_size();
     
@@ -94,7 +109,7 @@ impl<'a> Renderer<'a> {
             format: surface_format,
             width: size.width,
             height: size.height,
-            present_mode: wgpu::PresentMode::Fifo,
+            present_mode: present_mode(&surface_caps.present_modes, vsync),
             alpha_mode: surface_caps.alpha_modes[0],
             desired_maximum_frame_latency: 2,
             view_formats: vec![],
@@ -117,6 +132,10 @@ impl<'a> Renderer<'a> {
             window,
             layouts,
             depth_texture,
+            clear_color: SKY_COLOR,
+            last_present: Instant::now(),
+            frame_time: 0.0,
+            present_modes: surface_caps.present_modes,
         }
     }
 
@@ -130,6 +149,12 @@ impl<'a> Renderer<'a> {
         }
     }
 
+    /// See `present_mode`
+    pub fn set_vsync(&mut self, vsync: bool) {
+        self.config.present_mode = present_mode(&self.present_modes, vsync);
+        self.surface.configure(&self.device, &self.config);
+    }
+
     pub fn bind_globals(
         &self,
         global_model: &GlobalModel,
@@ -143,6 +168,17 @@ impl<'a> Renderer<'a> {
         //todo!();
     }
 
+    /// Frame time and surface size
+    pub fn fill_debug_stats(&self, stats: &mut DebugStats) {
+        stats.frame_time = self.frame_time;
+        stats.resolution = (self.config.width, self.config.height);
+    }
+
+    /// Color the frame is cleared with, should match the fog color
+    pub fn set_clear_color(&mut self, color: [f32; 3]) {
+        self.clear_color = color;
+    }
+
     pub fn create_consts<T: Copy + bytemuck::Pod>(
         &mut self,
         vals: &[T],
@@ -159,7 +195,7 @@ impl<'a> Renderer<'a> {
         consts.update(&self.queue, vals, 0)
     }
 
-    pub fn render(&mut self, terrain: &TerrainGen, hud: &HUD, globals: &BindGroup) -> Result<(), wgpu::SurfaceError> {
+    pub fn render(&mut self, terrain: Option<&TerrainGen>, selection: &BlockSelection, crack: &CrackOverlay, hud: &HUD, globals: &BindGroup) -> Result<(), wgpu::SurfaceError> {
 
 
 
@@ -191,9 +227,9 @@ impl<'a> Renderer<'a> {
                         resolve_target: None,
                         ops: wgpu::Operations {
                             load: wgpu::LoadOp::Clear(wgpu::Color {
-                                r: 0.1,
-                                g: 0.2,
-                                b: 0.3,
+                                r: self.clear_color[0] as f64,
+                                g: self.clear_color[1] as f64,
+                                b: self.clear_color[2] as f64,
                                 a: 1.0,
                             }),
                             store: wgpu::StoreOp::Store,
@@ -213,7 +249,14 @@ impl<'a> Renderer<'a> {
             );
             drop(create_render_pass);
 
-            terrain.draw(&mut _render_pass, globals).unwrap();
+            // en los menús sin mundo solo se dibuja el HUD
+            if let Some(terrain) = terrain {
+                terrain.draw(&mut _render_pass, globals).unwrap();
+
+                selection.draw(&mut _render_pass, globals).unwrap();
+
+                crack.draw(&mut _render_pass, globals).unwrap();
+            }
             
             hud.draw(&mut _render_pass, globals).unwrap();
 
@@ -228,6 +271,11 @@ impl<'a> Renderer<'a> {
         let prenset_output = span!("present output");
         output.present();
         drop(prenset_output);
+
+        let now = Instant::now();
+        let elapsed = (now - self.last_present).as_secs_f32();
+        self.last_present = now;
+        self.frame_time = if self.frame_time == 0.0 { elapsed } else { self.frame_time + (elapsed - self.frame_time) * FRAME_TIME_SMOOTHING };
     
         Ok(())
     }
@@ -235,3 +283,15 @@ impl<'a> Renderer<'a> {
 }
 
 
+/// Fifo waits for the display refresh. Without vsync Mailbox shows the newest frame
+/// without tearing, Immediate tears, and Fifo is left if the surface has neither.
+fn present_mode(supported: &[wgpu::PresentMode], vsync: bool) -> wgpu::PresentMode {
+    let wanted: &[wgpu::PresentMode] = if vsync {
+        &[wgpu::PresentMode::Fifo]
+    } else {
+        &[wgpu::PresentMode::Mailbox, wgpu::PresentMode::Immediate]
+    };
+    let mode = wanted.iter().copied().find(|mode| supported.contains(mode)).unwrap_or(wgpu::PresentMode::Fifo);
+    log::info!("Present mode {:?}", mode);
+    mode
+}
@@ -1,11 +1,33 @@
 use serde::{Serialize, Deserialize};
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use serde_json; 
 
-#[derive(Serialize, Deserialize)]
+use crate::{hud::layout::GUI_SCALE_AUTO, input::InputMap};
+
+/// Name of the settings file, inside the config folder of the game
+pub const SETTINGS_FILE: &str = "settings.json";
+/// Folder of the game inside the config folder of the platform
+const APP_DIR: &str = "wgpucraft";
+/// Version of the settings file written by this build. Files without a version are 0.
+pub const SETTINGS_VERSION: u32 = 1;
+
+/// Fields missing from the file keep their default value, unknown ones are ignored
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Settings {
+    /// Layout of the file, to upgrade the ones written by older builds
+    #[serde(default)]
+    pub schema_version: u32,
     pub graphics: GraphicsSettings,
+    /// Key and mouse bindings of every action
+    #[serde(default)]
+    pub controls: InputMap,
+    /// Camera turn speed
+    #[serde(default = "default_mouse_sensitivity")]
+    pub mouse_sensitivity: f32,
     //pub audio: AudioSettings,
 }
 
@@ -15,16 +37,44 @@ pub struct Settings {
 impl Default for Settings {
     fn default() -> Self {
         Self {
+            schema_version: SETTINGS_VERSION,
             graphics: GraphicsSettings::default(),
+            controls: InputMap::default(),
+            mouse_sensitivity: default_mouse_sensitivity(),
             //audio: AudioSettings::default(),
         }
     }
 }
 
-#[derive(Serialize, Deserialize)]
+pub const MIN_RENDER_DISTANCE: usize = 2;
+pub const MAX_RENDER_DISTANCE: usize = 16;
+pub const MIN_FOV: f32 = 30.0;
+pub const MAX_FOV: f32 = 110.0;
+pub const MIN_MOUSE_SENSITIVITY: f32 = 0.1;
+pub const MAX_MOUSE_SENSITIVITY: f32 = 5.0;
+/// `max_fps` setting that draws frames as fast as they come
+pub const UNLIMITED_FPS: u32 = 0;
+
+fn default_mouse_sensitivity() -> f32 {
+    2.1
+}
+
+/// Fields missing from the file keep their default value
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct GraphicsSettings {
     pub resolution: (u16, u16),
     pub fullscreen: bool,
+    /// Chunks from the player to the edge of the full detail terrain
+    pub render_distance: usize,
+    /// Pixels per HUD unit, `GUI_SCALE_AUTO` picks the biggest that fits the window
+    pub gui_scale: u32,
+    /// Vertical field of view in degrees
+    pub fov: f32,
+    /// Waits for the display refresh to present each frame
+    pub vsync: bool,
+    /// Frames drawn per second at most, `UNLIMITED_FPS` for no cap
+    pub max_fps: u32,
 }
 
 
@@ -33,10 +83,22 @@ impl Default for GraphicsSettings {
         Self {
             resolution: (1920, 1080), // Default resolution
             fullscreen: false, // Default fullscreen setting
+            render_distance: 8, // Default render distance (16x16 chunks)
+            gui_scale: GUI_SCALE_AUTO,
+            fov: 45.0,
+            vsync: true,
+            max_fps: 60,
         }
     }
 }
 
+impl GraphicsSettings {
+    /// Shortest time between two frames, `None` without a cap
+    pub fn frame_time(&self) -> Option<Duration> {
+        (self.max_fps != UNLIMITED_FPS).then(|| Duration::from_secs_f64(1.0 / self.max_fps as f64))
+    }
+}
+
 // #[derive(Serialize, Deserialize)]
 // pub struct AudioSettings {
 //     pub volume: u8,
@@ -53,18 +115,181 @@ impl Default for GraphicsSettings {
 // }
 
 impl Settings {
-    pub fn load_from_file(file_path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn load_from_file(file_path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
         let mut file = File::open(file_path)?;
         let mut contents = String::new();
         file.read_to_string(&mut contents)?;
-        let settings: Settings = serde_json::from_str(&contents)?;
+        let mut settings: Settings = serde_json::from_str(&contents)?;
+        settings.upgrade();
         Ok(settings)
     }
 
-    pub fn save_to_file(&self, file_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    /// The saved settings. If there are none the defaults are written to `file_path`, if
+    /// they can't be read the defaults are used and the file is moved to `backup_path`,
+    /// so saving the defaults later doesn't lose it.
+    pub fn load_or_create(file_path: &Path) -> Self {
+        if !file_path.exists() {
+            let settings = Self::default();
+            match settings.save_to_file(file_path) {
+                Ok(()) => log::info!("Created default settings in {}", file_path.display()),
+                Err(e) => log::warn!("Couldn't write default settings to {}: {}", file_path.display(), e),
+            }
+            return settings;
+        }
+
+        match Self::load_from_file(file_path) {
+            Ok(settings) => {
+                log::info!("Loaded settings from {}", file_path.display());
+                settings
+            }
+            Err(e) => {
+                log::warn!("Using default settings, couldn't load {}: {}", file_path.display(), e);
+                let backup = backup_path(file_path);
+                match fs::rename(file_path, &backup) {
+                    Ok(()) => log::warn!("The settings that couldn't be loaded were moved to {}", backup.display()),
+                    Err(e) => log::error!("Couldn't move {} to {}: {}", file_path.display(), backup.display(), e),
+                }
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save_to_file(&self, file_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(dir) = file_path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
         let serialized = serde_json::to_string_pretty(self)?;
         let mut file = File::create(file_path)?;
         file.write_all(serialized.as_bytes())?;
         Ok(())
     }
+
+    /// Brings settings of older files to the current version and puts values edited
+    /// by hand back in range
+    fn upgrade(&mut self) {
+        if self.schema_version > SETTINGS_VERSION {
+            log::warn!("Settings file version {} is newer than {}, some of it may be ignored", self.schema_version, SETTINGS_VERSION);
+        }
+        // la versión 0 no tenía número, los campos nuevos ya tienen sus valores por defecto
+        self.schema_version = SETTINGS_VERSION;
+
+        let graphics = &mut self.graphics;
+        graphics.render_distance = graphics.render_distance.clamp(MIN_RENDER_DISTANCE, MAX_RENDER_DISTANCE);
+        graphics.fov = graphics.fov.clamp(MIN_FOV, MAX_FOV);
+        graphics.resolution = (graphics.resolution.0.max(1), graphics.resolution.1.max(1));
+        self.mouse_sensitivity = self.mouse_sensitivity.clamp(MIN_MOUSE_SENSITIVITY, MAX_MOUSE_SENSITIVITY);
+    }
+}
+
+
+/// Values of the settings file replaced for this run only, by the command line. Saving
+/// puts them back, unless the player changed the setting in the game.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Overrides {
+    pub render_distance: Option<usize>,
+    pub fullscreen: Option<bool>,
+}
+
+impl Overrides {
+    /// What to write to the file: `session` with the values of the file that are still overridden
+    pub fn file_settings(&self, session: &Settings) -> Settings {
+        let mut settings = session.clone();
+        if let Some(render_distance) = self.render_distance {
+            settings.graphics.render_distance = render_distance;
+        }
+        if let Some(fullscreen) = self.fullscreen {
+            settings.graphics.fullscreen = fullscreen;
+        }
+        settings
+    }
+}
+
+
+/// Where a settings file that couldn't be loaded is kept, `settings.json.bak`
+pub fn backup_path(file_path: &Path) -> PathBuf {
+    let mut name = file_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".bak");
+    file_path.with_file_name(name)
+}
+
+/// Where the settings are kept: the config folder of the platform, or the working
+/// directory if there is none
+pub fn settings_path() -> PathBuf {
+    config_dir().map_or_else(|| PathBuf::from(SETTINGS_FILE), |dir| dir.join(APP_DIR).join(SETTINGS_FILE))
+}
+
+/// `%APPDATA%` on Windows, `~/Library/Application Support` on macOS and
+/// `$XDG_CONFIG_HOME` or `~/.config` elsewhere
+fn config_dir() -> Option<PathBuf> {
+    let absolute = |var: &str| std::env::var_os(var).map(PathBuf::from).filter(|path| path.is_absolute());
+    if cfg!(target_os = "windows") {
+        absolute("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        absolute("HOME").map(|home| home.join("Library").join("Application Support"))
+    } else {
+        absolute("XDG_CONFIG_HOME").or_else(|| absolute("HOME").map(|home| home.join(".config")))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_and_unknown_fields() {
+        let json = r#"{ "graphics": { "fov": 70.0, "shadows": true }, "volume": 3, "controls": { "dance": [] } }"#;
+        let settings: Settings = serde_json::from_str(json).unwrap();
+        assert_eq!(settings.schema_version, 0);
+        assert_eq!(settings.graphics.fov, 70.0);
+        assert_eq!(settings.graphics.max_fps, 60);
+        assert!(settings.graphics.vsync);
+        assert_eq!(settings.mouse_sensitivity, default_mouse_sensitivity());
+    }
+
+    #[test]
+    fn upgrades_old_files() {
+        let json = r#"{ "graphics": { "render_distance": 100, "resolution": [0, 720] }, "mouse_sensitivity": 50.0 }"#;
+        let mut settings: Settings = serde_json::from_str(json).unwrap();
+        settings.upgrade();
+        assert_eq!(settings.schema_version, SETTINGS_VERSION);
+        assert_eq!(settings.graphics.render_distance, MAX_RENDER_DISTANCE);
+        assert_eq!(settings.graphics.resolution, (1, 720));
+        assert_eq!(settings.mouse_sensitivity, MAX_MOUSE_SENSITIVITY);
+    }
+
+    #[test]
+    fn creates_defaults_when_missing() {
+        let dir = std::env::temp_dir().join(format!("wgpucraft-settings-{}", std::process::id()));
+        let path = dir.join("nested").join(SETTINGS_FILE);
+        let _ = fs::remove_dir_all(&dir);
+
+        let mut settings = Settings::load_or_create(&path);
+        assert!(path.is_file());
+        settings.graphics.max_fps = UNLIMITED_FPS;
+        settings.save_to_file(&path).unwrap();
+
+        let loaded = Settings::load_or_create(&path);
+        assert_eq!(loaded.graphics.frame_time(), None);
+        assert_eq!(loaded.schema_version, SETTINGS_VERSION);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn keeps_files_that_fail_to_load() {
+        let dir = std::env::temp_dir().join(format!("wgpucraft-broken-settings-{}", std::process::id()));
+        let path = dir.join(SETTINGS_FILE);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let broken = r#"{ "graphics": { "fov": 70.0, } }"#;
+        fs::write(&path, broken).unwrap();
+
+        let settings = Settings::load_or_create(&path);
+        assert_eq!(settings.graphics.fov, GraphicsSettings::default().fov);
+        // guardar los valores por defecto no pisa el archivo del jugador
+        settings.save_to_file(&path).unwrap();
+        assert_eq!(fs::read_to_string(backup_path(&path)).unwrap(), broken);
+        assert_eq!(backup_path(&path), dir.join("settings.json.bak"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -1,4 +1,6 @@
 pub struct BiomeParameters {
+    /// Shown in the debug overlay
+    pub name: &'static str,
     pub base_height: f32,
     pub frequency: f32,
     pub amplitude: f32,
@@ -8,6 +10,7 @@ pub struct BiomeParameters {
 }
 
 pub const PRAIRIE_PARAMS: BiomeParameters = BiomeParameters {
+    name: "prairie",
     base_height: 10.0,
     frequency: 0.05,
     amplitude: 7.0,
@@ -17,6 +20,7 @@ pub const PRAIRIE_PARAMS: BiomeParameters = BiomeParameters {
 };
 
 pub const MOUNTAIN_PARAMS: BiomeParameters = BiomeParameters {
+    name: "mountain",
     base_height: 15.0,
     frequency: 0.03,
     amplitude: 35.0,
@@ -2,22 +2,28 @@ use cgmath::Vector3;
 
 use crate::render::atlas::MaterialType;
 
-use crate::render::pipelines::terrain::BlockVertex;
+use crate::render::pipelines::terrain::{BlockVertex, MAX_AO, MAX_LIGHT};
 
 use super::chunk::CHUNK_AREA;
 
 
 
+// `position` is chunk-local. Padding blocks (x or z == -1) are never meshed,
+// so their vertices may wrap when packed.
 pub fn quad_vertex(pos: [i8; 3], material_type: MaterialType, texture_corners: [u32; 2], position: [i32; 3], quad_side: Direction) -> BlockVertex {
-    let tc = material_type.get_texture_coordinates(texture_corners, quad_side);
-    BlockVertex {
-        pos: [
-            pos[0] as f32 + position[0] as f32,
-            pos[1] as f32 + position[1] as f32,
-            pos[2] as f32 + position[2] as f32,
+    let tile = material_type.get_texture_tile(quad_side);
+    BlockVertex::new(
+        [
+            (pos[0] as i32 + position[0]) as u32,
+            (pos[1] as i32 + position[1]) as u32,
+            (pos[2] as i32 + position[2]) as u32,
         ],
-        texture_coordinates: [tc[0] as f32, tc[1] as f32],
-    }
+        quad_side.index(),
+        tile,
+        texture_corners,
+        MAX_AO,
+        MAX_LIGHT,
+    )
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -37,6 +43,18 @@ impl Direction {
             Self::LEFT, Self::FRONT, Self::BACK
         ];
     
+    /// Face index packed into `BlockVertex`, matches the order of `Direction::ALL`.
+    pub fn index(self) -> u8 {
+        match self {
+            Direction::TOP => 0,
+            Direction::BOTTOM => 1,
+            Direction::RIGHT => 2,
+            Direction::LEFT => 3,
+            Direction::FRONT => 4,
+            Direction::BACK => 5,
+        }
+    }
+
     pub fn to_vec(self) -> Vector3<i32> {
         match self {
             Direction::TOP => Vector3::new(0, 1, 0),
@@ -48,46 +66,21 @@ impl Direction {
         }
     }
 
-    fn get_vertices(self, material_type: MaterialType, position: [i32; 3]) -> [BlockVertex; 4] {
+    /// Corners of the face on the unit cube and their uv corner, in the order used by `Quad`
+    pub fn corners(self) -> [([i8; 3], [u32; 2]); 4] {
         match self {
-            Direction::TOP => [
-                quad_vertex([0, 1, 0], material_type, [0, 0], position, self),
-                quad_vertex([0, 1, 1], material_type, [0, 1], position, self),
-                quad_vertex([1, 1, 1], material_type, [1, 1], position, self),
-                quad_vertex([1, 1, 0], material_type, [1, 0], position, self),
-            ],
-            Direction::BOTTOM => [
-                quad_vertex([0, 0, 1], material_type, [0, 0], position, self),
-                quad_vertex([0, 0, 0], material_type, [0, 1], position, self),
-                quad_vertex([1, 0, 0], material_type, [1, 1], position, self),
-                quad_vertex([1, 0, 1], material_type, [1, 0], position, self),
-            ],
-            Direction::RIGHT => [
-                quad_vertex([1, 1, 1], material_type, [0, 0], position, self),
-                quad_vertex([1, 0, 1], material_type, [0, 1], position, self),
-                quad_vertex([1, 0, 0], material_type, [1, 1], position, self),
-                quad_vertex([1, 1, 0], material_type, [1, 0], position, self),
-            ],
-            Direction::LEFT => [
-                quad_vertex([0, 1, 0], material_type, [0, 0], position, self),
-                quad_vertex([0, 0, 0], material_type, [0, 1], position, self),
-                quad_vertex([0, 0, 1], material_type, [1, 1], position, self),
-                quad_vertex([0, 1, 1], material_type, [1, 0], position, self),
-            ],
-            Direction::FRONT => [
-                quad_vertex([0, 1, 1], material_type, [0, 0], position, self),
-                quad_vertex([0, 0, 1], material_type, [0, 1], position, self),
-                quad_vertex([1, 0, 1], material_type, [1, 1], position, self),
-                quad_vertex([1, 1, 1], material_type, [1, 0], position, self),
-            ],
-            Direction::BACK => [
-                quad_vertex([1, 1, 0], material_type, [0, 0], position, self),
-                quad_vertex([1, 0, 0], material_type, [0, 1], position, self),
-                quad_vertex([0, 0, 0], material_type, [1, 1], position, self),
-                quad_vertex([0, 1, 0], material_type, [1, 0], position, self),
-            ],
+            Direction::TOP => [([0, 1, 0], [0, 0]), ([0, 1, 1], [0, 1]), ([1, 1, 1], [1, 1]), ([1, 1, 0], [1, 0])],
+            Direction::BOTTOM => [([0, 0, 1], [0, 0]), ([0, 0, 0], [0, 1]), ([1, 0, 0], [1, 1]), ([1, 0, 1], [1, 0])],
+            Direction::RIGHT => [([1, 1, 1], [0, 0]), ([1, 0, 1], [0, 1]), ([1, 0, 0], [1, 1]), ([1, 1, 0], [1, 0])],
+            Direction::LEFT => [([0, 1, 0], [0, 0]), ([0, 0, 0], [0, 1]), ([0, 0, 1], [1, 1]), ([0, 1, 1], [1, 0])],
+            Direction::FRONT => [([0, 1, 1], [0, 0]), ([0, 0, 1], [0, 1]), ([1, 0, 1], [1, 1]), ([1, 1, 1], [1, 0])],
+            Direction::BACK => [([1, 1, 0], [0, 0]), ([1, 0, 0], [0, 1]), ([0, 0, 0], [1, 1]), ([0, 1, 0], [1, 0])],
         }
     }
+
+    fn get_vertices(self, material_type: MaterialType, position: [i32; 3]) -> [BlockVertex; 4] {
+        self.corners().map(|(pos, corner)| quad_vertex(pos, material_type, corner, position, self))
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -117,6 +110,19 @@ impl Quad {
 
     pub fn get_indices(&self, i: u16) -> [u16; 6] {
         let displacement = i * 4;
+        // Partimos el quad por la otra diagonal cuando el AO es anisotrópico,
+        // si no la interpolación deja una costura visible
+        let ao: [u8; 4] = std::array::from_fn(|v| self.vertices[v].ao());
+        if ao[0] + ao[2] < ao[1] + ao[3] {
+            return [
+                1 + displacement,
+                2 + displacement,
+                3 + displacement,
+                3 + displacement,
+                displacement,
+                1 + displacement,
+            ];
+        }
         [
             0 + displacement,
             1 + displacement,
@@ -138,7 +144,7 @@ pub struct Block {
 
 impl Block {
     pub fn new(material_type: MaterialType, position: [i32; 3], chunk_offset: [i32; 3]) -> Self {
-        let quads = Block::generate_quads(material_type, position, chunk_offset);
+        let quads = Block::generate_quads(material_type, position);
 
         Self {
             quads,
@@ -169,19 +175,14 @@ impl Block {
         ]
     }
 
-    fn generate_quads(material_type: MaterialType, position: [i32; 3], chunk_offset: [i32; 3]) -> [Quad; 6] {
-        let world_pos = [
-            position[0] + (chunk_offset[0] * CHUNK_AREA as i32),
-            position[1],
-            position[2] + (chunk_offset[2] * CHUNK_AREA as i32),
-        ];
-
-        let top = Quad::new(material_type, Direction::TOP, world_pos);
-        let bottom = Quad::new(material_type, Direction::BOTTOM, world_pos);
-        let right = Quad::new(material_type, Direction::RIGHT, world_pos);
-        let left = Quad::new(material_type, Direction::LEFT, world_pos);
-        let front = Quad::new(material_type, Direction::FRONT, world_pos);
-        let back = Quad::new(material_type, Direction::BACK, world_pos);
+    // Los vertices usan la posición local, el origen del chunk se pasa al dibujar
+    fn generate_quads(material_type: MaterialType, position: [i32; 3]) -> [Quad; 6] {
+        let top = Quad::new(material_type, Direction::TOP, position);
+        let bottom = Quad::new(material_type, Direction::BOTTOM, position);
+        let right = Quad::new(material_type, Direction::RIGHT, position);
+        let left = Quad::new(material_type, Direction::LEFT, position);
+        let front = Quad::new(material_type, Direction::FRONT, position);
+        let back = Quad::new(material_type, Direction::BACK, position);
 
         [top, bottom, right, left, front, back]
     }
@@ -189,7 +190,7 @@ impl Block {
     pub fn update(&mut self, new_material_type: MaterialType, offset: [i32; 3]) {
         self.chunk_offset = offset;
         self.material_type = new_material_type;
-        self.quads = Block::generate_quads(new_material_type, self.position, offset);
+        self.quads = Block::generate_quads(new_material_type, self.position);
     }
 }
 
@@ -1,14 +1,14 @@
-use std::sync::{Arc, RwLock};
+use std::{collections::{BTreeSet, HashMap}, sync::{Arc, RwLock}};
 
 
 use cgmath::Vector3;
-use tracy_client::span;
+use crate::profiling::span;
 
 
-use crate::render::{atlas::MaterialType, mesh::Mesh, pipelines::terrain::BlockVertex};
+use crate::render::{atlas::MaterialType, mesh::Mesh, pipelines::terrain::{BlockVertex, MAX_AO}};
 
 
-use super::{biomes::BiomeParameters, block::{self, Block}, generator::LAND_LEVEL, noise::NoiseGenerator};
+use super::{biomes::BiomeParameters, block::{self, Block, Direction}, generator::LAND_LEVEL, noise::NoiseGenerator};
 
 
 pub const CHUNK_Y_SIZE: usize = 100;
@@ -109,8 +109,7 @@ impl Chunk {
                     let local_x = x as i32 - 1;
                     let local_z = z as i32 - 1;
                     let world_pos = local_pos_to_world(self.offset, Vector3::new(local_x, y as i32, local_z));
-                    let height_variation = noise_generator.get_height(world_pos.x as f32, world_pos.z as f32, biome.frequency, biome.amplitude);
-                    let new_height = (biome.base_height + height_variation).round() as usize;
+                    let new_height = column_height(noise_generator, biome, world_pos.x, world_pos.z);
 
 
                     //let new_height = y;
@@ -170,6 +169,11 @@ impl Chunk {
                         let visible = self.is_quad_visible(&neighbor_pos);
 
                         if visible {
+                            let mut quad = *quad;
+                            for vertex in quad.vertices.iter_mut() {
+                                let ao = self.vertex_ao(block.get_vec_position(), quad.side, vertex.position());
+                                *vertex = vertex.with_ao(ao);
+                            }
                             block_vertices.extend_from_slice(&quad.vertices);
                             block_indices.extend_from_slice(&quad.get_indices(quad_counter));
                             quad_counter += 1;
@@ -188,6 +192,39 @@ impl Chunk {
 
 
 
+    /// Oclusión ambiental de un vértice a partir de los 3 bloques que lo rodean
+    /// del lado visible de la cara (0 = totalmente ocluido, MAX_AO = libre)
+    fn vertex_ao(&self, block_pos: Vector3<i32>, side: Direction, vertex_pos: [u32; 3]) -> u8 {
+        // hacia qué lado del bloque queda el vértice en cada eje
+        let towards = |axis: usize| if vertex_pos[axis] as i32 > block_pos[axis] { 1 } else { -1 };
+
+        let (a, b) = match side {
+            Direction::TOP | Direction::BOTTOM => (Vector3::new(towards(0), 0, 0), Vector3::new(0, 0, towards(2))),
+            Direction::RIGHT | Direction::LEFT => (Vector3::new(0, towards(1), 0), Vector3::new(0, 0, towards(2))),
+            Direction::FRONT | Direction::BACK => (Vector3::new(towards(0), 0, 0), Vector3::new(0, towards(1), 0)),
+        };
+
+        let base = block_pos + side.to_vec();
+        let side1 = self.is_solid_at(base + a);
+        let side2 = self.is_solid_at(base + b);
+        let corner = self.is_solid_at(base + a + b);
+
+        if side1 && side2 {
+            0
+        } else {
+            MAX_AO - (side1 as u8 + side2 as u8 + corner as u8)
+        }
+    }
+
+    // Posición local (-1..16 en x/z, incluye el padding)
+    fn is_solid_at(&self, pos: Vector3<i32>) -> bool {
+        if !pos_in_chunk_bounds(pos) {
+            return false;
+        }
+        self.get_block(pos.y as usize, (pos.x + 1) as usize, (pos.z + 1) as usize)
+            .is_some_and(|block| block.is_solid())
+    }
+
     fn is_quad_visible(&self, neighbor_pos: &Vector3<i32>) -> bool {
         if pos_in_chunk_bounds(*neighbor_pos) {
             // Convertir coordenadas (-1..16) a índices de array (0..17)
@@ -263,28 +300,49 @@ impl ChunkManager {
 
     // Establece el material de un bloque en una posición mundial
     pub fn set_block_material(&mut self, world_pos: Vector3<i32>, material: MaterialType) -> Option<usize> {
+        let (chunk_offset, _) = world_pos_to_chunk_and_local(world_pos);
+        let index = self.get_chunk_index_by_offset(&chunk_offset)?;
+        self.set_block_in_chunk(index, world_pos, material).then_some(index)
+    }
+
+    /// Sets the loaded blocks among `positions`, looking each chunk up once. Returns how
+    /// many blocks changed and the chunks touched, to remesh each one once.
+    pub fn set_blocks_material(&mut self, positions: impl IntoIterator<Item = Vector3<i32>>, material: MaterialType) -> (usize, BTreeSet<usize>) {
+        let mut indices: HashMap<[i32; 3], Option<usize>> = HashMap::new();
+        let mut changed = 0;
+        let mut touched = BTreeSet::new();
+        for world_pos in positions {
+            let (chunk_offset, _) = world_pos_to_chunk_and_local(world_pos);
+            let index = *indices.entry(chunk_offset).or_insert_with(|| self.get_chunk_index_by_offset(&chunk_offset));
+            if let Some(index) = index.filter(|&index| self.set_block_in_chunk(index, world_pos, material)) {
+                touched.insert(index);
+                changed += 1;
+            }
+        }
+        (changed, touched)
+    }
+
+    /// Sets a block of the chunk at `index`, which has to be the one containing `world_pos`
+    fn set_block_in_chunk(&self, index: usize, world_pos: Vector3<i32>, material: MaterialType) -> bool {
         let (chunk_offset, local_pos) = world_pos_to_chunk_and_local(world_pos);
-        
+
         // Ajustamos para el padding (local_pos es 0..15, necesitamos -1..16)
         let x = local_pos.x + 1;
         let z = local_pos.z + 1;
         let y = local_pos.y;
-        
+
         if !pos_in_chunk_bounds(Vector3::new(x, y, z)) {
-            println!("Position out of bounds: {:?}", world_pos);
-            return None;
-        }
-        
-        if let Some(index) = self.get_chunk_index_by_offset(&chunk_offset) {
-            let mut chunk = self.chunks[index].write().unwrap();
-            let block = chunk.get_block_mut(y as usize, x as usize, z as usize)?;
-            block.update(material, chunk_offset);
-            println!("Block updated at world position: {:?}", world_pos);
-            return Some(index);
-        } else {
-            println!("Chunk not found for world position: {:?}", world_pos);
-            return None;
+            log::trace!("Position out of bounds: {:?}", world_pos);
+            return false;
         }
+
+        let mut chunk = self.chunks[index].write().unwrap();
+        let Some(block) = chunk.get_block_mut(y as usize, x as usize, z as usize) else {
+            return false;
+        };
+        block.update(material, chunk_offset);
+        log::trace!("Block updated at world position: {:?}", world_pos);
+        true
     }
 
     
@@ -296,6 +354,13 @@ impl ChunkManager {
 
 
 
+/// Altura del terreno (y del bloque de pasto) en una columna del mundo
+pub fn column_height(noise_generator: &NoiseGenerator, biome: &BiomeParameters, world_x: f32, world_z: f32) -> usize {
+    let height_variation = noise_generator.get_height(world_x, world_z, biome.frequency, biome.amplitude);
+    (biome.base_height + height_variation).round() as usize
+}
+
+
 pub fn pos_in_chunk_bounds(pos: Vector3<i32>) -> bool {
     // Ahora acepta posiciones desde -1 hasta CHUNK_AREA (0..15 es el área interna, -1 y 16 son padding)
     pos.x >= -1 && pos.y >= 0 && pos.z >= -1 &&
@@ -324,3 +389,25 @@ pub fn local_pos_to_world(offset:[i32;3], local_pos: Vector3<i32>) -> Vector3<f3
         local_pos.z as f32 + (offset[2] as f32 * CHUNK_AREA as f32)
     )
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sets_blocks_across_chunks() {
+        let mut chunks = ChunkManager::new();
+        chunks.add_chunk(Chunk::new([0, 0, 0]));
+        chunks.add_chunk(Chunk::new([1, 0, 0]));
+
+        // x 14..18 cruza al segundo chunk, x 40 y y 200 no están cargados
+        let positions = (14..18).map(|x| Vector3::new(x, 5, 3))
+            .chain([Vector3::new(40, 5, 3), Vector3::new(0, 200, 0)]);
+        let (changed, touched) = chunks.set_blocks_material(positions, MaterialType::ROCK);
+        assert_eq!(changed, 4);
+        assert_eq!(touched.into_iter().collect::<Vec<_>>(), vec![0, 1]);
+        assert_eq!(chunks.get_block_material(Vector3::new(17, 5, 3)), Some(MaterialType::ROCK));
+        assert_eq!(chunks.set_block_material(Vector3::new(-1, 5, 3), MaterialType::ROCK), None);
+    }
+}
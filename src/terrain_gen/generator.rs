@@ -1,19 +1,18 @@
 use std::{collections::VecDeque, sync::{Arc, RwLock}};
 
-use crate::{render::{atlas::Atlas, model::DynamicModel, pipelines::terrain::{create_terrain_pipeline, BlockVertex}, renderer::{Draw, Renderer}}, terrain_gen::biomes::PRAIRIE_PARAMS};
+use crate::{hud::debug::DebugStats, render::{atlas::Atlas, model::DynamicModel, pipelines::terrain::{create_terrain_pipeline, BlockVertex, ChunkLocals, TerrainLocals}, renderer::{Draw, Renderer}}, terrain_gen::biomes::{BiomeParameters, PRAIRIE_PARAMS}};
 use crate::render::pipelines::GlobalsLayouts;
 use crate::terrain_gen::chunk::{Chunk, ChunkManager, CHUNK_AREA, CHUNK_Y_SIZE};
+use crate::terrain_gen::lod::{LodTerrain, Rect};
 
 
 use cgmath::{EuclideanSpace, Point3, Vector3};
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
-use tracy_client::span;
+use crate::profiling::span;
 use wgpu::Queue;
 
 
 pub const LAND_LEVEL: usize = 9;
-pub const CHUNKS_VIEW_SIZE: usize = 16;
-pub const CHUNKS_ARRAY_SIZE: usize = CHUNKS_VIEW_SIZE * CHUNKS_VIEW_SIZE;
 
 
 use super::noise::NoiseGenerator;
@@ -26,44 +25,34 @@ use super::noise::NoiseGenerator;
 pub struct TerrainGen {
     pipeline: wgpu::RenderPipeline,
     atlas: Atlas,
+    layouts: GlobalsLayouts,
     pub chunks: ChunkManager,
-    chunk_indices: Arc<RwLock<[Option<usize>; CHUNKS_ARRAY_SIZE]>>,
+    /// Chunks per side of the full detail grid, twice the render distance
+    view_size: usize,
+    chunk_indices: Arc<RwLock<Vec<Option<usize>>>>,
     free_chunk_indices: Arc<RwLock<VecDeque<usize>>>,
     center_offset: Vector3<i32>,
     chunks_origin: Vector3<i32>,
     pub chunk_models: Vec<Arc<RwLock<DynamicModel<BlockVertex>>>>,
-    noise_gen: NoiseGenerator
+    chunk_locals: Vec<Arc<RwLock<ChunkLocals>>>,
+    lod: LodTerrain,
+    noise_gen: NoiseGenerator,
+    seed: u32,
 
 
 }
 
 
 impl TerrainGen {
-    pub fn new(renderer: &Renderer) -> Self {
+    /// `render_distance` is in chunks, measured from the player to the edge of the full detail area.
+    /// Nothing is generated yet, the grid around `center` is filled by `load_step`.
+    pub fn new(renderer: &Renderer, render_distance: usize, seed: u32, center: Point3<f32>) -> Self {
         let global_layouts = GlobalsLayouts::new(&renderer.device);
         let atlas = Atlas::new(&renderer.device, &renderer.queue, &global_layouts).unwrap();
-        let mut chunk_models = vec![];
-        let mut chunks = ChunkManager::new();
-        let chunk_indices: [Option<usize>; CHUNKS_ARRAY_SIZE] = [None; CHUNKS_ARRAY_SIZE];
-        //let updated_indices = Arc::new(RwLock::new([false; CHUNKS_ARRAY_SIZE]));
-        let mut free_chunk_indices = VecDeque::new();
+        let view_size = render_distance * 2;
+        let chunks_array_size = view_size * view_size;
 
-        let noise_gen = NoiseGenerator::new(10);
-
-
-
-        for x in 0..CHUNKS_ARRAY_SIZE {
-            //println!("initial x from new World: {:?}", x);
-            chunks.add_chunk(Chunk::new([0,0,0]));
-            let mut chunk_model = DynamicModel::new(&renderer.device, (CHUNK_AREA ^ 2) * CHUNK_Y_SIZE * 24);
-
-            //TODO: handle unwraps
-            chunk_model.update(&renderer.queue, &chunks.get_chunk(x).unwrap().read().unwrap().mesh, 0);
-            chunk_models.push(Arc::new(RwLock::new(chunk_model)));
-            free_chunk_indices.push_back(x);
-
-
-        }
+        let noise_gen = NoiseGenerator::new(seed);
 
 
         let shader = renderer.device.create_shader_module(
@@ -79,29 +68,62 @@ impl TerrainGen {
         );
 
 
-        let center_offset = Vector3::new(0, 0, 0);
-        let chunks_origin = center_offset - Vector3::new(CHUNKS_VIEW_SIZE as i32 / 2, 0, CHUNKS_VIEW_SIZE as i32 / 2);
+        let lod = LodTerrain::new(renderer, &global_layouts, view_size);
+
+        let center_offset = Self::world_pos_to_chunk_offset(center.to_vec());
+        let chunks_origin = center_offset - Vector3::new(view_size as i32 / 2, 0, view_size as i32 / 2);
 
 
         let mut world = Self {
             pipeline: world_pipeline,
             atlas,
-            chunks,
-            chunk_models,
+            layouts: global_layouts,
+            chunks: ChunkManager::new(),
+            view_size,
+            chunk_models: vec![],
+            chunk_locals: vec![],
+            lod,
             center_offset,
             chunks_origin,
             //updated_indices,
-            chunk_indices: Arc::new(RwLock::new(chunk_indices)),
-            free_chunk_indices: Arc::new(RwLock::new(free_chunk_indices)),
-            noise_gen
+            chunk_indices: Arc::new(RwLock::new(vec![None; chunks_array_size])),
+            free_chunk_indices: Arc::new(RwLock::new(VecDeque::new())),
+            noise_gen,
+            seed,
         };
+        world.allocate_chunk_slots(renderer, chunks_array_size);
 
 
-        println!("about to load first chunks");
-        world.load_empty_chunks(&renderer.queue);
+        world
+    }
 
 
-        world
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+
+    /// Full detail chunks generated and cells in the grid
+    pub fn loading_progress(&self) -> (usize, usize) {
+        let chunk_indices = self.chunk_indices.read().unwrap();
+        (chunk_indices.iter().flatten().count(), chunk_indices.len())
+    }
+
+
+    /// Generates up to `max_chunks` of the missing chunks, closest to the center first,
+    /// and the LOD rings once they are all there. Returns true when everything is loaded.
+    pub fn load_step(&mut self, queue: &Queue, max_chunks: usize) -> bool {
+        let (loaded, total) = self.loading_progress();
+        if loaded < total {
+            self.load_chunks(queue, max_chunks);
+            return false;
+        }
+
+        if self.lod.area().is_none() && !self.lod.levels.is_empty() {
+            let center = chunk_origin(self.center_offset.into());
+            self.update_lod(queue, [center[0], center[2]]);
+        }
+        true
     }
 
 
@@ -115,7 +137,7 @@ impl TerrainGen {
         let _span = span!("update_world"); // <- Marca el inicio del bloque
 
         let new_center_offset = Self::world_pos_to_chunk_offset(player_position.to_vec());
-        let new_chunk_origin = new_center_offset - Vector3::new(CHUNKS_VIEW_SIZE as i32 / 2, 0, CHUNKS_VIEW_SIZE as i32 / 2);
+        let new_chunk_origin = new_center_offset - Vector3::new(self.view_size as i32 / 2, 0, self.view_size as i32 / 2);
 
         if new_chunk_origin == self.chunks_origin {
             return;
@@ -125,10 +147,19 @@ impl TerrainGen {
         self.chunks_origin = new_chunk_origin;
         //println!("chunks origin updated {:?}", self.chunks_origin);
 
+        self.reassign_chunk_indices();
+
+        self.load_empty_chunks(queue);
+        self.update_lod(queue, [player_position.x.floor() as i32, player_position.z.floor() as i32]);
+    }
+
+
+    /// Rebuilds the world grid around `chunks_origin`, chunks that left it go back to the free list
+    fn reassign_chunk_indices(&mut self) {
         let chunk_indices_copy = self.chunk_indices.read().unwrap().clone();
-        self.chunk_indices = Arc::new(RwLock::new([None; CHUNKS_ARRAY_SIZE]));
+        self.chunk_indices = Arc::new(RwLock::new(vec![None; self.view_size * self.view_size]));
 
-        for i in 0..CHUNKS_ARRAY_SIZE {
+        for i in 0..chunk_indices_copy.len() {
             match chunk_indices_copy[i] {
                 Some(chunk_index) => {
                     let chunk_offset = self.chunks.get_chunk(chunk_index).unwrap().read().unwrap().offset.clone();
@@ -142,18 +173,169 @@ impl TerrainGen {
                 None => {}
             }
         }
+    }
 
-        self.load_empty_chunks(queue);
+
+    /// Adds chunk slots (cpu chunk, gpu model and locals) until there are `len` of them
+    fn allocate_chunk_slots(&mut self, renderer: &Renderer, len: usize) {
+        for x in self.chunk_models.len()..len {
+            //println!("initial x from new World: {:?}", x);
+            self.chunks.add_chunk(Chunk::new([0,0,0]));
+            let mut chunk_model = DynamicModel::new(&renderer.device, (CHUNK_AREA ^ 2) * CHUNK_Y_SIZE * 24);
+
+            //TODO: handle unwraps
+            chunk_model.update(&renderer.queue, &self.chunks.get_chunk(x).unwrap().read().unwrap().mesh, 0);
+            self.chunk_models.push(Arc::new(RwLock::new(chunk_model)));
+            self.chunk_locals.push(Arc::new(RwLock::new(ChunkLocals::new(renderer, &self.layouts, TerrainLocals::new([0, 0, 0], 1)))));
+            self.free_chunk_indices.write().unwrap().push_back(x);
+        }
+    }
+
+
+    /// Grows or shrinks the slot pool to the new render distance (in chunks).
+    /// Chunks still in view are kept, LOD rings are rebuilt for the new size.
+    pub fn set_render_distance(&mut self, renderer: &Renderer, render_distance: usize) {
+        let _span = span!("set render distance");
+
+        let view_size = render_distance * 2;
+        if view_size == self.view_size {
+            return;
+        }
+
+        self.view_size = view_size;
+        self.chunks_origin = self.center_offset - Vector3::new(view_size as i32 / 2, 0, view_size as i32 / 2);
+        self.reassign_chunk_indices();
+
+        let len = view_size * view_size;
+        if len > self.chunk_models.len() {
+            self.allocate_chunk_slots(renderer, len);
+        } else {
+            self.release_chunk_slots(len);
+        }
+
+        self.lod = LodTerrain::new(renderer, &self.layouts, view_size);
+        self.load_empty_chunks(&renderer.queue);
+        let center = chunk_origin(self.center_offset.into());
+        self.update_lod(&renderer.queue, [center[0], center[2]]);
+    }
+
+
+    /// Drops slots until there are `len` of them. Chunks living in the slots that are
+    /// removed are moved into free slots below `len` first.
+    fn release_chunk_slots(&mut self, len: usize) {
+        let mut chunk_indices = self.chunk_indices.write().unwrap();
+        let mut used = vec![false; self.chunk_models.len()];
+        for index in chunk_indices.iter().flatten() {
+            used[*index] = true;
+        }
+
+        let mut free = (0..len).filter(|&i| !used[i]).collect::<Vec<_>>().into_iter();
+        for world_index in 0..chunk_indices.len() {
+            let Some(index) = chunk_indices[world_index] else { continue };
+            if index < len {
+                continue;
+            }
+
+            let target = free.next().expect("Error: No free space for chunk");
+            self.chunks.chunks.swap(index, target);
+            self.chunk_models.swap(index, target);
+            self.chunk_locals.swap(index, target);
+            chunk_indices[world_index] = Some(target);
+            used[target] = true;
+        }
+
+        self.chunks.chunks.truncate(len);
+        self.chunk_models.truncate(len);
+        self.chunk_locals.truncate(len);
+        *self.free_chunk_indices.write().unwrap() = (0..len).filter(|&i| !used[i]).collect();
+    }
+
+
+    /// Distance in blocks to the furthest terrain drawn, including LOD rings
+    pub fn view_distance(&self) -> f32 {
+        let area = self.lod.area().unwrap_or(self.detail_area());
+        let half_size = (area.max[0] - area.min[0]) as f32 / 2.0;
+        half_size * std::f32::consts::SQRT_2 + CHUNK_Y_SIZE as f32
+    }
+
+    /// Shortest distance in blocks from the player to the edge of the drawn terrain
+    pub fn horizon(&self) -> f32 {
+        match self.lod.levels.last().zip(self.lod.area()) {
+            // el jugador puede estar hasta un chunk de LOD fuera del centro
+            Some((level, area)) => (area.max[0] - area.min[0]) as f32 / 2.0 - level.chunk_blocks() as f32,
+            None => ((self.view_size / 2 - 1) * CHUNK_AREA) as f32,
+        }
+    }
+
+    pub fn render_distance(&self) -> usize {
+        self.view_size / 2
+    }
+
+
+    /// Area in blocks covered by the full detail chunks
+    pub fn detail_area(&self) -> Rect {
+        let size = self.view_size as i32 * CHUNK_AREA as i32;
+        let min = [self.chunks_origin.x * CHUNK_AREA as i32, self.chunks_origin.z * CHUNK_AREA as i32];
+        Rect {
+            min,
+            max: [min[0] + size, min[1] + size],
+        }
+    }
+
+
+    /// Biome used to generate the column at world x/z, for now the whole world is prairie
+    pub fn biome_at(&self, _x: i32, _z: i32) -> &'static BiomeParameters {
+        &PRAIRIE_PARAMS
+    }
+
+
+    /// Chunk counts, vertices and the biome at `stats.position`
+    pub fn fill_debug_stats(&self, stats: &mut DebugStats) {
+        let chunk_indices = self.chunk_indices.read().unwrap();
+        stats.loaded_chunks = chunk_indices.iter().flatten().count();
+        stats.pending_chunks = chunk_indices.len() - stats.loaded_chunks;
+
+        let drawn = chunk_indices.iter().flatten()
+            .map(|&index| self.chunk_models[index].read().unwrap())
+            .filter(|model| model.num_indices > 0);
+        let (chunks, vertices) = drawn.fold((0, 0), |(chunks, vertices), model| (chunks + 1, vertices + model.num_vertices));
+        let (lod_chunks, lod_vertices) = self.lod.drawn();
+        stats.drawn_chunks = chunks;
+        stats.lod_chunks = lod_chunks;
+        stats.terrain_vertices = vertices + lod_vertices;
+
+        let [x, _, z] = stats.position;
+        stats.biome = self.biome_at(x.floor() as i32, z.floor() as i32).name;
+    }
+
+
+    fn update_lod(&mut self, queue: &Queue, center: [i32; 2]) {
+        let detail_area = self.detail_area();
+        self.lod.update(queue, center, detail_area, &self.noise_gen, &PRAIRIE_PARAMS);
     }
 
 
 
     pub fn load_empty_chunks(&mut self, queue: &Queue) {
+        self.load_chunks(queue, usize::MAX);
+    }
+
+
+    /// Generates up to `max_chunks` empty cells of the grid, the closest to its center first
+    fn load_chunks(&mut self, queue: &Queue, max_chunks: usize) {
         let _span = span!("load empty chunks"); // <- Marca el inicio del bloque
 
-        let chunks_to_update: Vec<usize> = (0..CHUNKS_ARRAY_SIZE)
+        let mut chunks_to_update: Vec<usize> = (0..self.view_size * self.view_size)
             .filter(|&i| self.chunk_indices.read().unwrap()[i].is_none())
             .collect();
+        if chunks_to_update.len() > max_chunks {
+            let center = self.center_offset;
+            chunks_to_update.sort_by_key(|&i| {
+                let offset = self.get_chunk_offset(i) - center;
+                offset.x * offset.x + offset.z * offset.z
+            });
+            chunks_to_update.truncate(max_chunks);
+        }
 
         chunks_to_update.into_par_iter().for_each(|i| {
             let _inner_span = span!(" ldc: thread_work"); // Span por hilo
@@ -181,6 +363,8 @@ impl TerrainGen {
                 
                 chunk_model.update(queue, &chunk.mesh, 0);
 
+                self.chunk_locals[new_index].write().unwrap().update(queue, TerrainLocals::new(chunk_origin(chunk.offset), 1));
+
 
             } else {
                 panic!("Error: No free space for chunk")
@@ -204,13 +388,13 @@ impl TerrainGen {
 
     // world array index -> chunk offset
     fn get_chunk_offset(&self, i: usize) -> Vector3<i32> {
-        return self.chunks_origin + Vector3::new(i as i32 % CHUNKS_VIEW_SIZE as i32, 0, i as i32 / CHUNKS_VIEW_SIZE as i32);
+        return self.chunks_origin + Vector3::new(i as i32 % self.view_size as i32, 0, i as i32 / self.view_size as i32);
     }
 
 
     fn chunk_in_bounds(&self, chunk_offset: Vector3<i32>) -> bool {
         let p = chunk_offset - self.chunks_origin;
-        if p.x >= 0 && p.z >= 0 && p.x < CHUNKS_VIEW_SIZE as i32 && p.z < CHUNKS_VIEW_SIZE as i32 {
+        if p.x >= 0 && p.z >= 0 && p.x < self.view_size as i32 && p.z < self.view_size as i32 {
             return true;
         }
         return false;
@@ -227,13 +411,18 @@ impl TerrainGen {
     
     fn get_chunk_world_index(&self, chunk_offset: Vector3<i32>) -> usize {
         let p = chunk_offset - self.chunks_origin;
-        (p.z as usize * CHUNKS_VIEW_SIZE) + p.x as usize
+        (p.z as usize * self.view_size) + p.x as usize
     }
 
 
 
 
 
+}
+
+/// World position (in blocks) of the corner of a chunk, used as the origin of its vertices
+pub fn chunk_origin(offset: [i32; 3]) -> [i32; 3] {
+    [offset[0] * CHUNK_AREA as i32, 0, offset[2] * CHUNK_AREA as i32]
 }
 
 impl Draw for TerrainGen {
@@ -245,8 +434,10 @@ impl Draw for TerrainGen {
         render_pass.set_bind_group(0, &self.atlas.bind_group, &[]);
         render_pass.set_bind_group(1, globals, &[]);
         
-        for chunk_model in &self.chunk_models {
+        for (chunk_model, chunk_locals) in self.chunk_models.iter().zip(&self.chunk_locals) {
             let chunk_model = chunk_model.read().unwrap();
+            let chunk_locals = chunk_locals.read().unwrap();
+            render_pass.set_bind_group(2, &chunk_locals.bind_group, &[]);
         
             let vertex_buffer = chunk_model.vbuf().slice(..);
             let index_buffer = chunk_model.ibuf().slice(..);
@@ -256,6 +447,8 @@ impl Draw for TerrainGen {
             render_pass.set_index_buffer(index_buffer, wgpu::IndexFormat::Uint16);
             render_pass.draw_indexed(0..num_indices as u32, 0, 0..1 as _);
         }
+
+        self.lod.draw(render_pass);
         
         Ok(())
     }
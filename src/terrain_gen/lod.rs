@@ -0,0 +1,435 @@
+use rayon::iter::{IntoParallelRefMutIterator, ParallelIterator};
+use crate::profiling::span;
+use wgpu::Queue;
+
+use crate::render::{atlas::MaterialType, mesh::Mesh, model::DynamicModel, pipelines::{terrain::{BlockVertex, ChunkLocals, TerrainLocals, MAX_AO, MAX_LIGHT}, GlobalsLayouts}, renderer::Renderer};
+
+use super::{biomes::BiomeParameters, block::Direction, chunk::{column_height, CHUNK_AREA}, generator::LAND_LEVEL, noise::NoiseGenerator};
+
+
+/// Number of LOD rings around the full detail chunks, ring `n` uses cells of 2^n blocks
+pub const LOD_LEVELS: u32 = 3;
+/// How far below the surface the skirts around holes and outer edges go, hides cracks
+/// between levels
+const LOD_SKIRT_DEPTH: usize = 16;
+/// Max quads of a LOD chunk: one top and up to four sides per cell
+const LOD_MAX_QUADS: usize = CHUNK_AREA * CHUNK_AREA * 5;
+
+
+/// Area in blocks `[min, max)` on the x/z plane
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Rect {
+    pub min: [i32; 2],
+    pub max: [i32; 2],
+}
+
+impl Rect {
+    pub fn contains(&self, x: i32, z: i32) -> bool {
+        x >= self.min[0] && z >= self.min[1] && x < self.max[0] && z < self.max[1]
+    }
+
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        let min = [self.min[0].max(other.min[0]), self.min[1].max(other.min[1])];
+        let max = [self.max[0].min(other.max[0]), self.max[1].min(other.max[1])];
+        if min[0] < max[0] && min[1] < max[1] {
+            Some(Rect { min, max })
+        } else {
+            None
+        }
+    }
+}
+
+
+/// Sides of a LOD chunk on the outer edge of its level, where the coarser level starts
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+struct Edges {
+    min_x: bool,
+    max_x: bool,
+    min_z: bool,
+    max_z: bool,
+}
+
+impl Edges {
+    /// Edges of the chunk at `offset` in a grid of `grid_size` chunks starting at `origin`
+    fn of(offset: [i32; 2], origin: [i32; 2], grid_size: usize) -> Self {
+        let last = grid_size as i32 - 1;
+        Self {
+            min_x: offset[0] == origin[0],
+            max_x: offset[0] == origin[0] + last,
+            min_z: offset[1] == origin[1],
+            max_z: offset[1] == origin[1] + last,
+        }
+    }
+
+    /// The cell `(i, j)` of the chunk, one outside it at most, belongs to the coarser level
+    fn outside(&self, i: i32, j: i32) -> bool {
+        let cells = CHUNK_AREA as i32;
+        (self.min_x && i < 0) || (self.max_x && i >= cells) || (self.min_z && j < 0) || (self.max_z && j >= cells)
+    }
+}
+
+
+/// LOD chunk offset of the corner of a grid of `grid_size` chunks of `chunk_blocks`
+/// blocks centered on the block `center`
+fn grid_origin(center: [i32; 2], chunk_blocks: i32, grid_size: usize) -> [i32; 2] {
+    let half = grid_size as i32 / 2;
+    [center[0].div_euclid(chunk_blocks) - half, center[1].div_euclid(chunk_blocks) - half]
+}
+
+/// Blocks covered by the grid starting at `origin`
+fn grid_area(origin: [i32; 2], chunk_blocks: i32, grid_size: usize) -> Rect {
+    let end = [origin[0] + grid_size as i32, origin[1] + grid_size as i32];
+    Rect {
+        min: [origin[0] * chunk_blocks, origin[1] * chunk_blocks],
+        max: [end[0] * chunk_blocks, end[1] * chunk_blocks],
+    }
+}
+
+
+/// A reduced resolution heightmap mesh covering `CHUNK_AREA` x `CHUNK_AREA` cells
+struct LodSlot {
+    /// LOD chunk offset (in units of `CHUNK_AREA * scale` blocks) loaded in this slot
+    offset: Option<[i32; 2]>,
+    /// Part of the inner level that was cut out of the mesh
+    hole: Option<Rect>,
+    /// Sides with skirts against the coarser level
+    edges: Edges,
+    dirty: bool,
+    model: DynamicModel<BlockVertex>,
+    locals: ChunkLocals,
+}
+
+
+pub struct LodLevel {
+    /// Blocks per cell
+    scale: i32,
+    /// LOD chunks per side
+    grid_size: usize,
+    /// LOD chunk offset of the corner of the grid
+    origin: Option<[i32; 2]>,
+    slots: Vec<LodSlot>,
+}
+
+impl LodLevel {
+    pub fn new(renderer: &Renderer, layouts: &GlobalsLayouts, level: u32, grid_size: usize) -> Self {
+        let scale = 1 << level;
+        let slots = (0..grid_size * grid_size).map(|_| LodSlot {
+            offset: None,
+            hole: None,
+            edges: Edges::default(),
+            dirty: false,
+            model: DynamicModel::new(&renderer.device, LOD_MAX_QUADS * 6),
+            locals: ChunkLocals::new(renderer, layouts, TerrainLocals::new([0, 0, 0], scale)),
+        }).collect();
+
+        Self {
+            scale,
+            grid_size,
+            origin: None,
+            slots,
+        }
+    }
+
+    /// Size in blocks of a LOD chunk of this level
+    pub fn chunk_blocks(&self) -> i32 {
+        CHUNK_AREA as i32 * self.scale
+    }
+
+    /// Area covered by this level, `None` until the first update
+    pub fn area(&self) -> Option<Rect> {
+        self.origin.map(|origin| grid_area(origin, self.chunk_blocks(), self.grid_size))
+    }
+
+    fn chunk_rect(&self, offset: [i32; 2]) -> Rect {
+        let size = self.chunk_blocks();
+        Rect {
+            min: [offset[0] * size, offset[1] * size],
+            max: [(offset[0] + 1) * size, (offset[1] + 1) * size],
+        }
+    }
+
+    /// Recenters the level around `center` (world x/z in blocks) and cuts out `hole`,
+    /// the area already covered by the finer level. Returns the number of remeshed chunks.
+    pub fn update(&mut self, queue: &Queue, center: [i32; 2], hole: Rect, noise_gen: &NoiseGenerator, biome: &BiomeParameters) -> usize {
+        let _span = span!("update lod level");
+
+        let size = self.chunk_blocks();
+        let origin = grid_origin(center, size, self.grid_size);
+        let in_grid = |offset: [i32; 2]| {
+            let p = [offset[0] - origin[0], offset[1] - origin[1]];
+            p[0] >= 0 && p[1] >= 0 && p[0] < self.grid_size as i32 && p[1] < self.grid_size as i32
+        };
+
+        let mut loaded = vec![false; self.grid_size * self.grid_size];
+        for i in 0..self.slots.len() {
+            let Some(offset) = self.slots[i].offset else { continue };
+            if !in_grid(offset) {
+                self.slots[i].offset = None;
+                continue;
+            }
+            loaded[(offset[1] - origin[1]) as usize * self.grid_size + (offset[0] - origin[0]) as usize] = true;
+
+            // the hole or the edge of the level moved across this chunk, the cut has to be redone
+            let new_hole = self.chunk_rect(offset).intersection(&hole);
+            let edges = Edges::of(offset, origin, self.grid_size);
+            if new_hole != self.slots[i].hole || edges != self.slots[i].edges {
+                self.slots[i].hole = new_hole;
+                self.slots[i].edges = edges;
+                self.slots[i].dirty = true;
+            }
+        }
+
+        let mut free_slots = (0..self.slots.len())
+            .filter(|&i| self.slots[i].offset.is_none())
+            .collect::<Vec<_>>()
+            .into_iter();
+        for (i, _) in loaded.iter().enumerate().filter(|(_, loaded)| !**loaded) {
+            let offset = [origin[0] + (i % self.grid_size) as i32, origin[1] + (i / self.grid_size) as i32];
+            let slot = free_slots.next().expect("Error: No free space for lod chunk");
+            let chunk_hole = self.chunk_rect(offset).intersection(&hole);
+
+            let slot = &mut self.slots[slot];
+            slot.offset = Some(offset);
+            slot.hole = chunk_hole;
+            slot.edges = Edges::of(offset, origin, self.grid_size);
+            slot.dirty = true;
+        }
+        self.origin = Some(origin);
+
+        let scale = self.scale;
+        self.slots.par_iter_mut().filter(|slot| slot.dirty).map(|slot| {
+            let _inner_span = span!("mesh lod chunk");
+
+            let offset = slot.offset.unwrap();
+            let chunk_origin = [offset[0] * size, 0, offset[1] * size];
+            let mesh = lod_mesh(chunk_origin, scale, slot.hole, slot.edges, noise_gen, biome);
+            slot.model.update(queue, &mesh, 0);
+            slot.locals.update(queue, TerrainLocals::new(chunk_origin, scale));
+            slot.dirty = false;
+            1
+        }).sum()
+    }
+
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        for slot in self.slots.iter().filter(|slot| slot.offset.is_some()) {
+            render_pass.set_bind_group(2, &slot.locals.bind_group, &[]);
+            render_pass.set_vertex_buffer(0, slot.model.vbuf().slice(..));
+            render_pass.set_index_buffer(slot.model.ibuf().slice(..), wgpu::IndexFormat::Uint16);
+            render_pass.draw_indexed(0..slot.model.num_indices as u32, 0, 0..1);
+        }
+    }
+}
+
+
+/// Rings of LOD chunks around the full detail area, each one twice as coarse as the previous
+pub struct LodTerrain {
+    pub levels: Vec<LodLevel>,
+}
+
+impl LodTerrain {
+    pub fn new(renderer: &Renderer, layouts: &GlobalsLayouts, grid_size: usize) -> Self {
+        let levels = (1..=LOD_LEVELS)
+            .map(|level| LodLevel::new(renderer, layouts, level, grid_size))
+            .collect();
+
+        Self { levels }
+    }
+
+    /// `detail_area` is the area covered by full detail chunks
+    pub fn update(&mut self, queue: &Queue, center: [i32; 2], detail_area: Rect, noise_gen: &NoiseGenerator, biome: &BiomeParameters) {
+        let _span = span!("update lod terrain");
+
+        let mut hole = detail_area;
+        for level in self.levels.iter_mut() {
+            level.update(queue, center, hole, noise_gen, biome);
+            hole = level.area().unwrap();
+        }
+    }
+
+    /// LOD chunks with something to draw and their vertices
+    pub fn drawn(&self) -> (usize, usize) {
+        self.levels.iter()
+            .flat_map(|level| &level.slots)
+            .filter(|slot| slot.offset.is_some() && slot.model.num_indices > 0)
+            .fold((0, 0), |(chunks, vertices), slot| (chunks + 1, vertices + slot.model.num_vertices))
+    }
+
+    /// Area covered by the coarsest level
+    pub fn area(&self) -> Option<Rect> {
+        self.levels.last().and_then(|level| level.area())
+    }
+
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        for level in &self.levels {
+            level.draw(render_pass);
+        }
+    }
+}
+
+
+/// Surface of a LOD cell: height of the top face and its material
+fn lod_cell(noise_gen: &NoiseGenerator, biome: &BiomeParameters, world_x: i32, world_z: i32, scale: i32) -> (usize, MaterialType) {
+    // muestreamos el centro de la celda
+    let half = scale / 2;
+    let height = column_height(noise_gen, biome, (world_x + half) as f32, (world_z + half) as f32);
+    if height < LAND_LEVEL {
+        (LAND_LEVEL + 1, MaterialType::WATER)
+    } else {
+        (height + 1, MaterialType::GRASS)
+    }
+}
+
+
+/// Builds the heightmap mesh of a LOD chunk. Vertex x/z are in cells, the shader scales them.
+/// Cells inside `hole` are skipped and their neighbours get skirts so no gaps show
+/// against the finer terrain drawn there. The cells along `edges` get skirts too,
+/// against the coarser level around.
+fn lod_mesh(chunk_origin: [i32; 3], scale: i32, hole: Option<Rect>, edges: Edges, noise_gen: &NoiseGenerator, biome: &BiomeParameters) -> Mesh<BlockVertex> {
+    let cells = CHUNK_AREA as i32;
+    let side = CHUNK_AREA + 2;
+    let cell_x = |i: i32| chunk_origin[0] + i * scale;
+    let cell_z = |j: i32| chunk_origin[2] + j * scale;
+    let in_hole = |i: i32, j: i32| hole.is_some_and(|hole| hole.contains(cell_x(i), cell_z(j)));
+
+    // alturas con un borde de una celda para conocer a los vecinos
+    let mut surface = Vec::with_capacity(side * side);
+    for j in -1..=cells {
+        for i in -1..=cells {
+            surface.push(lod_cell(noise_gen, biome, cell_x(i), cell_z(j), scale));
+        }
+    }
+    let surface_at = |i: i32, j: i32| surface[(j + 1) as usize * side + (i + 1) as usize];
+
+    let mut mesh = Mesh::new();
+    for j in 0..cells {
+        for i in 0..cells {
+            if in_hole(i, j) {
+                continue;
+            }
+
+            let (top, material) = surface_at(i, j);
+            push_lod_quad(&mut mesh, Direction::TOP, [i, j], 0, top, material);
+
+            for direction in [Direction::RIGHT, Direction::LEFT, Direction::FRONT, Direction::BACK] {
+                let n = direction.to_vec();
+                let (ni, nj) = (i + n.x, j + n.z);
+                let bottom = if in_hole(ni, nj) {
+                    top.saturating_sub(LOD_SKIRT_DEPTH)
+                } else if edges.outside(ni, nj) {
+                    // la altura del nivel grueso no se conoce aquí, la falda cubre hasta su vecino
+                    top.saturating_sub(LOD_SKIRT_DEPTH).min(surface_at(ni, nj).0)
+                } else {
+                    surface_at(ni, nj).0
+                };
+
+                if bottom < top {
+                    push_lod_quad(&mut mesh, direction, [i, j], bottom, top, MaterialType::DIRT);
+                }
+            }
+        }
+    }
+
+    mesh
+}
+
+
+/// Face of the column `cell` between `bottom` and `top`
+fn push_lod_quad(mesh: &mut Mesh<BlockVertex>, direction: Direction, cell: [i32; 2], bottom: usize, top: usize, material: MaterialType) {
+    let base_index = mesh.verts.len() as u16;
+    let tile = material.get_texture_tile(direction);
+
+    for (pos, corner) in direction.corners() {
+        let y = if pos[1] == 1 { top } else { bottom };
+        mesh.push(BlockVertex::new(
+            [(cell[0] + pos[0] as i32) as u32, y as u32, (cell[1] + pos[2] as i32) as u32],
+            direction.index(),
+            tile,
+            corner,
+            MAX_AO,
+            MAX_LIGHT,
+        ));
+    }
+
+    mesh.push_indices(&[base_index, base_index + 1, base_index + 2, base_index + 2, base_index + 3, base_index]);
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::terrain_gen::biomes::PRAIRIE_PARAMS;
+
+    use super::*;
+
+    const CELLS: i32 = CHUNK_AREA as i32;
+
+    #[test]
+    fn rect_contains_and_intersects() {
+        let rect = Rect { min: [-16, 0], max: [16, 32] };
+        assert!(rect.contains(-16, 0));
+        assert!(rect.contains(15, 31));
+        assert!(!rect.contains(16, 0));
+        assert!(!rect.contains(0, -1));
+
+        let other = Rect { min: [0, 16], max: [48, 48] };
+        assert_eq!(rect.intersection(&other), Some(Rect { min: [0, 16], max: [16, 32] }));
+        assert_eq!(other.intersection(&rect), rect.intersection(&other));
+        // los que solo se tocan no se cortan
+        assert_eq!(rect.intersection(&Rect { min: [16, 0], max: [32, 32] }), None);
+        assert_eq!(rect.intersection(&rect), Some(rect));
+    }
+
+    #[test]
+    fn grid_follows_the_center() {
+        // nivel 1: chunks de 32 bloques, 8 por lado
+        assert_eq!(grid_origin([0, 0], 32, 8), [-4, -4]);
+        assert_eq!(grid_origin([31, -1], 32, 8), [-4, -5]);
+        assert_eq!(grid_origin([32, 95], 32, 8), [-3, -2]);
+        assert_eq!(grid_area([-4, -4], 32, 8), Rect { min: [-128, -128], max: [128, 128] });
+
+        // el nivel siguiente, el doble de grueso, cubre por completo al anterior
+        let center = [100, -70];
+        let fine = grid_area(grid_origin(center, 32, 8), 32, 8);
+        let coarse = grid_area(grid_origin(center, 64, 8), 64, 8);
+        assert_eq!(coarse.intersection(&fine), Some(fine));
+    }
+
+    #[test]
+    fn edges_of_the_grid() {
+        let origin = [-4, -4];
+        assert_eq!(Edges::of([-4, -4], origin, 8), Edges { min_x: true, min_z: true, ..Default::default() });
+        assert_eq!(Edges::of([3, 0], origin, 8), Edges { max_x: true, ..Default::default() });
+        assert_eq!(Edges::of([0, 0], origin, 8), Edges::default());
+
+        let edges = Edges { max_x: true, ..Default::default() };
+        assert!(edges.outside(CELLS, 3));
+        assert!(!edges.outside(-1, 3));
+        assert!(!edges.outside(CELLS - 1, CELLS));
+    }
+
+    /// Side quads of the mesh on the `direction` border of the chunk
+    fn border_quads(mesh: &Mesh<BlockVertex>, direction: Direction, x: u32) -> usize {
+        mesh.vertices().iter().filter(|v| v.face() == direction.index() && v.position()[0] == x).count() / 4
+    }
+
+    #[test]
+    fn skirts_around_holes_and_outer_edges() {
+        let noise = NoiseGenerator::new(7);
+        let mesh = |hole, edges| lod_mesh([0, 0, 0], 2, hole, edges, &noise, &PRAIRIE_PARAMS);
+
+        let full = mesh(None, Edges::default());
+        assert_eq!(full.vertices().iter().filter(|v| v.face() == Direction::TOP.index()).count(), (CELLS * CELLS * 4) as usize);
+
+        // cada celda del borde exterior tiene falda hacia el nivel grueso
+        let edged = mesh(None, Edges { max_x: true, ..Default::default() });
+        assert_eq!(border_quads(&edged, Direction::RIGHT, CELLS as u32), CELLS as usize);
+        assert!(border_quads(&full, Direction::RIGHT, CELLS as u32) <= CELLS as usize);
+
+        // la mitad izquierda es del nivel fino: sin techo y con falda en el borde del hueco
+        let hole = Rect { min: [-64, -64], max: [CELLS, 64] };
+        let cut = mesh(Some(hole), Edges::default());
+        assert_eq!(cut.vertices().iter().filter(|v| v.face() == Direction::TOP.index()).count(), (CELLS * CELLS * 2) as usize);
+        assert_eq!(border_quads(&cut, Direction::LEFT, (CELLS / 2) as u32), CELLS as usize);
+
+        assert!(mesh(Some(Rect { min: [0, 0], max: [CELLS * 2, CELLS * 2] }), Edges::default()).vertices().is_empty());
+    }
+}
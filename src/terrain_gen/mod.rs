@@ -5,5 +5,6 @@ pub mod block;
 pub mod chunk;
 pub mod biomes;
 pub mod noise;
+pub mod lod;
 
 
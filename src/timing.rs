@@ -0,0 +1,126 @@
+use std::time::{Duration, Instant};
+
+
+/// Simulation ticks per second: physics, mining and the time of day move in steps of
+/// this size whatever the frame rate
+pub const TICK_RATE: u32 = 60;
+/// Ticks run in one frame at most. After a long hitch the time left over is dropped,
+/// otherwise catching up would make the next frame even slower.
+const MAX_TICKS_PER_FRAME: u32 = 8;
+
+
+/// Splits the time between frames in fixed ticks, keeping what is left for the next frame
+pub struct FixedTimestep {
+    step: Duration,
+    accumulator: Duration,
+}
+
+impl FixedTimestep {
+    pub fn new(rate: u32) -> Self {
+        Self {
+            step: Duration::from_secs(1) / rate,
+            accumulator: Duration::ZERO,
+        }
+    }
+
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    /// Adds the time of a frame, returns how many ticks to run
+    pub fn advance(&mut self, elapsed: Duration) -> u32 {
+        self.accumulator += elapsed;
+        let ticks = (self.accumulator.as_nanos() / self.step.as_nanos()) as u32;
+        if ticks > MAX_TICKS_PER_FRAME {
+            self.accumulator = Duration::ZERO;
+            return MAX_TICKS_PER_FRAME;
+        }
+        self.accumulator -= self.step * ticks;
+        ticks
+    }
+
+    /// Fraction of a tick since the last one ran, to draw between the last two states
+    pub fn alpha(&self) -> f32 {
+        self.accumulator.as_secs_f32() / self.step.as_secs_f32()
+    }
+}
+
+
+/// Spaces frames to the `max_fps` setting. The event loop sleeps until `next_frame`
+/// instead of spinning.
+pub struct FrameLimiter {
+    /// `None` draws as fast as possible
+    frame_time: Option<Duration>,
+    next_frame: Instant,
+}
+
+impl FrameLimiter {
+    pub fn new(frame_time: Option<Duration>) -> Self {
+        Self { frame_time, next_frame: Instant::now() }
+    }
+
+    /// When the next frame can start
+    pub fn next_frame(&self) -> Instant {
+        self.next_frame
+    }
+
+    pub fn ready(&self, now: Instant) -> bool {
+        self.frame_time.is_none() || now >= self.next_frame
+    }
+
+    /// Schedules the frame after the one starting at `now`. The schedule keeps its own
+    /// rhythm, but a late frame doesn't make the next ones come early to catch up.
+    pub fn frame_started(&mut self, now: Instant) {
+        let Some(frame_time) = self.frame_time else {
+            self.next_frame = now;
+            return;
+        };
+        self.next_frame = (self.next_frame + frame_time).max(now);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ticks_keep_the_remainder() {
+        let mut timestep = FixedTimestep::new(20);
+        assert_eq!(timestep.step(), Duration::from_millis(50));
+        assert_eq!(timestep.advance(Duration::from_millis(30)), 0);
+        assert!((timestep.alpha() - 0.6).abs() < 1e-4);
+        assert_eq!(timestep.advance(Duration::from_millis(30)), 1);
+        assert!((timestep.alpha() - 0.2).abs() < 1e-4);
+        assert_eq!(timestep.advance(Duration::from_millis(140)), 3);
+        assert!(timestep.alpha() < 1e-4);
+    }
+
+    #[test]
+    fn hitches_drop_time() {
+        let mut timestep = FixedTimestep::new(60);
+        assert_eq!(timestep.advance(Duration::from_secs(2)), MAX_TICKS_PER_FRAME);
+        assert_eq!(timestep.alpha(), 0.0);
+        assert_eq!(timestep.advance(Duration::ZERO), 0);
+    }
+
+    #[test]
+    fn frames_are_spaced() {
+        let frame = Duration::from_millis(10);
+        let mut limiter = FrameLimiter::new(Some(frame));
+        let start = Instant::now();
+        assert!(limiter.ready(start));
+        limiter.frame_started(start);
+        assert!(!limiter.ready(start + frame / 2));
+        assert!(limiter.ready(start + frame));
+
+        // un frame tarde no adelanta los siguientes
+        let late = start + frame * 5;
+        limiter.frame_started(late);
+        assert_eq!(limiter.next_frame(), late);
+        limiter.frame_started(late);
+        assert_eq!(limiter.next_frame(), late + frame);
+
+        assert!(FrameLimiter::new(None).ready(start));
+    }
+}
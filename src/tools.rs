@@ -0,0 +1,192 @@
+use std::{collections::BTreeMap, path::Path, time::Instant};
+
+use anyhow::Context;
+use rayon::prelude::*;
+
+use crate::{
+    cli::{Cli, Tool},
+    render::atlas::MaterialType,
+    terrain_gen::{biomes::PRAIRIE_PARAMS, chunk::{Chunk, CHUNK_AREA, CHUNK_Y_SIZE}, noise::NoiseGenerator},
+    world::{self, WorldInfo},
+};
+
+
+/// Runs a tool of the command line and returns once it's done
+pub fn run(tool: &Tool, cli: &Cli) -> anyhow::Result<()> {
+    match tool {
+        Tool::Generate { center, radius, heightmap } => {
+            // con --world se genera el terreno de ese mundo
+            let seed = match &cli.world {
+                Some(dir) => WorldInfo::load(dir)?.seed,
+                None => cli.seed(),
+            };
+            let start = Instant::now();
+            let region = Region::generate(seed, *center, *radius);
+            println!("Generated {} chunks in {:.2?} (seed {})", region.chunks.len(), start.elapsed(), seed);
+            region.print_stats();
+            if let Some(path) = heightmap {
+                region.save_heightmap(path)?;
+                println!("Heightmap written to {}", path.display());
+            }
+            Ok(())
+        }
+        Tool::Info { dir } => {
+            let dir = dir.as_ref().or(cli.world.as_ref()).context("Give the folder of the world, or --world")?;
+            print_world_info(&WorldInfo::load(dir)?);
+            Ok(())
+        }
+    }
+}
+
+
+/// Square of chunks generated and meshed like the game does, without the GPU
+struct Region {
+    /// Chunk at the corner with the smallest offset
+    origin: [i32; 2],
+    /// Chunks a side
+    size: usize,
+    /// Row by row, x grows first
+    chunks: Vec<Chunk>,
+}
+
+impl Region {
+    /// Chunks up to `radius` from the one containing the `center` column
+    fn generate(seed: u32, center: [i32; 2], radius: u32) -> Self {
+        let noise = NoiseGenerator::new(seed);
+        let size = radius as usize * 2 + 1;
+        let origin = center.map(|c| c.div_euclid(CHUNK_AREA as i32) - radius as i32);
+
+        let chunks = (0..size * size).into_par_iter().map(|i| {
+            let offset = [origin[0] + (i % size) as i32, 0, origin[1] + (i / size) as i32];
+            let mut chunk = Chunk::new(offset);
+            chunk.update_blocks(offset, &noise, &PRAIRIE_PARAMS);
+            chunk.update_mesh(PRAIRIE_PARAMS);
+            chunk
+        }).collect();
+
+        Self { origin, size, chunks }
+    }
+
+    /// Height of the highest block that isn't air of each column, row by row
+    fn heights(&self) -> Vec<usize> {
+        let side = self.size * CHUNK_AREA;
+        let mut heights = vec![0; side * side];
+        for (i, chunk) in self.chunks.iter().enumerate() {
+            let (chunk_x, chunk_z) = (i % self.size, i / self.size);
+            for x in 0..CHUNK_AREA {
+                for z in 0..CHUNK_AREA {
+                    // +1 por el borde de relleno del chunk
+                    let height = (0..CHUNK_Y_SIZE).rev()
+                        .find(|&y| chunk.get_block(y, x + 1, z + 1).is_some_and(|block| block.material_type != MaterialType::AIR))
+                        .unwrap_or(0);
+                    heights[(chunk_z * CHUNK_AREA + z) * side + chunk_x * CHUNK_AREA + x] = height;
+                }
+            }
+        }
+        heights
+    }
+
+    /// Blocks of each material, without the padding the chunks share
+    fn materials(&self) -> BTreeMap<&'static str, usize> {
+        let mut counts = BTreeMap::new();
+        for chunk in &self.chunks {
+            for y in 0..CHUNK_Y_SIZE {
+                for x in 1..=CHUNK_AREA {
+                    for z in 1..=CHUNK_AREA {
+                        let material = chunk.get_block(y, x, z).map_or(MaterialType::AIR, |block| block.material_type);
+                        *counts.entry(material.name()).or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+        counts
+    }
+
+    fn print_stats(&self) {
+        let block_size = CHUNK_AREA as i32;
+        let end = [self.origin[0] + self.size as i32, self.origin[1] + self.size as i32];
+        println!("Blocks x {}..{}, z {}..{}", self.origin[0] * block_size, end[0] * block_size, self.origin[1] * block_size, end[1] * block_size);
+
+        let vertices: usize = self.chunks.iter().map(|chunk| chunk.mesh.vertices().len()).sum();
+        println!("Mesh vertices: {}", vertices);
+
+        let heights = self.heights();
+        let (min, max) = (heights.iter().min().unwrap_or(&0), heights.iter().max().unwrap_or(&0));
+        let mean = heights.iter().sum::<usize>() as f32 / heights.len().max(1) as f32;
+        println!("Surface height: min {}, max {}, mean {:.1}", min, max, mean);
+
+        for (name, count) in self.materials().iter().filter(|(name, _)| **name != "air") {
+            println!("  {:<16}{}", name, count);
+        }
+    }
+
+    /// One pixel per column, brighter the higher
+    fn save_heightmap(&self, path: &Path) -> anyhow::Result<()> {
+        let side = (self.size * CHUNK_AREA) as u32;
+        let heights = self.heights();
+        let image = image::GrayImage::from_fn(side, side, |x, z| {
+            let height = heights[(z * side + x) as usize];
+            image::Luma([(height * 255 / (CHUNK_Y_SIZE - 1)) as u8])
+        });
+        image.save(path).with_context(|| format!("writing {}", path.display()))
+    }
+}
+
+
+fn print_world_info(info: &WorldInfo) {
+    let now = world::unix_time();
+    println!("Name:         {}", info.name);
+    println!("Folder:       {}", info.dir.display());
+    println!("Seed:         {}", info.seed);
+    println!("Created:      {}", ago(now, info.created));
+    println!("Last played:  {}", ago(now, info.last_played));
+    println!("Time of day:  {} / {}", info.time, world::DAY_LENGTH);
+    match info.player_position {
+        Some([x, y, z]) => println!("Player:       {:.1} {:.1} {:.1}", x, y, z),
+        None => println!("Player:       not placed yet"),
+    }
+}
+
+/// "3 days ago", from two Unix times in seconds
+fn ago(now: u64, time: u64) -> String {
+    let seconds = now.saturating_sub(time);
+    let (amount, unit) = match seconds {
+        0..60 => return "just now".to_string(),
+        60..3600 => (seconds / 60, "minute"),
+        3600..86400 => (seconds / 3600, "hour"),
+        _ => (seconds / 86400, "day"),
+    };
+    format!("{} {}{} ago", amount, unit, if amount == 1 { "" } else { "s" })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generates_a_region() {
+        let region = Region::generate(42, [-1, 40], 1);
+        assert_eq!(region.origin, [-2, 1]);
+        assert_eq!(region.chunks.len(), 9);
+        assert_eq!(region.chunks[5].offset, [0, 0, 2]);
+
+        let heights = region.heights();
+        assert_eq!(heights.len(), 48 * 48);
+        assert!(heights.iter().all(|&h| h > 0 && h < CHUNK_Y_SIZE));
+
+        let blocks: usize = region.materials().values().sum();
+        assert_eq!(blocks, 9 * CHUNK_AREA * CHUNK_AREA * CHUNK_Y_SIZE);
+        // el mismo seed da el mismo terreno
+        assert_eq!(Region::generate(42, [-1, 40], 1).heights(), heights);
+    }
+
+    #[test]
+    fn time_ago() {
+        assert_eq!(ago(100, 90), "just now");
+        assert_eq!(ago(200, 80), "2 minutes ago");
+        assert_eq!(ago(3600, 0), "1 hour ago");
+        assert_eq!(ago(86400 * 3, 10), "2 days ago");
+        assert_eq!(ago(0, 10), "just now");
+    }
+}
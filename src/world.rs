@@ -0,0 +1,229 @@
+use std::{fs, path::{Path, PathBuf}, time::{SystemTime, UNIX_EPOCH}};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+
+/// Folder with one subfolder per saved world
+pub const WORLDS_DIR: &str = "worlds";
+/// File inside the folder of a world with its `WorldInfo`
+const WORLD_FILE: &str = "world.json";
+/// Characters of a world name, the folder name is cut to the same length
+pub const MAX_WORLD_NAME: usize = 32;
+/// Ticks in a whole day. 0 is sunrise, a quarter of the day is noon and three quarters midnight.
+pub const DAY_LENGTH: u32 = 24000;
+/// Ticks the time of day moves per second of play
+pub const TICKS_PER_SECOND: f32 = 20.0;
+/// Light left in the darkest hour of the night
+const NIGHT_LIGHT: f32 = 0.2;
+
+
+/// What is saved of a world: the terrain comes back from the seed, and the player
+/// comes back where it left
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WorldInfo {
+    pub name: String,
+    pub seed: u32,
+    /// Seconds since the Unix epoch
+    pub created: u64,
+    pub last_played: u64,
+    /// Feet of the player when the world was saved, `None` spawns on the surface
+    #[serde(default)]
+    pub player_position: Option<[f32; 3]>,
+    /// Time of day in ticks, see `DAY_LENGTH`
+    #[serde(default)]
+    pub time: u32,
+    /// Folder the world is saved in, not written to the file
+    #[serde(skip)]
+    pub dir: PathBuf,
+}
+
+impl WorldInfo {
+    /// World that hasn't been played yet, saved in `dir`. Call `save` to write it.
+    pub fn new(dir: PathBuf, name: &str, seed: u32) -> Self {
+        let name: String = name.trim().chars().take(MAX_WORLD_NAME).collect();
+        let now = unix_time();
+        Self {
+            name: if name.is_empty() { "New world".to_string() } else { name },
+            seed,
+            created: now,
+            last_played: now,
+            player_position: None,
+            time: 0,
+            dir,
+        }
+    }
+
+    pub fn load(dir: &Path) -> anyhow::Result<Self> {
+        let path = dir.join(WORLD_FILE);
+        let contents = fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+        let mut info: WorldInfo = serde_json::from_str(&contents).with_context(|| format!("parsing {}", path.display()))?;
+        info.dir = dir.to_path_buf();
+        Ok(info)
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        fs::create_dir_all(&self.dir).with_context(|| format!("creating {}", self.dir.display()))?;
+        let path = self.dir.join(WORLD_FILE);
+        fs::write(&path, serde_json::to_string_pretty(self)?).with_context(|| format!("writing {}", path.display()))
+    }
+
+    /// Row of the world list
+    pub fn summary(&self) -> String {
+        format!("{} (seed {})", self.name, self.seed)
+    }
+}
+
+
+/// Worlds saved in `root`, last played first. Folders that can't be read are skipped.
+pub fn list_worlds(root: &Path) -> Vec<WorldInfo> {
+    let Ok(entries) = fs::read_dir(root) else {
+        return Vec::new();
+    };
+
+    let mut worlds: Vec<WorldInfo> = entries
+        .flatten()
+        .filter(|entry| is_world(&entry.path()))
+        .filter_map(|entry| WorldInfo::load(&entry.path())
+            .inspect_err(|e| log::warn!("Skipping world {}: {:#}", entry.path().display(), e))
+            .ok())
+        .collect();
+    worlds.sort_by(|a, b| b.last_played.cmp(&a.last_played).then_with(|| a.name.cmp(&b.name)));
+    worlds
+}
+
+/// Whether `dir` has a saved world
+pub fn is_world(dir: &Path) -> bool {
+    dir.join(WORLD_FILE).is_file()
+}
+
+/// Saves a new world in a folder of `root` named after it
+pub fn create_world(root: &Path, name: &str, seed: u32) -> anyhow::Result<WorldInfo> {
+    let mut info = WorldInfo::new(PathBuf::new(), name, seed);
+    info.dir = free_dir(root, &folder_name(&info.name));
+    info.save()?;
+    Ok(info)
+}
+
+pub fn delete_world(info: &WorldInfo) -> anyhow::Result<()> {
+    fs::remove_dir_all(&info.dir).with_context(|| format!("deleting {}", info.dir.display()))
+}
+
+/// Seed typed by the player: numbers are used as they are, any other text is hashed
+/// and an empty one picks a random seed
+pub fn parse_seed(text: &str) -> u32 {
+    let text = text.trim();
+    if text.is_empty() {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos();
+        return (nanos ^ (nanos >> 32)) as u32;
+    }
+    if let Ok(number) = text.parse::<i64>() {
+        return number as u32;
+    }
+    // el mismo hash que String.hashCode de Java, siempre da lo mismo para el mismo texto
+    text.chars().fold(0u32, |hash, c| hash.wrapping_mul(31).wrapping_add(c as u32))
+}
+
+/// Brightness of the sky and the terrain at `time` ticks, from `NIGHT_LIGHT` to 1
+pub fn daylight(time: f32) -> f32 {
+    let sun = (time / DAY_LENGTH as f32 * std::f32::consts::TAU).sin();
+    // la luz cambia sobre todo alrededor del amanecer y el atardecer
+    let t = (sun * 2.0 + 0.5).clamp(0.0, 1.0);
+    NIGHT_LIGHT + (1.0 - NIGHT_LIGHT) * t
+}
+
+pub fn unix_time() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs())
+}
+
+/// Name of the folder: lowercase letters, digits and `_`
+fn folder_name(name: &str) -> String {
+    let folder: String = name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    if folder.trim_matches('_').is_empty() { "world".to_string() } else { folder }
+}
+
+/// `root/folder`, or `root/folder-2`, `-3`... if it's taken
+fn free_dir(root: &Path, folder: &str) -> PathBuf {
+    let mut dir = root.join(folder);
+    let mut n = 2;
+    while dir.exists() {
+        dir = root.join(format!("{}-{}", folder, n));
+        n += 1;
+    }
+    dir
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Empty folder for one test, removed when dropped
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("wgpucraft-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn seeds_from_text() {
+        assert_eq!(parse_seed("42"), 42);
+        assert_eq!(parse_seed(" -1 "), u32::MAX);
+        assert_eq!(parse_seed("abc"), 96354);
+        assert_eq!(parse_seed("hello"), parse_seed("hello"));
+        assert_ne!(parse_seed("hello"), parse_seed("world"));
+    }
+
+    #[test]
+    fn folder_names_are_safe() {
+        assert_eq!(folder_name("My World!"), "my_world_");
+        assert_eq!(folder_name("../.."), "world");
+        assert_eq!(folder_name("Mundo ñ"), "mundo__");
+    }
+
+    #[test]
+    fn create_list_and_delete() {
+        let root = TempDir::new("worlds");
+        let first = create_world(&root.0, "Test", 1).unwrap();
+        let mut second = create_world(&root.0, "Test", 2).unwrap();
+        assert_ne!(first.dir, second.dir);
+        assert!(second.dir.ends_with("test-2"));
+
+        second.last_played = first.last_played + 10;
+        second.player_position = Some([1.0, 2.0, 3.0]);
+        second.save().unwrap();
+
+        let worlds = list_worlds(&root.0);
+        assert_eq!(worlds, vec![second.clone(), first.clone()]);
+
+        delete_world(&second).unwrap();
+        assert_eq!(list_worlds(&root.0), vec![first]);
+    }
+
+    #[test]
+    fn days_and_nights() {
+        let day = DAY_LENGTH as f32;
+        assert_eq!(daylight(day / 4.0), 1.0);
+        assert_eq!(daylight(day * 3.0 / 4.0), NIGHT_LIGHT);
+        assert!(daylight(0.0) > NIGHT_LIGHT && daylight(0.0) < 1.0);
+        assert_eq!(daylight(day / 4.0 + day), 1.0);
+    }
+
+    #[test]
+    fn missing_root_has_no_worlds() {
+        assert!(list_worlds(Path::new("/nonexistent/wgpucraft/worlds")).is_empty());
+    }
+}